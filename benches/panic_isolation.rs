@@ -0,0 +1,60 @@
+//! Compares the cost of starting and shutting down many subsystems
+//! with and without panic isolation, on a `current_thread` runtime.
+//!
+//! Run with `cargo bench --bench panic_isolation`.
+
+use std::time::{Duration, Instant};
+
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+
+const SUBSYSTEMS: usize = 10_000;
+const ITERATIONS: u32 = 10;
+
+async fn idle(subsys: SubsystemHandle) -> Result<(), std::convert::Infallible> {
+    subsys.on_shutdown_requested().await;
+    Ok(())
+}
+
+async fn run(panic_isolation: bool) -> Duration {
+    let start = Instant::now();
+
+    Toplevel::new(move |s| async move {
+        for i in 0..SUBSYSTEMS {
+            let builder = SubsystemBuilder::new(format!("idle{i}"), idle);
+            if panic_isolation {
+                s.start(builder);
+            } else {
+                s.start(builder.no_panic_isolation());
+            }
+        }
+        s.request_shutdown();
+    })
+    .handle_shutdown_requests(Duration::from_secs(10))
+    .await
+    .unwrap();
+
+    start.elapsed()
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    for (label, panic_isolation) in [
+        ("with panic isolation", true),
+        ("without panic isolation", false),
+    ] {
+        // Warm up
+        runtime.block_on(run(panic_isolation));
+
+        let total: Duration = (0..ITERATIONS)
+            .map(|_| runtime.block_on(run(panic_isolation)))
+            .sum();
+        println!(
+            "{label}: {:?} per {SUBSYSTEMS} subsystems",
+            total / ITERATIONS
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Compares the cost of starting many subsystems one by one
+//! with starting them in a single batch, on a `multi_thread` runtime.
+//!
+//! Run with `cargo bench --bench start_batch`.
+
+use std::time::{Duration, Instant};
+
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+
+const SUBSYSTEMS: usize = 10_000;
+const ITERATIONS: u32 = 10;
+
+async fn idle(subsys: SubsystemHandle) -> Result<(), std::convert::Infallible> {
+    subsys.on_shutdown_requested().await;
+    Ok(())
+}
+
+async fn run(batched: bool) -> Duration {
+    let (elapsed_sender, elapsed_receiver) = tokio::sync::oneshot::channel();
+
+    Toplevel::new(move |s| async move {
+        let builders = (0..SUBSYSTEMS).map(|i| SubsystemBuilder::new(format!("idle{i}"), idle));
+
+        let start = Instant::now();
+        if batched {
+            s.start_batch(builders);
+        } else {
+            for builder in builders {
+                s.start(builder);
+            }
+        }
+        elapsed_sender.send(start.elapsed()).unwrap();
+
+        s.request_shutdown();
+    })
+    .handle_shutdown_requests(Duration::from_secs(10))
+    .await
+    .unwrap();
+
+    elapsed_receiver.await.unwrap()
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    for (label, batched) in [("individual starts", false), ("batched start", true)] {
+        // Warm up
+        runtime.block_on(run(batched));
+
+        let total: Duration = (0..ITERATIONS)
+            .map(|_| runtime.block_on(run(batched)))
+            .sum();
+        println!(
+            "{label}: {:?} per {SUBSYSTEMS} subsystems",
+            total / ITERATIONS
+        );
+    }
+}
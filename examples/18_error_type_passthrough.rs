@@ -99,7 +99,11 @@ async fn main() -> Result<(), miette::Report> {
     if let Err(e) = &errors {
         match e {
             GracefulShutdownError::SubsystemsFailed(_) => {
-                tracing::warn!("Subsystems failed.")
+                tracing::warn!(
+                    "Subsystems failed: {} failures, {} panics.",
+                    e.failure_count(),
+                    e.panic_count()
+                )
             }
             GracefulShutdownError::ShutdownTimeout(_) => {
                 tracing::warn!("Shutdown timed out.")
@@ -122,6 +126,9 @@ async fn main() -> Result<(), miette::Report> {
                 SubsystemError::Panicked(name) => {
                     tracing::warn!("   Subsystem '{}' panicked.", name)
                 }
+                SubsystemError::Cancelled(name) => {
+                    tracing::warn!("   Subsystem '{}' got cancelled.", name)
+                }
             }
         }
     };
@@ -86,12 +86,11 @@ async fn root(subsys: SubsystemHandle) -> Result<()> {
     tracing::info!("Root started.");
 
     tracing::info!("Starting nested subsystems ...");
-    let nested1 = subsys.start(SubsystemBuilder::new("Nested1", nested1));
-    let nested1_finished = nested1.finished();
-    let nested2 = subsys.start(SubsystemBuilder::new("Nested2", |s| {
+    let (_, nested1_finished) =
+        subsys.start_and_finished(SubsystemBuilder::new("Nested1", nested1));
+    let (_, nested2_finished) = subsys.start_and_finished(SubsystemBuilder::new("Nested2", |s| {
         nested2(s, nested1_finished)
     }));
-    let nested2_finished = nested2.finished();
     subsys.start(SubsystemBuilder::new("Nested3", |s| {
         nested3(s, nested2_finished)
     }));
@@ -35,12 +35,9 @@ async fn parent(subsys: SubsystemHandle) -> Result<()> {
     tracing::info!("Parent started.");
 
     tracing::info!("Starting detached nested subsystems ...");
-    let nested1 =
-        subsys.start(SubsystemBuilder::new("Nested1", |s| child("Nested1", s)).detached());
-    let nested2 =
-        subsys.start(SubsystemBuilder::new("Nested2", |s| child("Nested2", s)).detached());
-    let nested3 =
-        subsys.start(SubsystemBuilder::new("Nested3", |s| child("Nested3", s)).detached());
+    let nested1 = subsys.start_detached("Nested1", |s| child("Nested1", s));
+    let nested2 = subsys.start_detached("Nested2", |s| child("Nested2", s));
+    let nested3 = subsys.start_detached("Nested3", |s| child("Nested3", s));
     tracing::info!("Nested subsystems started.");
 
     // Wait for the shutdown to happen
@@ -13,12 +13,13 @@ use miette::{Context, IntoDiagnostic, Result};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::time::Duration;
 use tokio_graceful_shutdown::errors::CancelledByShutdown;
-use tokio_graceful_shutdown::{FutureExt, SubsystemBuilder, SubsystemHandle, Toplevel};
+use tokio_graceful_shutdown::{
+    ConnectionSet, FutureExt, SubsystemBuilder, SubsystemHandle, Toplevel,
+};
 
 use std::net::SocketAddr;
 
 use tokio::net::{TcpListener, TcpStream};
-use tokio_util::task::TaskTracker;
 
 async fn echo_connection(tcp: &mut TcpStream) -> Result<()> {
     tcp.write_all(b"Hello!\r\n").await.into_diagnostic()?;
@@ -49,7 +50,7 @@ async fn echo_connection_shutdown(tcp: &mut TcpStream) -> Result<()> {
 async fn connection_handler(
     subsys: SubsystemHandle,
     listener: TcpListener,
-    connection_tracker: TaskTracker,
+    connections: ConnectionSet,
 ) -> Result<()> {
     loop {
         let connection = match listener.accept().cancel_on_shutdown(&subsys).await {
@@ -60,9 +61,9 @@ async fn connection_handler(
             .into_diagnostic()
             .context("Error while waiting for connection")?;
 
-        // Spawn handler on connection tracker to give the parent subsystem
+        // Spawn handler in the connection set to give the parent subsystem
         // the chance to wait for the shutdown to finish
-        connection_tracker.spawn({
+        connections.spawn({
             let cancellation_token = subsys.create_cancellation_token();
             async move {
                 tracing::info!("Connected to {} ...", addr);
@@ -97,21 +98,20 @@ async fn echo_subsystem(subsys: SubsystemHandle) -> Result<()> {
         .context("Unable to start tcp server")?;
     tracing::info!("Listening on {}", addr);
 
-    // Use a tasktracker instead of spawning a subsystem for every connection,
+    // Use a connection set instead of spawning a subsystem for every connection,
     // as this would result in a lot of overhead.
-    let connection_tracker = TaskTracker::new();
+    let connections = subsys.connection_set(Duration::from_secs(3));
 
     let listener = subsys.start(SubsystemBuilder::new("Echo Listener", {
-        let connection_tracker = connection_tracker.clone();
-        move |subsys| connection_handler(subsys, listener, connection_tracker)
+        let connections = connections.clone();
+        move |subsys| connection_handler(subsys, listener, connections)
     }));
 
-    // Make sure no more tasks can be spawned before we close the tracker
+    // Make sure no more connections can be spawned before we drain the set
     listener.join().await?;
 
-    // Wait for connections to close
-    connection_tracker.close();
-    connection_tracker.wait().await;
+    // Wait for connections to close, and cancel the ones that take too long
+    connections.drain().await;
 
     Ok(())
 }
@@ -0,0 +1,62 @@
+//! This example demonstrates how to gracefully shutdown an axum
+//! server using this crate.
+//!
+//! It requires the `axum` feature, which provides
+//! [`SubsystemHandle::axum_shutdown_signal`].
+//!
+//! Run it with `cargo run --example axum --features axum`.
+
+use miette::{Context, IntoDiagnostic, Result};
+use tokio::time::Duration;
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+
+use std::net::SocketAddr;
+
+use axum::{routing::get, Router};
+use tokio::net::TcpListener;
+
+async fn hello() -> &'static str {
+    "Hello World!"
+}
+
+async fn axum_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    let addr: SocketAddr = ([127, 0, 0, 1], 12345).into();
+
+    // Bind to the port and listen for incoming TCP connections
+    let listener = TcpListener::bind(addr)
+        .await
+        .into_diagnostic()
+        .context("Unable to start tcp server")?;
+    tracing::info!("Listening on http://{}", addr);
+
+    let app = Router::new().route("/", get(hello));
+
+    // axum stops accepting new connections once the signal resolves
+    // and waits for all open connections to close.
+    axum::serve(listener, app)
+        .with_graceful_shutdown(subsys.axum_shutdown_signal())
+        .await
+        .into_diagnostic()
+        .context("Error while serving")?;
+
+    tracing::info!("All connections closed.");
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Init logging
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .init();
+
+    // Setup and execute subsystem tree
+    Toplevel::new(|s| async move {
+        s.start(SubsystemBuilder::new("Axum", axum_subsystem));
+    })
+    .catch_signals()
+    .handle_shutdown_requests(Duration::from_secs(5))
+    .await
+    .map_err(Into::into)
+}
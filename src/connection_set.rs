@@ -0,0 +1,180 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{runtime::Handle, sync::watch, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+struct Inner {
+    // The number of connections that are still running
+    active: watch::Sender<usize>,
+    // Set once the set stops accepting new connections
+    closed: AtomicBool,
+    // Cancels the connections that outlive the grace period
+    stragglers: CancellationToken,
+    shutdown: CancellationToken,
+    shutdown_deadline: Arc<watch::Sender<Option<Instant>>>,
+    grace: Duration,
+    runtime: Option<Handle>,
+}
+
+/// A set of connection tasks that belong to a subsystem.
+///
+/// Created through [`SubsystemHandle::connection_set()`](crate::SubsystemHandle::connection_set).
+///
+/// Encapsulates the usual shutdown of a server: once a shutdown is requested, the set
+/// stops accepting new connections. [`drain()`](ConnectionSet::drain) then waits for the
+/// in-flight connections to finish, and cancels the ones that are still running
+/// after the grace period.
+///
+/// Spawning a subsystem for every connection would have a lot of overhead;
+/// the connections of a set are plain tokio tasks instead.
+///
+/// # Examples
+///
+/// ```
+/// use miette::{IntoDiagnostic, Result};
+/// use tokio::{net::TcpListener, time::Duration};
+/// use tokio_graceful_shutdown::{errors::CancelledByShutdown, FutureExt, SubsystemHandle};
+///
+/// async fn server(subsys: SubsystemHandle, listener: TcpListener) -> Result<()> {
+///     let connections = subsys.connection_set(Duration::from_secs(10));
+///
+///     loop {
+///         let (tcp, addr) = match listener.accept().cancel_on_shutdown(&subsys).await {
+///             Ok(connection) => connection.into_diagnostic()?,
+///             Err(CancelledByShutdown) => break,
+///         };
+///
+///         connections.spawn(async move {
+///             tracing::info!("Serving {addr} ...");
+///             drop(tcp);
+///         });
+///     }
+///
+///     connections.drain().await;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ConnectionSet {
+    inner: Arc<Inner>,
+}
+
+impl ConnectionSet {
+    pub(crate) fn new(
+        shutdown: CancellationToken,
+        shutdown_deadline: Arc<watch::Sender<Option<Instant>>>,
+        grace: Duration,
+        runtime: Option<Handle>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                active: watch::channel(0).0,
+                closed: AtomicBool::new(false),
+                stragglers: CancellationToken::new(),
+                shutdown,
+                shutdown_deadline,
+                grace,
+                runtime,
+            }),
+        }
+    }
+
+    /// Spawns a connection task.
+    ///
+    /// Once a shutdown is requested or the set got drained, no new connections
+    /// are accepted any more; the connection gets dropped instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The future that serves the connection.
+    ///
+    /// # Returns
+    ///
+    /// Whether the connection got accepted.
+    #[track_caller]
+    pub fn spawn<Fut>(&self, connection: Fut) -> bool
+    where
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        // Count first, so a concurrent `drain()` can't miss the connection
+        self.inner.active.send_modify(|active| *active += 1);
+        let guard = ConnectionGuard(Arc::clone(&self.inner));
+
+        if self.inner.closed.load(Ordering::Acquire) || self.inner.shutdown.is_cancelled() {
+            return false;
+        }
+
+        let stragglers = self.inner.stragglers.clone();
+        crate::tokio_task::spawn(
+            async move {
+                let _guard = guard;
+                tokio::select! {
+                    _ = connection => (),
+                    _ = stragglers.cancelled() => (),
+                }
+            },
+            "connection",
+            self.inner.runtime.as_ref(),
+        );
+
+        true
+    }
+
+    /// Returns the number of connections that are still running.
+    pub fn len(&self) -> usize {
+        *self.inner.active.borrow()
+    }
+
+    /// Returns `true` if no connections are running.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stops accepting new connections and waits for the running ones to finish.
+    ///
+    /// Connections that are still running after the grace period get cancelled.
+    /// The grace period never extends past the shutdown timeout of the
+    /// [`Toplevel`](crate::Toplevel), so the remaining connections get cancelled
+    /// right before the subsystem would get cancelled itself.
+    pub async fn drain(&self) {
+        self.inner.closed.store(true, Ordering::Release);
+
+        let grace = match *self.inner.shutdown_deadline.borrow() {
+            Some(deadline) => self
+                .inner
+                .grace
+                .min(deadline.saturating_duration_since(Instant::now())),
+            None => self.inner.grace,
+        };
+
+        let mut active = self.inner.active.subscribe();
+        let finished = tokio::time::timeout(grace, active.wait_for(|active| *active == 0))
+            .await
+            .is_ok();
+        if !finished {
+            tracing::warn!(
+                remaining = self.len(),
+                "Connections did not finish within the grace period, cancelling them ..."
+            );
+            self.inner.stragglers.cancel();
+            // Can't fail; the sender lives as long as the set
+            let _ = active.wait_for(|active| *active == 0).await;
+        }
+    }
+}
+
+/// Removes a connection from the count once it is finished.
+struct ConnectionGuard(Arc<Inner>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.active.send_modify(|active| *active -= 1);
+    }
+}
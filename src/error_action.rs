@@ -1,4 +1,6 @@
-use bytemuck::NoUninit;
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+use tokio::time::Instant;
 
 /// Possible ways a subsystem can react to errors.
 ///
@@ -13,8 +15,7 @@ use bytemuck::NoUninit;
 /// - [`NestedSubsystem::change_failure_action`](crate::NestedSubsystem::change_failure_action)
 /// - [`NestedSubsystem::change_panic_action`](crate::NestedSubsystem::change_panic_action)
 ///
-#[derive(Clone, Copy, Debug, Eq, PartialEq, NoUninit)]
-#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ErrorAction {
     /// Pass the error on to the parent subsystem, but don't react to it.
     Forward,
@@ -23,6 +24,43 @@ pub enum ErrorAction {
     /// then initiate a shutdown of the subsystem and its children.
     /// Do not forward the error to the parent subsystem.
     CatchAndLocalShutdown,
+    /// Behave like [`CatchAndLocalShutdown`](ErrorAction::CatchAndLocalShutdown),
+    /// unless the subsystem already failed `count` times within the
+    /// given `window`, including the current failure; then behave like
+    /// [`Forward`](ErrorAction::Forward).
+    ///
+    /// Useful for flaky subsystems that are restarted on failure,
+    /// but should escalate if they fail too often.
+    CatchUntilThreshold {
+        /// The number of failures at which errors get forwarded.
+        count: usize,
+        /// The time window in which failures are counted.
+        window: Duration,
+    },
+}
+
+/// Remembers when errors happened, to evaluate [`ErrorAction::CatchUntilThreshold`].
+#[derive(Default)]
+pub(crate) struct ErrorHistory {
+    timestamps: Mutex<VecDeque<Instant>>,
+}
+
+impl ErrorHistory {
+    /// Records an error and returns whether `count` errors happened within `window`.
+    pub(crate) fn record(&self, count: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        let mut timestamps = self.timestamps.lock().unwrap();
+
+        while timestamps
+            .front()
+            .is_some_and(|&timestamp| now.duration_since(timestamp) >= window)
+        {
+            timestamps.pop_front();
+        }
+        timestamps.push_back(now);
+
+        timestamps.len() >= count
+    }
 }
 
 #[cfg(test)]
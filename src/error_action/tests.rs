@@ -10,3 +10,17 @@ fn derives() {
     assert_ne!(a, b.clone());
     assert_ne!(format!("{:?}", a), format!("{:?}", b));
 }
+
+#[test]
+fn error_history_counts_within_window() {
+    let history = ErrorHistory::default();
+    let window = Duration::from_millis(100);
+
+    assert!(!history.record(3, window));
+    assert!(!history.record(3, window));
+    assert!(history.record(3, window));
+
+    std::thread::sleep(Duration::from_millis(150));
+
+    assert!(!history.record(3, window));
+}
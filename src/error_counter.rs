@@ -0,0 +1,21 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// A read-only counter of the errors that reached the toplevel.
+///
+/// Created through [`Toplevel::total_errors_seen()`](crate::Toplevel::total_errors_seen).
+#[derive(Clone, Debug)]
+pub struct ErrorCounter(Arc<AtomicU64>);
+
+impl ErrorCounter {
+    pub(crate) fn new(counter: Arc<AtomicU64>) -> Self {
+        Self(counter)
+    }
+
+    /// Returns the number of errors counted so far.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use tokio_stream::Stream;
+
+use crate::{errors::SubsystemError, BoxedError, ErrTypeTraits};
+
+struct QueueState<ErrType: ErrTypeTraits> {
+    errors: VecDeque<SubsystemError<ErrType>>,
+    capacity: Option<usize>,
+    closed: bool,
+    // One waker per waiting stream, identified by the id of the stream
+    wakers: Vec<(u64, Waker)>,
+}
+
+/// The queue of errors that reached the root of the subsystem tree.
+pub(crate) struct ErrorQueue<ErrType: ErrTypeTraits> {
+    state: Mutex<QueueState<ErrType>>,
+    streamed_any: AtomicBool,
+    next_stream_id: AtomicU64,
+}
+
+impl<ErrType: ErrTypeTraits> ErrorQueue<ErrType> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                errors: VecDeque::new(),
+                capacity: None,
+                closed: false,
+                wakers: Vec::new(),
+            }),
+            streamed_any: AtomicBool::new(false),
+            next_stream_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Limits the number of errors the queue holds; the oldest errors get dropped first.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = Some(capacity);
+        while state.errors.len() > capacity {
+            drop_oldest(&mut state.errors);
+        }
+    }
+
+    pub(crate) fn push(&self, error: SubsystemError<ErrType>) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.closed {
+            tracing::warn!(?error, "An error got dropped.");
+            return;
+        }
+
+        if state
+            .capacity
+            .is_some_and(|capacity| state.errors.len() >= capacity)
+        {
+            drop_oldest(&mut state.errors);
+        }
+        state.errors.push_back(error);
+
+        // All waiting streams get woken up; the first one to poll receives the error
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        wakers.into_iter().for_each(|(_, waker)| waker.wake());
+    }
+
+    /// Closes the queue and returns all errors that were not consumed by an [`ErrorStream`] yet.
+    pub(crate) fn collect_remaining(&self) -> Box<[SubsystemError<ErrType>]> {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+
+        let wakers = std::mem::take(&mut state.wakers);
+        let errors = std::mem::take(&mut state.errors);
+        drop(state);
+        wakers.into_iter().for_each(|(_, waker)| waker.wake());
+
+        Vec::from(errors).into_boxed_slice()
+    }
+
+    /// Whether an [`ErrorStream`] consumed at least one error.
+    pub(crate) fn streamed_any(&self) -> bool {
+        self.streamed_any.load(Ordering::Acquire)
+    }
+
+    fn next_stream_id(&self) -> u64 {
+        self.next_stream_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn poll_recv(
+        &self,
+        stream_id: u64,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<SubsystemError<ErrType>>> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(error) = state.errors.pop_front() {
+            self.streamed_any.store(true, Ordering::Release);
+            Poll::Ready(Some(error))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            let waker = cx.waker().clone();
+            match state.wakers.iter_mut().find(|(id, _)| *id == stream_id) {
+                Some((_, existing)) => *existing = waker,
+                None => state.wakers.push((stream_id, waker)),
+            }
+            Poll::Pending
+        }
+    }
+
+    fn remove_waker(&self, stream_id: u64) {
+        self.state
+            .lock()
+            .unwrap()
+            .wakers
+            .retain(|(id, _)| *id != stream_id);
+    }
+}
+
+fn drop_oldest<ErrType: ErrTypeTraits>(errors: &mut VecDeque<SubsystemError<ErrType>>) {
+    if let Some(error) = errors.pop_front() {
+        tracing::warn!(?error, "Error buffer is full, dropping the oldest error.");
+    }
+}
+
+/// Receives the errors of the subsystem tree live, while it is running.
+///
+/// Created through [`Toplevel::error_stream()`](crate::Toplevel::error_stream).
+///
+/// Every error gets delivered only once; errors that were received through
+/// this stream are not contained in the result of
+/// [`handle_shutdown_requests()`](crate::Toplevel::handle_shutdown_requests) any more.
+/// If multiple streams are consumed concurrently, every error
+/// gets delivered to only one of them.
+///
+/// Also implements [`tokio_stream::Stream`].
+pub struct ErrorStream<ErrType: ErrTypeTraits = BoxedError> {
+    queue: Arc<ErrorQueue<ErrType>>,
+    id: u64,
+}
+
+impl<ErrType: ErrTypeTraits> ErrorStream<ErrType> {
+    pub(crate) fn new(queue: Arc<ErrorQueue<ErrType>>) -> Self {
+        let id = queue.next_stream_id();
+        Self { queue, id }
+    }
+
+    /// Receives the next error.
+    ///
+    /// # Returns
+    ///
+    /// The next error, or `None` once the shutdown is finished and
+    /// no errors are left.
+    pub async fn recv(&self) -> Option<SubsystemError<ErrType>> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Polls to receive the next error.
+    ///
+    /// Useful for implementing the `Stream` trait of other crates.
+    ///
+    /// # Returns
+    ///
+    /// - `Poll::Pending` if no error is available yet.
+    /// - `Poll::Ready(Some(error))` if an error was received.
+    /// - `Poll::Ready(None)` once the shutdown is finished and no errors are left.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<SubsystemError<ErrType>>> {
+        self.queue.poll_recv(self.id, cx)
+    }
+}
+
+impl<ErrType: ErrTypeTraits> Stream for ErrorStream<ErrType> {
+    type Item = SubsystemError<ErrType>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl<ErrType: ErrTypeTraits> Drop for ErrorStream<ErrType> {
+    fn drop(&mut self) {
+        self.queue.remove_waker(self.id);
+    }
+}
@@ -1,12 +1,12 @@
 //! All the errors that can be caused by this crate.
 
-use std::sync::Arc;
+use std::{backtrace::Backtrace, sync::Arc, time::Duration};
 
 use miette::Diagnostic;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
-use crate::ErrTypeTraits;
+use crate::{ErrTypeTraits, Severity};
 
 /// This enum contains all the possible errors that could be returned
 /// by [`handle_shutdown_requests()`](crate::Toplevel::handle_shutdown_requests).
@@ -37,6 +37,102 @@ impl<ErrType: ErrTypeTraits> GracefulShutdownError<ErrType> {
             GracefulShutdownError::ShutdownTimeout(rel) => rel,
         }
     }
+    /// Looks up the error of the subsystem with the given absolute name.
+    ///
+    /// Useful for asserting the outcome of individual subsystems in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The absolute name of the subsystem, like `/subsys/nested`.
+    ///
+    /// # Returns
+    ///
+    /// The first error of the subsystem, or `None` if the subsystem did not fail.
+    pub fn subsystem_error(&self, name: &str) -> Option<&SubsystemError<ErrType>> {
+        self.get_subsystem_errors()
+            .iter()
+            .find(|error| error.name() == name)
+    }
+
+    /// Maps the error to a process exit code.
+    ///
+    /// This allows service managers and tests to distinguish the failure modes
+    /// through the exit status of the process.
+    ///
+    /// The mapping is:
+    /// - [`SubsystemsFailed`](GracefulShutdownError::SubsystemsFailed): `1`
+    /// - [`ShutdownTimeout`](GracefulShutdownError::ShutdownTimeout): `2`
+    /// - [`SubsystemsFailed`](GracefulShutdownError::SubsystemsFailed) with a
+    ///   [`Critical`](Severity::Critical) error: `3`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.request_shutdown();
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let result = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Subsys", my_subsystem));
+    ///     })
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await;
+    ///
+    ///     if let Err(e) = result {
+    ///         std::process::exit(e.exit_code());
+    ///     }
+    /// }
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GracefulShutdownError::SubsystemsFailed(_)
+                if self.max_severity() == Severity::Critical =>
+            {
+                3
+            }
+            GracefulShutdownError::SubsystemsFailed(_) => 1,
+            GracefulShutdownError::ShutdownTimeout(_) => 2,
+        }
+    }
+
+    /// The highest severity of all subsystem errors that occurred.
+    ///
+    /// Returns [`Severity::Error`] if no subsystem errors got reported,
+    /// for example for a [`ShutdownTimeout`](GracefulShutdownError::ShutdownTimeout).
+    pub fn max_severity(&self) -> Severity {
+        self.get_subsystem_errors()
+            .iter()
+            .map(SubsystemError::severity)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The number of subsystems that returned an error.
+    ///
+    /// Counts all [`SubsystemError::Failed`] errors.
+    pub fn failure_count(&self) -> usize {
+        self.get_subsystem_errors()
+            .iter()
+            .filter(|error| matches!(error, SubsystemError::Failed(_, _)))
+            .count()
+    }
+
+    /// The number of subsystems that panicked.
+    ///
+    /// Counts all [`SubsystemError::Panicked`] errors.
+    pub fn panic_count(&self) -> usize {
+        self.get_subsystem_errors()
+            .iter()
+            .filter(|error| matches!(error, SubsystemError::Panicked(_)))
+            .count()
+    }
 }
 
 /// This enum contains all the possible errors that joining a subsystem
@@ -50,7 +146,7 @@ pub enum SubsystemJoinError<ErrType: ErrTypeTraits = crate::BoxedError> {
 }
 
 /// A wrapper type that carries the errors returned by subsystems.
-pub struct SubsystemFailure<ErrType>(pub(crate) ErrType);
+pub struct SubsystemFailure<ErrType>(pub(crate) ErrType, pub(crate) Severity);
 
 impl<ErrType> std::ops::Deref for SubsystemFailure<ErrType> {
     type Target = ErrType;
@@ -68,6 +164,12 @@ impl<ErrType> SubsystemFailure<ErrType> {
     pub fn into_error(self) -> ErrType {
         self.0
     }
+    /// Retrieves the severity of the subsystem that returned the error.
+    ///
+    /// See [`SubsystemBuilder::error_severity()`](crate::SubsystemBuilder::error_severity).
+    pub fn severity(&self) -> Severity {
+        self.1
+    }
 }
 
 impl<ErrType> std::fmt::Debug for SubsystemFailure<ErrType>
@@ -91,6 +193,82 @@ impl<ErrType> std::error::Error for SubsystemFailure<ErrType> where
 {
 }
 
+/// The details of a panicked subsystem, carried by [`SubsystemError::Panicked`].
+///
+/// Dereferences to the name of the subsystem.
+pub struct SubsystemPanic {
+    name: Arc<str>,
+    backtrace: Option<Arc<Backtrace>>,
+    severity: Severity,
+}
+
+impl SubsystemPanic {
+    pub(crate) fn new(
+        name: Arc<str>,
+        backtrace: Option<Arc<Backtrace>>,
+        severity: Severity,
+    ) -> Self {
+        Self {
+            name,
+            backtrace,
+            severity,
+        }
+    }
+    /// Retrieves the name of the subsystem that panicked.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Retrieves the backtrace at the point of the panic.
+    ///
+    /// Backtraces are only captured if the `capture-backtrace` feature is enabled.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+    /// Retrieves the severity that was configured for panics of the subsystem.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+impl std::ops::Deref for SubsystemPanic {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.name
+    }
+}
+impl AsRef<str> for SubsystemPanic {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+impl From<Arc<str>> for SubsystemPanic {
+    fn from(name: Arc<str>) -> Self {
+        Self::new(name, None, Severity::default())
+    }
+}
+impl From<&str> for SubsystemPanic {
+    fn from(name: &str) -> Self {
+        Self::new(Arc::from(name), None, Severity::default())
+    }
+}
+impl From<String> for SubsystemPanic {
+    fn from(name: String) -> Self {
+        Self::new(Arc::from(name), None, Severity::default())
+    }
+}
+
+impl std::fmt::Debug for SubsystemPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.name, f)
+    }
+}
+impl std::fmt::Display for SubsystemPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.name, f)
+    }
+}
+
 /// This enum contains all the possible errors that a subsystem execution
 /// could cause.
 ///
@@ -101,10 +279,20 @@ pub enum SubsystemError<ErrType: ErrTypeTraits = crate::BoxedError> {
     #[diagnostic(code(graceful_shutdown::subsystem::failed))]
     #[error("Error in subsystem '{0}'")]
     Failed(Arc<str>, #[source] SubsystemFailure<ErrType>),
-    /// The subsystem panicked.
+    /// The subsystem panicked. Carries the name and the backtrace of the panic.
     #[diagnostic(code(graceful_shutdown::subsystem::panicked))]
     #[error("Subsystem '{0}' panicked")]
-    Panicked(Arc<str>),
+    Panicked(SubsystemPanic),
+    /// The subsystem was still running when the shutdown timed out, and got cancelled.
+    ///
+    /// Only reported if enabled through
+    /// [`Toplevel::report_cancelled_subsystems()`](crate::Toplevel::report_cancelled_subsystems).
+    /// Also reported for the root subsystem of the [`Toplevel`](crate::Toplevel) if it got
+    /// aborted through [`SubsystemHandle::abort_all()`](crate::SubsystemHandle::abort_all)
+    /// before it returned.
+    #[diagnostic(code(graceful_shutdown::subsystem::cancelled))]
+    #[error("Subsystem '{0}' got cancelled")]
+    Cancelled(Arc<str>),
 }
 
 impl<ErrType: ErrTypeTraits> SubsystemError<ErrType> {
@@ -116,9 +304,49 @@ impl<ErrType: ErrTypeTraits> SubsystemError<ErrType> {
     pub fn name(&self) -> &str {
         match self {
             SubsystemError::Failed(name, _) => name,
-            SubsystemError::Panicked(name) => name,
+            SubsystemError::Panicked(panic) => panic.name(),
+            SubsystemError::Cancelled(name) => name,
+        }
+    }
+
+    /// Retrieves the severity of the error.
+    ///
+    /// Failures and panics have the severity of the subsystem that caused them, see
+    /// [`SubsystemBuilder::error_severity()`](crate::SubsystemBuilder::error_severity).
+    /// Cancellations are [`Error`](Severity::Error)s.
+    pub fn severity(&self) -> Severity {
+        match self {
+            SubsystemError::Failed(_, failure) => failure.severity(),
+            SubsystemError::Panicked(panic) => panic.severity(),
+            SubsystemError::Cancelled(_) => Severity::Error,
         }
     }
+
+    /// Retrieves the backtrace of a panicked subsystem.
+    ///
+    /// Backtraces are only captured if the `capture-backtrace` feature is enabled.
+    /// Capturing installs a process wide panic hook that forwards to
+    /// the previously installed one; replacing the hook afterwards disables the capturing.
+    ///
+    /// # Returns
+    ///
+    /// The backtrace at the point of the panic, or `None` if the error is not
+    /// a [`Panicked`](Self::Panicked) error or no backtrace got captured.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            SubsystemError::Panicked(panic) => panic.backtrace(),
+            _ => None,
+        }
+    }
+
+    /// Creates a [`Panicked`](Self::Panicked) error with the given backtrace and severity.
+    pub(crate) fn panicked(
+        name: &str,
+        backtrace: Option<Arc<Backtrace>>,
+        severity: Severity,
+    ) -> Self {
+        SubsystemError::Panicked(SubsystemPanic::new(Arc::from(name), backtrace, severity))
+    }
 }
 
 /// The error that happens when a task gets cancelled through
@@ -128,6 +356,54 @@ impl<ErrType: ErrTypeTraits> SubsystemError<ErrType> {
 #[diagnostic(code(graceful_shutdown::future::cancelled_by_shutdown))]
 pub struct CancelledByShutdown;
 
+/// The error that happens when a task gets cancelled through
+/// [`cancel_on_shutdown_detailed()`](crate::FutureExt::cancel_on_shutdown_detailed).
+///
+/// Distinguishes whether the cancellation originated from a shutdown
+/// of the entire subsystem tree or only from a local shutdown.
+#[derive(Error, Debug, Diagnostic, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownCancelReason {
+    /// A shutdown of the entire subsystem tree caused this task to be cancelled.
+    #[error("A global shutdown request caused this task to be cancelled")]
+    #[diagnostic(code(graceful_shutdown::future::cancelled_by_global_shutdown))]
+    Global,
+    /// A local shutdown of the current subsystem or one of its parents
+    /// caused this task to be cancelled.
+    #[error("A local shutdown request caused this task to be cancelled")]
+    #[diagnostic(code(graceful_shutdown::future::cancelled_by_local_shutdown))]
+    Local,
+}
+
+/// The error that happens when trying to [`restart()`](crate::NestedSubsystem::restart)
+/// a subsystem that was not started as restartable.
+#[derive(Error, Debug, Diagnostic)]
+#[error("The subsystem is not restartable")]
+#[diagnostic(code(graceful_shutdown::subsystem::not_restartable))]
+pub struct NotRestartable;
+
+/// The error that happens when
+/// [`wait_for_subsystem()`](crate::SubsystemHandle::wait_for_subsystem)
+/// cannot find a running subsystem with the given name.
+#[derive(Error, Debug, Diagnostic)]
+#[error("No running subsystem is called '{name}'")]
+#[diagnostic(code(graceful_shutdown::subsystem::not_found))]
+pub struct SubsystemNotFound {
+    /// The absolute name that was looked up.
+    pub name: Arc<str>,
+}
+
+/// The error that gets reported when a subsystem returns successfully before
+/// its [`min_runtime()`](crate::SubsystemBuilder::min_runtime) elapsed.
+#[derive(Error, Debug, Diagnostic)]
+#[error("The subsystem exited too quickly, after {elapsed:?} instead of at least {min_runtime:?}")]
+#[diagnostic(code(graceful_shutdown::subsystem::exited_too_quickly))]
+pub struct ExitedTooQuickly {
+    /// How long the subsystem ran.
+    pub elapsed: Duration,
+    /// How long the subsystem was supposed to run at least.
+    pub min_runtime: Duration,
+}
+
 // This function contains code that stems from the principle
 // of defensive coding - meaning, handle potential errors
 // gracefully, even if they should not happen.
@@ -137,7 +413,7 @@ pub(crate) fn handle_dropped_error<ErrType: ErrTypeTraits>(
     result: Result<(), mpsc::error::SendError<ErrType>>,
 ) {
     if let Err(mpsc::error::SendError(e)) = result {
-        tracing::warn!("An error got dropped: {e:?}");
+        tracing::warn!(error = ?e, "An error got dropped.");
     }
 }
 
@@ -150,7 +426,7 @@ pub(crate) fn handle_unhandled_stopreason<ErrType: ErrTypeTraits>(
     maybe_stop_reason: Option<SubsystemError<ErrType>>,
 ) {
     if let Some(stop_reason) = maybe_stop_reason {
-        tracing::warn!("Unhandled stop reason: {:?}", stop_reason);
+        tracing::warn!(?stop_reason, "Unhandled stop reason.");
     }
 }
 
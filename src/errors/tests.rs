@@ -31,9 +31,10 @@ fn errors_can_be_converted_to_diagnostic() {
         Arc::new([]),
     ));
     examine_report(SubsystemError::Panicked::<BoxedError>("".into()));
+    examine_report(SubsystemError::Cancelled::<BoxedError>("".into()));
     examine_report(SubsystemError::Failed::<BoxedError>(
         "".into(),
-        SubsystemFailure("".into()),
+        SubsystemFailure("".into(), Severity::Error),
     ));
     examine_report(CancelledByShutdown);
 }
@@ -42,7 +43,10 @@ fn errors_can_be_converted_to_diagnostic() {
 fn extract_related_from_graceful_shutdown_error() {
     let related = || {
         Box::new([
-            SubsystemError::Failed("a".into(), SubsystemFailure(String::from("A").into())),
+            SubsystemError::Failed(
+                "a".into(),
+                SubsystemFailure(String::from("A").into(), Severity::Error),
+            ),
             SubsystemError::Panicked("b".into()),
         ])
     };
@@ -67,10 +71,121 @@ fn extract_related_from_graceful_shutdown_error() {
     matches_related(&GracefulShutdownError::SubsystemsFailed(related()).into_subsystem_errors());
 }
 
+#[test]
+fn lookup_subsystem_error_by_name() {
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([
+        SubsystemError::Failed(
+            "/a".into(),
+            SubsystemFailure(String::from("A").into(), Severity::Error),
+        ),
+        SubsystemError::Panicked("/b".into()),
+    ]));
+
+    assert!(matches!(
+        error.subsystem_error("/a"),
+        Some(SubsystemError::Failed(_, _))
+    ));
+    assert!(matches!(
+        error.subsystem_error("/b"),
+        Some(SubsystemError::Panicked(_))
+    ));
+    assert!(error.subsystem_error("/c").is_none());
+}
+
+#[test]
+fn exit_codes_are_distinct_and_nonzero() {
+    let failed = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([]));
+    let timeout = GracefulShutdownError::<BoxedError>::ShutdownTimeout(Box::new([]));
+
+    assert_eq!(failed.exit_code(), 1);
+    assert_eq!(timeout.exit_code(), 2);
+}
+
+#[test]
+fn max_severity_picks_the_worst_error() {
+    let failure = |name: &str, severity| {
+        SubsystemError::Failed(
+            name.into(),
+            SubsystemFailure(String::from("A").into(), severity),
+        )
+    };
+
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([
+        failure("/a", Severity::Warning),
+        failure("/b", Severity::Error),
+    ]));
+    assert_eq!(error.max_severity(), Severity::Error);
+    assert_eq!(error.exit_code(), 1);
+
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([
+        failure("/a", Severity::Warning),
+        failure("/b", Severity::Critical),
+    ]));
+    assert_eq!(error.max_severity(), Severity::Critical);
+    assert_eq!(error.exit_code(), 3);
+
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([
+        failure("/a", Severity::Warning),
+        SubsystemError::Panicked("/b".into()),
+    ]));
+    assert_eq!(error.max_severity(), Severity::Error);
+    assert_eq!(error.exit_code(), 1);
+
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([
+        failure("/a", Severity::Warning),
+        SubsystemError::panicked("/b", None, Severity::Critical),
+    ]));
+    assert_eq!(error.max_severity(), Severity::Critical);
+    assert_eq!(error.exit_code(), 3);
+
+    let error = GracefulShutdownError::<BoxedError>::ShutdownTimeout(Box::new([]));
+    assert_eq!(error.max_severity(), Severity::Error);
+}
+
+#[test]
+fn count_failures_and_panics() {
+    let error = GracefulShutdownError::<BoxedError>::ShutdownTimeout(Box::new([
+        SubsystemError::Failed(
+            "/a".into(),
+            SubsystemFailure(String::from("A").into(), Severity::Error),
+        ),
+        SubsystemError::Panicked("/b".into()),
+        SubsystemError::Failed(
+            "/c".into(),
+            SubsystemFailure(String::from("C").into(), Severity::Warning),
+        ),
+        SubsystemError::Cancelled("/d".into()),
+    ]));
+    assert_eq!(error.failure_count(), 2);
+    assert_eq!(error.panic_count(), 1);
+
+    let error = GracefulShutdownError::<BoxedError>::SubsystemsFailed(Box::new([]));
+    assert_eq!(error.failure_count(), 0);
+    assert_eq!(error.panic_count(), 0);
+}
+
+#[test]
+fn panicked_errors_keep_their_backtrace() {
+    let backtrace = Arc::new(Backtrace::disabled());
+
+    let with_backtrace =
+        SubsystemError::<BoxedError>::panicked("/a", Some(Arc::clone(&backtrace)), Severity::Error);
+    let without_backtrace = SubsystemError::<BoxedError>::panicked("/a", None, Severity::Error);
+
+    assert!(std::ptr::eq(
+        with_backtrace.backtrace().unwrap(),
+        Arc::as_ptr(&backtrace)
+    ));
+    assert!(without_backtrace.backtrace().is_none());
+    assert!(SubsystemError::<BoxedError>::Panicked("/a".into())
+        .backtrace()
+        .is_none());
+}
+
 #[test]
 fn extract_contained_error_from_convert_subsystem_failure() {
     let msg = "MyFailure".to_string();
-    let failure = SubsystemFailure(msg.clone());
+    let failure = SubsystemFailure(msg.clone(), Severity::Error);
 
     assert_eq!(&msg, failure.get_error());
     assert_eq!(msg, *failure);
@@ -84,15 +199,15 @@ fn handle_dropped_errors() {
         "ABC",
     )))));
 
-    assert!(logs_contain("An error got dropped: \"ABC\""));
+    assert!(logs_contain("An error got dropped. error=\"ABC\""));
 }
 
 #[test]
 #[traced_test]
 fn handle_unhandled_stopreasons() {
-    handle_unhandled_stopreason(Some(SubsystemError::<BoxedError>::Panicked(Arc::from(
-        "def",
-    ))));
+    handle_unhandled_stopreason(Some(SubsystemError::<BoxedError>::Panicked("def".into())));
 
-    assert!(logs_contain("Unhandled stop reason: Panicked(\"def\")"));
+    assert!(logs_contain(
+        "Unhandled stop reason. stop_reason=Panicked(\"def\")"
+    ));
 }
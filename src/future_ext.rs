@@ -1,8 +1,11 @@
-use crate::{errors::CancelledByShutdown, SubsystemHandle};
+use crate::{
+    errors::{CancelledByShutdown, ShutdownCancelReason},
+    SubsystemHandle,
+};
 
 use pin_project_lite::pin_project;
 
-use tokio_util::sync::WaitForCancellationFuture;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFuture};
 
 pin_project! {
     /// A future that is resolved once the corresponding task is finished
@@ -41,6 +44,181 @@ impl<T: std::future::Future> std::future::Future for CancelOnShutdownFuture<'_,
     }
 }
 
+pin_project! {
+    /// A future that is resolved once the corresponding task is finished
+    /// or a shutdown is initiated, resolving to a default value in the latter case.
+    #[must_use = "futures do nothing unless polled"]
+    pub struct CancelOnShutdownOrFuture<'a, T: std::future::Future>{
+        #[pin]
+        future: CancelOnShutdownFuture<'a, T>,
+        default: Option<T::Output>,
+    }
+}
+
+impl<T: std::future::Future> std::future::Future for CancelOnShutdownOrFuture<'_, T> {
+    type Output = T::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = self.project();
+
+        this.future.poll(cx).map(|result| {
+            result.unwrap_or_else(|CancelledByShutdown| {
+                this.default
+                    .take()
+                    .expect("The default value is only taken once.")
+            })
+        })
+    }
+}
+
+pin_project! {
+    /// A future that is resolved once the corresponding task is finished
+    /// or a shutdown is initiated, computing a default value in the latter case.
+    #[must_use = "futures do nothing unless polled"]
+    pub struct CancelOnShutdownOrElseFuture<'a, T: std::future::Future, F>{
+        #[pin]
+        future: CancelOnShutdownFuture<'a, T>,
+        default: Option<F>,
+    }
+}
+
+impl<T, F> std::future::Future for CancelOnShutdownOrElseFuture<'_, T, F>
+where
+    T: std::future::Future,
+    F: FnOnce() -> T::Output,
+{
+    type Output = T::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = self.project();
+
+        this.future.poll(cx).map(|result| {
+            result.unwrap_or_else(|CancelledByShutdown| {
+                let default = this
+                    .default
+                    .take()
+                    .expect("The default function is only taken once.");
+                default()
+            })
+        })
+    }
+}
+
+pin_project! {
+    /// A future that is resolved once the corresponding task is finished
+    /// or a shutdown is initiated, reporting which kind of shutdown
+    /// caused the cancellation.
+    #[must_use = "futures do nothing unless polled"]
+    pub struct CancelOnShutdownDetailedFuture<'a, T: std::future::Future>{
+        #[pin]
+        future: T,
+        #[pin]
+        cancellation: WaitForCancellationFuture<'a>,
+        global_token: &'a CancellationToken,
+    }
+}
+
+impl<T: std::future::Future> std::future::Future for CancelOnShutdownDetailedFuture<'_, T> {
+    type Output = Result<T::Output, ShutdownCancelReason>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        use std::task::Poll;
+
+        let mut this = self.project();
+
+        // Abort if there is a shutdown. A global shutdown always implies
+        // a local one, so check the global token to find the cause.
+        match this.cancellation.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                let reason = if this.global_token.is_cancelled() {
+                    ShutdownCancelReason::Global
+                } else {
+                    ShutdownCancelReason::Local
+                };
+                return Poll::Ready(Err(reason));
+            }
+            Poll::Pending => (),
+        }
+
+        // If there is no shutdown, see if the task is finished
+        match this.future.as_mut().poll(cx) {
+            Poll::Ready(res) => Poll::Ready(Ok(res)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pin_project! {
+    #[project = SwitchStateProj]
+    enum SwitchState<T, G> {
+        Main {
+            #[pin]
+            future: T,
+            cleanup: Option<G>,
+        },
+        Cleanup {
+            #[pin]
+            cleanup: G,
+        },
+    }
+}
+
+pin_project! {
+    /// A future that runs the corresponding task until a shutdown is initiated,
+    /// and then switches to a cleanup future.
+    #[must_use = "futures do nothing unless polled"]
+    pub struct OnShutdownSwitchFuture<'a, T, G>{
+        #[pin]
+        state: SwitchState<T, G>,
+        #[pin]
+        cancellation: WaitForCancellationFuture<'a>,
+    }
+}
+
+impl<T, G> std::future::Future for OnShutdownSwitchFuture<'_, T, G>
+where
+    T: std::future::Future,
+    G: std::future::Future<Output = T::Output>,
+{
+    type Output = T::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        use std::task::Poll;
+
+        let mut this = self.project();
+
+        if let SwitchStateProj::Main { future, cleanup } = this.state.as_mut().project() {
+            match this.cancellation.as_mut().poll(cx) {
+                Poll::Ready(()) => {
+                    // Drop the task before the cleanup starts
+                    let cleanup = cleanup
+                        .take()
+                        .expect("The cleanup future is only taken once.");
+                    this.state.set(SwitchState::Cleanup { cleanup });
+                }
+                Poll::Pending => return future.poll(cx),
+            }
+        }
+
+        match this.state.project() {
+            SwitchStateProj::Cleanup { cleanup } => cleanup.poll(cx),
+            SwitchStateProj::Main { .. } => unreachable!("The state already switched to cleanup."),
+        }
+    }
+}
+
 /// Extends the [std::future::Future] trait with useful utility functions.
 pub trait FutureExt {
     /// The type of the future.
@@ -83,6 +261,170 @@ pub trait FutureExt {
         self,
         subsys: &SubsystemHandle,
     ) -> CancelOnShutdownFuture<'_, Self::Future>;
+
+    /// Cancels the future when a shutdown is initiated, and resolves to `default` instead.
+    ///
+    /// Behaves like [`cancel_on_shutdown()`](FutureExt::cancel_on_shutdown), but avoids
+    /// matching on [CancelledByShutdown] if a fallback value is good enough.
+    ///
+    /// ## Returns
+    ///
+    /// A future that resolves to either the return value of the original future, or to
+    /// `default` when a shutdown happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The [SubsystemHandle] to receive the shutdown request from.
+    /// * `default` - The value to resolve to when a shutdown happened.
+    ///
+    /// # Examples
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{FutureExt, SubsystemHandle};
+    /// use tokio::time::{sleep, Duration};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let processed = async {
+    ///         sleep(Duration::from_secs(9001)).await;
+    ///         100
+    ///     }
+    ///     .cancel_on_shutdown_or(&subsys, 0)
+    ///     .await;
+    ///
+    ///     println!("Processed {processed} items.");
+    ///     Ok(())
+    /// }
+    /// ```
+    fn cancel_on_shutdown_or(
+        self,
+        subsys: &SubsystemHandle,
+        default: <Self::Future as std::future::Future>::Output,
+    ) -> CancelOnShutdownOrFuture<'_, Self::Future>;
+
+    /// Cancels the future when a shutdown is initiated, and resolves to the
+    /// return value of `default` instead.
+    ///
+    /// Like [`cancel_on_shutdown_or()`](FutureExt::cancel_on_shutdown_or), but only
+    /// computes the fallback value if a shutdown happened.
+    ///
+    /// ## Returns
+    ///
+    /// A future that resolves to either the return value of the original future, or to
+    /// the return value of `default` when a shutdown happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The [SubsystemHandle] to receive the shutdown request from.
+    /// * `default` - The function that computes the value to resolve to when a shutdown happened.
+    ///
+    /// # Examples
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{FutureExt, SubsystemHandle};
+    /// use tokio::time::{sleep, Duration};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let message = async {
+    ///         sleep(Duration::from_secs(9001)).await;
+    ///         String::from("Sleep finished.")
+    ///     }
+    ///     .cancel_on_shutdown_or_else(&subsys, || String::from("Sleep got cancelled by shutdown."))
+    ///     .await;
+    ///
+    ///     println!("{message}");
+    ///     Ok(())
+    /// }
+    /// ```
+    fn cancel_on_shutdown_or_else<F>(
+        self,
+        subsys: &SubsystemHandle,
+        default: F,
+    ) -> CancelOnShutdownOrElseFuture<'_, Self::Future, F>
+    where
+        F: FnOnce() -> <Self::Future as std::future::Future>::Output;
+
+    /// Cancels the future when a shutdown is initiated, and reports
+    /// whether the shutdown was global or local.
+    ///
+    /// ## Returns
+    ///
+    /// A future that resolves to either the return value of the original future, or to
+    /// a [ShutdownCancelReason] when a shutdown happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The [SubsystemHandle] to receive the shutdown request from.
+    ///
+    /// # Examples
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{errors::ShutdownCancelReason, FutureExt, SubsystemHandle};
+    /// use tokio::time::{sleep, Duration};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     match sleep(Duration::from_secs(9001))
+    ///         .cancel_on_shutdown_detailed(&subsys)
+    ///         .await
+    ///     {
+    ///         Ok(()) => {
+    ///             println!("Sleep finished.");
+    ///         }
+    ///         Err(ShutdownCancelReason::Global) => {
+    ///             println!("Sleep got cancelled by a global shutdown.");
+    ///         }
+    ///         Err(ShutdownCancelReason::Local) => {
+    ///             println!("Sleep got cancelled by a local shutdown.");
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn cancel_on_shutdown_detailed(
+        self,
+        subsys: &SubsystemHandle,
+    ) -> CancelOnShutdownDetailedFuture<'_, Self::Future>;
+
+    /// Runs the future until a shutdown is initiated, and then switches to
+    /// running the given cleanup future instead.
+    ///
+    /// The original future gets dropped before the cleanup future gets polled for the first time.
+    ///
+    /// ## Returns
+    ///
+    /// A future that resolves to either the return value of the original future, or to
+    /// the return value of `cleanup` when a shutdown happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The [SubsystemHandle] to receive the shutdown request from.
+    /// * `cleanup` - The future to run once a shutdown happened.
+    ///
+    /// # Examples
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{FutureExt, SubsystemHandle};
+    /// use tokio::time::{sleep, Duration};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let message = async {
+    ///         sleep(Duration::from_secs(9001)).await;
+    ///         "Sleep finished."
+    ///     }
+    ///     .on_shutdown_switch(&subsys, async { "Sleep got cancelled by shutdown." })
+    ///     .await;
+    ///
+    ///     println!("{message}");
+    ///     Ok(())
+    /// }
+    /// ```
+    fn on_shutdown_switch<G>(
+        self,
+        subsys: &SubsystemHandle,
+        cleanup: G,
+    ) -> OnShutdownSwitchFuture<'_, Self::Future, G>
+    where
+        G: std::future::Future<Output = <Self::Future as std::future::Future>::Output>;
 }
 
 impl<T: std::future::Future> FutureExt for T {
@@ -96,4 +438,62 @@ impl<T: std::future::Future> FutureExt for T {
             cancellation,
         }
     }
+
+    fn cancel_on_shutdown_or(
+        self,
+        subsys: &SubsystemHandle,
+        default: T::Output,
+    ) -> CancelOnShutdownOrFuture<'_, T> {
+        CancelOnShutdownOrFuture {
+            future: self.cancel_on_shutdown(subsys),
+            default: Some(default),
+        }
+    }
+
+    fn cancel_on_shutdown_or_else<F>(
+        self,
+        subsys: &SubsystemHandle,
+        default: F,
+    ) -> CancelOnShutdownOrElseFuture<'_, T, F>
+    where
+        F: FnOnce() -> T::Output,
+    {
+        CancelOnShutdownOrElseFuture {
+            future: self.cancel_on_shutdown(subsys),
+            default: Some(default),
+        }
+    }
+
+    fn cancel_on_shutdown_detailed(
+        self,
+        subsys: &SubsystemHandle,
+    ) -> CancelOnShutdownDetailedFuture<'_, T> {
+        let cancellation = subsys.get_cancellation_token().cancelled();
+        let global_token = subsys.get_toplevel_cancellation_token();
+
+        CancelOnShutdownDetailedFuture {
+            future: self,
+            cancellation,
+            global_token,
+        }
+    }
+
+    fn on_shutdown_switch<G>(
+        self,
+        subsys: &SubsystemHandle,
+        cleanup: G,
+    ) -> OnShutdownSwitchFuture<'_, T, G>
+    where
+        G: std::future::Future<Output = T::Output>,
+    {
+        let cancellation = subsys.get_cancellation_token().cancelled();
+
+        OnShutdownSwitchFuture {
+            state: SwitchState::Main {
+                future: self,
+                cleanup: Some(cleanup),
+            },
+            cancellation,
+        }
+    }
 }
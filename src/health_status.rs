@@ -0,0 +1,32 @@
+/// The health of a subsystem, as reported by the subsystem itself.
+///
+/// Set through [`SubsystemHandle::set_health()`](crate::SubsystemHandle::set_health),
+/// and observed through [`NestedSubsystem::health()`](crate::NestedSubsystem::health)
+/// or [`Toplevel::aggregate_health()`](crate::Toplevel::aggregate_health).
+///
+/// The variants are ordered from best to worst, so the worst status
+/// of multiple subsystems is their maximum.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum HealthStatus {
+    /// The subsystem works as intended. The default of every subsystem.
+    #[default]
+    Healthy,
+    /// The subsystem works, but with limitations.
+    Degraded,
+    /// The subsystem does not work.
+    Unhealthy,
+}
+
+impl HealthStatus {
+    pub(crate) fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            0 => HealthStatus::Healthy,
+            1 => HealthStatus::Degraded,
+            _ => HealthStatus::Unhealthy,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+/// Defines what happens if a [`SubsystemHandle`](crate::SubsystemHandle)
+/// gets leaked out of its subsystem.
+///
+/// Leaking a handle is a programming error. It prevents the subsystem
+/// from being cleaned up properly once the subsystem function returned.
+///
+/// Configured through [`Toplevel::on_handle_leak()`](crate::Toplevel::on_handle_leak).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LeakPolicy {
+    /// Log an error and panic inside of the subsystem runner.
+    ///
+    /// The subsystem never counts as finished, so the shutdown will time out.
+    #[default]
+    Panic,
+    /// Log an error and abort the process.
+    Abort,
+    /// Log an error and treat the subsystem as finished.
+    ///
+    /// Its children receive a shutdown request and are waited for as usual.
+    LogAndCancel,
+}
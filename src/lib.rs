@@ -107,21 +107,74 @@ impl<T> ErrTypeTraits for T where
 
 pub mod errors;
 
+mod connection_set;
 mod error_action;
+mod error_counter;
+mod error_stream;
 mod future_ext;
+mod health_status;
 mod into_subsystem;
+mod leak_policy;
+mod lifecycle;
+mod restart_policy;
 mod runner;
+mod sample;
+mod scoped_subsystem;
+mod severity;
+mod shutdown_order;
+mod shutdown_state;
+mod shutdown_trigger;
 mod signal_handling;
+mod stream_ext;
 mod subsystem;
+mod subsystem_name;
+mod subsystem_outcome;
+mod supervision_strategy;
 mod tokio_task;
 mod toplevel;
+mod toplevel_builder;
+mod tree_snapshot;
 mod utils;
+mod wake_reason;
 
+pub use connection_set::ConnectionSet;
 pub use error_action::ErrorAction;
+pub use error_counter::ErrorCounter;
+pub use error_stream::ErrorStream;
 pub use future_ext::FutureExt;
+pub use health_status::HealthStatus;
 pub use into_subsystem::IntoSubsystem;
+pub use leak_policy::LeakPolicy;
+pub use lifecycle::LifecycleEvent;
+pub use lifecycle::LifecycleEventKind;
+pub use restart_policy::RestartPolicy;
+pub use sample::Sample;
+pub use sample::SubsystemSample;
+pub use scoped_subsystem::ScopedSubsystem;
+pub use severity::Severity;
+pub use shutdown_order::ShutdownOrder;
+pub use shutdown_state::ShutdownState;
+pub use shutdown_trigger::ShutdownTrigger;
+pub use signal_handling::EarlySignalPolicy;
+pub use signal_handling::SignalHandlerControl;
+pub use signal_handling::SignalKind;
+pub use stream_ext::StreamExt;
 pub use subsystem::NestedSubsystem;
 pub use subsystem::SubsystemBuilder;
 pub use subsystem::SubsystemFinishedFuture;
 pub use subsystem::SubsystemHandle;
+pub use subsystem::SubsystemJoinReport;
+pub use subsystem::SubsystemPool;
+pub use subsystem::SubsystemTreeBuilder;
+pub use subsystem::SubsystemTreeNode;
+pub use subsystem::SupervisedSubsystem;
+#[cfg(feature = "test-util")]
+pub use subsystem::TestController;
+pub use subsystem::WeakNestedSubsystem;
+pub use subsystem_name::SubsystemName;
+pub use subsystem_outcome::SubsystemOutcome;
+pub use supervision_strategy::SupervisionStrategy;
 pub use toplevel::Toplevel;
+pub use toplevel_builder::ToplevelBuilder;
+pub use tree_snapshot::TreeSnapshot;
+pub use wake_reason::WakeReason;
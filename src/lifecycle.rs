@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+/// The number of events a lagging receiver can fall behind before
+/// it starts to miss events.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// An event in the lifecycle of a subsystem.
+///
+/// Received through [`Toplevel::events()`](crate::Toplevel::events).
+#[derive(Clone, Debug)]
+pub struct LifecycleEvent {
+    /// The name of the subsystem that caused the event.
+    pub name: Arc<str>,
+    /// What happened to the subsystem.
+    pub kind: LifecycleEventKind,
+}
+
+/// The different kinds of a [`LifecycleEvent`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LifecycleEventKind {
+    /// The subsystem got started.
+    Started,
+    /// The subsystem returned an error.
+    Failed,
+    /// The subsystem panicked.
+    Panicked,
+    /// The subsystem got cancelled before it could finish.
+    Cancelled,
+    /// The subsystem and all of its children finished.
+    ///
+    /// Also sent after [`Failed`](LifecycleEventKind::Failed) or
+    /// [`Panicked`](LifecycleEventKind::Panicked).
+    Finished,
+}
+
+/// Distributes lifecycle events to all subscribers.
+///
+/// Sending never blocks; receivers that lag behind lose events.
+#[derive(Clone)]
+pub(crate) struct LifecycleEvents {
+    sender: broadcast::Sender<LifecycleEvent>,
+}
+
+impl LifecycleEvents {
+    pub(crate) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    pub(crate) fn emit(&self, name: Arc<str>, kind: LifecycleEventKind) {
+        // Ignore errors; an error only means that nobody is listening.
+        let _ = self.sender.send(LifecycleEvent { name, kind });
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.sender.subscribe()
+    }
+}
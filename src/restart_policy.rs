@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+/// How often and how quickly a supervised subsystem gets restarted.
+///
+/// See [`SubsystemHandle::spawn_supervised()`](crate::SubsystemHandle::spawn_supervised).
+///
+/// The default restarts the subsystem an unlimited number of times,
+/// without a delay in between.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RestartPolicy {
+    pub(crate) max_restarts: Option<u64>,
+    pub(crate) delay: Duration,
+}
+
+impl RestartPolicy {
+    /// Creates a policy that restarts the subsystem an unlimited number of times,
+    /// without a delay in between.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits how often the subsystem gets restarted.
+    ///
+    /// Once the limit is reached, the next failure gets passed on
+    /// to the parent like the failure of any other subsystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_restarts` - The maximum number of restarts.
+    pub fn max_restarts(mut self, max_restarts: u64) -> Self {
+        self.max_restarts = Some(max_restarts);
+        self
+    }
+
+    /// Waits before every restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - The time between the failure and the restart.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Whether another restart is allowed after `restarts` restarts.
+    pub(crate) fn allows_restart(&self, restarts: u64) -> bool {
+        self.max_restarts
+            .map_or(true, |max_restarts| restarts < max_restarts)
+    }
+}
@@ -3,19 +3,111 @@
 //! A two-layer `tokio::spawn` is required to make this work reliably; the inner `spawn` is the actual subsystem,
 //! and the outer `spawn` carries out the duty of propagating the `StopReason` and cleaning up.
 //!
+//! Without panic isolation, the subsystem runs directly inside of the outer `spawn` instead.
+//! This saves one task per subsystem, but a panic then takes down the outer task as well;
+//! see [`InlineSubsystem`] for how it still gets reported.
+//!
 //! Further, everything in here reacts properly to being dropped, including
 //! the runner itself, who cancels the subsystem on drop.
+//!
+//! The finalizer of a subsystem runs in a `spawn` of its own, so it even gets to run
+//! if the runner got dropped.
+
+use std::{
+    any::Any,
+    future::Future,
+    pin::Pin,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Duration,
+};
 
-use std::{future::Future, sync::Arc};
+use tokio::{runtime::Handle, task::JoinHandle};
 
 use crate::{
     errors::{SubsystemError, SubsystemFailure},
-    ErrTypeTraits, SubsystemHandle,
+    lifecycle::{LifecycleEventKind, LifecycleEvents},
+    subsystem::OutcomeLog,
+    ErrTypeTraits, LeakPolicy, Severity, SubsystemHandle, SubsystemOutcome,
 };
 
 mod alive_guard;
 pub(crate) use self::alive_guard::AliveGuard;
 
+mod backtrace;
+use self::backtrace::BacktraceSlot;
+
+mod inline_subsystem;
+use self::inline_subsystem::InlineSubsystem;
+
+/// Converts the payload of a panic into an error.
+pub(crate) type PanicMapper<ErrType> = Box<dyn FnOnce(&str, Box<dyn Any + Send>) -> ErrType + Send>;
+
+/// Cleanup work that runs once the subsystem is finished, even if it got cancelled.
+pub(crate) type Finalizer = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Customizes how the runner handles the end of a subsystem.
+pub(crate) struct RunnerHooks<ErrType> {
+    /// Converts a panic of the subsystem into an error.
+    pub(crate) panic_mapper: Option<PanicMapper<ErrType>>,
+    /// Runs once the subsystem is finished.
+    pub(crate) finalizer: Option<Finalizer>,
+}
+
+impl<ErrType> Default for RunnerHooks<ErrType> {
+    fn default() -> Self {
+        Self {
+            panic_mapper: None,
+            finalizer: None,
+        }
+    }
+}
+
+/// Why the runner of a subsystem got aborted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CancelReason {
+    /// The subsystem got aborted explicitly, for example through
+    /// [`SubsystemHandle::abort_all()`].
+    Aborted,
+    /// The subsystem was still running when the shutdown timed out.
+    ShutdownTimeout,
+}
+
+impl CancelReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            CancelReason::Aborted => "aborted",
+            CancelReason::ShutdownTimeout => "shutdown timeout",
+        }
+    }
+}
+
+/// How long a finalizer may run before it gets abandoned.
+pub(crate) const FINALIZER_GRACE: Duration = Duration::from_secs(1);
+
+/// Configures how a subsystem gets run.
+#[derive(Clone, Copy)]
+pub(crate) struct RunnerOptions {
+    /// Log the cancellation of the subsystem as `debug` instead of `warn`.
+    pub(crate) quiet_cancel: bool,
+    /// Run the subsystem in a task of its own, to catch its panics.
+    pub(crate) panic_isolation: bool,
+    /// The severity of the failures of the subsystem.
+    pub(crate) severity: Severity,
+    /// Allow the subsystem to shut down the entire tree.
+    pub(crate) shutdown_authority: bool,
+}
+
+impl Default for RunnerOptions {
+    fn default() -> Self {
+        Self {
+            quiet_cancel: false,
+            panic_isolation: true,
+            severity: Severity::default(),
+            shutdown_authority: true,
+        }
+    }
+}
+
 pub(crate) struct SubsystemRunner {
     aborthandle: tokio::task::AbortHandle,
 }
@@ -23,20 +115,38 @@ pub(crate) struct SubsystemRunner {
 impl SubsystemRunner {
     #[track_caller]
     pub(crate) fn new<Fut, Subsys, ErrType: ErrTypeTraits, Err>(
-        name: Arc<str>,
+        name: Arc<Mutex<Arc<str>>>,
         subsystem: Subsys,
         subsystem_handle: SubsystemHandle<ErrType>,
         guard: AliveGuard,
+        options: RunnerOptions,
+        events: LifecycleEvents,
+        hooks: RunnerHooks<ErrType>,
     ) -> Self
     where
         Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
         Fut: 'static + Future<Output = Result<(), Err>> + Send,
         Err: Into<ErrType>,
     {
-        let future = run_subsystem(name, subsystem, subsystem_handle, guard);
-        let aborthandle = crate::tokio_task::spawn(future, "subsystem_runner").abort_handle();
+        let runner_name = format!("runner:{}", name.lock().unwrap());
+        let runtime = subsystem_handle.runtime().cloned();
+        let future = run_subsystem(
+            name,
+            subsystem,
+            subsystem_handle,
+            guard,
+            options,
+            events,
+            hooks,
+        );
+        let aborthandle =
+            crate::tokio_task::spawn(future, &runner_name, runtime.as_ref()).abort_handle();
         SubsystemRunner { aborthandle }
     }
+
+    pub(crate) fn abort_handle(&self) -> tokio::task::AbortHandle {
+        self.aborthandle.clone()
+    }
 }
 
 impl Drop for SubsystemRunner {
@@ -47,10 +157,13 @@ impl Drop for SubsystemRunner {
 
 #[track_caller]
 fn run_subsystem<Fut, Subsys, ErrType: ErrTypeTraits, Err>(
-    name: Arc<str>,
+    name: Arc<Mutex<Arc<str>>>,
     subsystem: Subsys,
     mut subsystem_handle: SubsystemHandle<ErrType>,
     guard: AliveGuard,
+    options: RunnerOptions,
+    events: LifecycleEvents,
+    hooks: RunnerHooks<ErrType>,
 ) -> impl Future<Output = ()> + 'static
 where
     Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
@@ -58,34 +171,151 @@ where
     Err: Into<ErrType>,
 {
     let mut redirected_subsystem_handle = subsystem_handle.delayed_clone();
+    let tree_node = Arc::clone(subsystem_handle.tree_node());
+    let outcome_logs = subsystem_handle.outcome_logs();
+    let leak_policy = subsystem_handle.leak_policy();
+    let joiner_token_finisher = subsystem_handle.joiner_token_finisher();
+    let panic_joiner_token_finisher = subsystem_handle.joiner_token_finisher();
+    let cancellation_token = subsystem_handle.get_cancellation_token().clone();
+    let children_outlive_parent = subsystem_handle.children_outlive_parent();
+    let runtime = subsystem_handle.runtime().cloned();
+    let RunnerHooks {
+        panic_mapper,
+        finalizer,
+    } = hooks;
+    // Whoever takes the finalizer first runs it; the runner if the subsystem
+    // finished, or the cancel callback if the runner got dropped.
+    let finalizer = Arc::new(Mutex::new(finalizer));
+    let finalizer_runtime = runtime.clone().unwrap_or_else(Handle::current);
 
-    let future = async { subsystem(subsystem_handle).await.map_err(|e| e.into()) };
-    let join_handle = crate::tokio_task::spawn(future, &name);
+    let backtrace = Arc::new(BacktraceSlot::default());
+    let future =
+        backtrace.capture(async { subsystem(subsystem_handle).await.map_err(|e| e.into()) });
+    let subsystem_task = if options.panic_isolation {
+        SubsystemTask::Spawned(crate::tokio_task::spawn(
+            future,
+            &name.lock().unwrap(),
+            runtime.as_ref(),
+        ))
+    } else {
+        SubsystemTask::Inline(InlineSubsystem::new(future, {
+            let name = Arc::clone(&name);
+            let tree_node = Arc::clone(&tree_node);
+            let outcome_logs = Arc::clone(&outcome_logs);
+            let joiner_token_finisher = panic_joiner_token_finisher;
+            let events = events.clone();
+            let backtrace = Arc::clone(&backtrace);
+            let severity = options.severity;
+            move || {
+                // The remaining cleanup of the runner never gets to run,
+                // so the children are not waited for.
+                tree_node.mark_finished();
+                tree_node.set_outcome(SubsystemOutcome::Panicked);
+                let name = Arc::clone(&name.lock().unwrap());
+                events.emit(Arc::clone(&name), LifecycleEventKind::Panicked);
+                OutcomeLog::record(&outcome_logs, &name, SubsystemOutcome::Panicked);
+                joiner_token_finisher.raise_failure(SubsystemError::panicked(
+                    &name,
+                    backtrace.take(),
+                    severity,
+                ));
+            }
+        }))
+    };
+    events.emit(
+        Arc::clone(&name.lock().unwrap()),
+        LifecycleEventKind::Started,
+    );
 
     async move {
         // Abort on drop
         guard.on_cancel({
-            let abort_handle = join_handle.abort_handle();
+            let is_finished = subsystem_task.is_finished_fn();
+            let abort_handle = subsystem_task.abort_handle();
             let name = Arc::clone(&name);
+            let tree_node = Arc::clone(&tree_node);
+            let events = events.clone();
+            let finalizer = Arc::clone(&finalizer);
+            let finalizer_runtime = finalizer_runtime.clone();
             move || {
-                if !abort_handle.is_finished() {
-                    tracing::warn!("Subsystem cancelled: '{}'", name);
+                if !is_finished() {
+                    tree_node.set_outcome(SubsystemOutcome::Cancelled);
+                    let name = Arc::clone(&name.lock().unwrap());
+                    let reason = tree_node.cancel_reason().map(CancelReason::as_str);
+                    if options.quiet_cancel {
+                        tracing::debug!(subsystem = %name, reason, "Subsystem cancelled.");
+                    } else {
+                        tracing::warn!(subsystem = %name, reason, "Subsystem cancelled.");
+                    }
+                    events.emit(name, LifecycleEventKind::Cancelled);
+                }
+                if let Some(abort_handle) = abort_handle {
+                    abort_handle.abort();
+                }
+                let finalizer = finalizer.lock().unwrap().take();
+                if let Some(finalizer) = finalizer {
+                    spawn_finalizer(finalizer, &name.lock().unwrap(), &finalizer_runtime);
                 }
-                abort_handle.abort();
             }
         });
 
-        let failure = match join_handle.await {
+        let join_result = match subsystem_task {
+            SubsystemTask::Spawned(join_handle) => join_handle.await,
+            SubsystemTask::Inline(future) => Ok(future.await),
+        };
+
+        // The subsystem only counts as finished once its finalizer is done
+        let finalizer = finalizer.lock().unwrap().take();
+        if let Some(finalizer) = finalizer {
+            let finalizer = spawn_finalizer(finalizer, &name.lock().unwrap(), &finalizer_runtime);
+            // A panicking finalizer does not affect the outcome of the subsystem
+            let _ = finalizer.await;
+        }
+
+        tree_node.mark_finished();
+        let name = Arc::clone(&name.lock().unwrap());
+        let outcome = match &join_result {
+            Ok(Ok(())) => SubsystemOutcome::Succeeded,
+            Ok(Err(_)) => SubsystemOutcome::Failed,
+            Err(_) => SubsystemOutcome::Panicked,
+        };
+        tree_node.set_outcome(outcome);
+        let failure = match join_result {
             Ok(Ok(())) => None,
-            Ok(Err(e)) => Some(SubsystemError::Failed(name, SubsystemFailure(e))),
+            Ok(Err(e)) => {
+                events.emit(Arc::clone(&name), LifecycleEventKind::Failed);
+                Some(SubsystemError::Failed(
+                    Arc::clone(&name),
+                    SubsystemFailure(e, options.severity),
+                ))
+            }
             Err(e) => {
                 // We can assume that this is a panic, because a cancellation
                 // can never happen as long as we still hold `guard`.
                 assert!(e.is_panic());
-                Some(SubsystemError::Panicked(name))
+                events.emit(Arc::clone(&name), LifecycleEventKind::Panicked);
+                match panic_mapper {
+                    Some(panic_mapper) => Some(SubsystemError::Failed(
+                        Arc::clone(&name),
+                        SubsystemFailure(panic_mapper(&name, e.into_panic()), options.severity),
+                    )),
+                    None => Some(SubsystemError::panicked(
+                        &name,
+                        backtrace.take(),
+                        options.severity,
+                    )),
+                }
             }
         };
 
+        // A panic that got mapped into an error counts as a failure
+        let logged_outcome = match &failure {
+            None => SubsystemOutcome::Succeeded,
+            Some(SubsystemError::Panicked(_)) => SubsystemOutcome::Panicked,
+            Some(_) => SubsystemOutcome::Failed,
+        };
+        OutcomeLog::record(&outcome_logs, &name, logged_outcome);
+
         // Retrieve the handle that was passed into the subsystem.
         // Originally it was intended to pass the handle as reference, but due
         // to complications (https://stackoverflow.com/a/70592053/2902833 and
@@ -96,12 +326,45 @@ where
         let subsystem_handle = match redirected_subsystem_handle.try_recv() {
             Ok(s) => s,
             Err(_) => {
-                tracing::error!(
-                    "The SubsystemHandle object must not be leaked out of the subsystem!"
-                );
-                panic!("The SubsystemHandle object must not be leaked out of the subsystem!");
+                let leak_policy = *leak_policy.lock().unwrap();
+                match leak_policy {
+                    LeakPolicy::Panic => {
+                        tracing::error!(
+                            subsystem = %name,
+                            "The SubsystemHandle object must not be leaked out of the subsystem!"
+                        );
+                        panic!(
+                            "The SubsystemHandle object must not be leaked out of the subsystem!"
+                        );
+                    }
+                    LeakPolicy::Abort => {
+                        tracing::error!(
+                            subsystem = %name,
+                            "The SubsystemHandle object must not be leaked out of the subsystem! Aborting."
+                        );
+                        std::process::abort();
+                    }
+                    LeakPolicy::LogAndCancel => {
+                        tracing::error!(
+                            subsystem = %name,
+                            "The SubsystemHandle object must not be leaked out of the subsystem! Treating the subsystem as finished."
+                        );
+
+                        // The children are owned by the leaked handle; shut them down
+                        // and keep tracking them, but don't wait for the handle.
+                        cancellation_token.cancel();
+                        if let Some(failure) = failure {
+                            joiner_token_finisher.raise_failure(failure);
+                        }
+                        joiner_token_finisher.finish();
+
+                        events.emit(name, LifecycleEventKind::Finished);
+                        return;
+                    }
+                }
             }
         };
+        drop(joiner_token_finisher);
 
         // Raise potential errors
         let joiner_token = subsystem_handle.joiner_token;
@@ -109,10 +372,69 @@ where
             joiner_token.raise_failure(failure);
         }
 
+        if children_outlive_parent.load(Ordering::Acquire) {
+            // The parent takes over the children, so this subsystem is finished already.
+            // Keep the children alive until they are finished, though; they still
+            // get cancelled together with this runner.
+            joiner_token.detach_children();
+            events.emit(name, LifecycleEventKind::Finished);
+            joiner_token.join_detached_children().await;
+            return;
+        }
+
         // Wait for children to finish before we destroy the `SubsystemHandle` object.
         // Otherwise the children would be cancelled immediately.
         //
         // This is the main mechanism that forwards a cancellation to all the children.
         joiner_token.downgrade().join().await;
+
+        events.emit(name, LifecycleEventKind::Finished);
+    }
+}
+
+/// Runs the finalizer of a subsystem in a task of its own, limited to [`FINALIZER_GRACE`].
+fn spawn_finalizer(finalizer: Finalizer, name: &Arc<str>, runtime: &Handle) -> JoinHandle<()> {
+    let name = Arc::clone(name);
+    crate::tokio_task::spawn(
+        async move {
+            if tokio::time::timeout(FINALIZER_GRACE, finalizer())
+                .await
+                .is_err()
+            {
+                tracing::warn!(subsystem = %name, "Finalizer timed out.");
+            }
+        },
+        "finalizer",
+        Some(runtime),
+    )
+}
+
+/// The subsystem, either spawned in a task of its own or run inline.
+enum SubsystemTask<F: Future> {
+    Spawned(tokio::task::JoinHandle<F::Output>),
+    Inline(InlineSubsystem<F>),
+}
+
+impl<F: Future> SubsystemTask<F> {
+    fn abort_handle(&self) -> Option<tokio::task::AbortHandle> {
+        match self {
+            SubsystemTask::Spawned(join_handle) => Some(join_handle.abort_handle()),
+            // Gets dropped together with the runner task
+            SubsystemTask::Inline(_) => None,
+        }
+    }
+
+    /// Whether the subsystem finished on its own, as opposed to being cancelled.
+    fn is_finished_fn(&self) -> Box<dyn Fn() -> bool + Send> {
+        match self {
+            SubsystemTask::Spawned(join_handle) => {
+                let abort_handle = join_handle.abort_handle();
+                Box::new(move || abort_handle.is_finished())
+            }
+            SubsystemTask::Inline(future) => {
+                let settled = future.settled();
+                Box::new(move || settled.load(Ordering::Acquire))
+            }
+        }
     }
 }
@@ -0,0 +1,66 @@
+//! Records the backtrace of a panicking subsystem.
+//!
+//! The panic hook is process wide, so it gets installed once and only records
+//! a backtrace if the panic happens while a subsystem future is being polled.
+//! The previously installed hook still gets called afterwards.
+
+use std::{backtrace::Backtrace, future::Future, sync::Arc};
+
+#[cfg(feature = "capture-backtrace")]
+use std::sync::{Mutex, Once};
+
+#[cfg(feature = "capture-backtrace")]
+tokio::task_local! {
+    static CURRENT_SLOT: Arc<Mutex<Option<Backtrace>>>;
+}
+
+/// Holds the backtrace of the last panic of a subsystem.
+#[derive(Default)]
+pub(crate) struct BacktraceSlot {
+    #[cfg(feature = "capture-backtrace")]
+    backtrace: Arc<Mutex<Option<Backtrace>>>,
+}
+
+impl BacktraceSlot {
+    /// Wraps the given future so that panics inside of it get recorded in this slot.
+    pub(crate) fn capture<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "capture-backtrace")]
+        {
+            install_panic_hook();
+            CURRENT_SLOT.scope(Arc::clone(&self.backtrace), future)
+        }
+        #[cfg(not(feature = "capture-backtrace"))]
+        {
+            future
+        }
+    }
+
+    /// Takes the recorded backtrace, if there is one.
+    pub(crate) fn take(&self) -> Option<Arc<Backtrace>> {
+        #[cfg(feature = "capture-backtrace")]
+        {
+            self.backtrace.lock().unwrap().take().map(Arc::new)
+        }
+        #[cfg(not(feature = "capture-backtrace"))]
+        {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "capture-backtrace")]
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = CURRENT_SLOT.try_with(|slot| {
+                if let Ok(mut slot) = slot.lock() {
+                    *slot = Some(Backtrace::force_capture());
+                }
+            });
+            previous_hook(info);
+        }));
+    });
+}
@@ -0,0 +1,80 @@
+//! Runs a subsystem future directly inside of the runner task, without spawning it.
+//!
+//! As there is no inner task that could catch a panic, panics are detected by
+//! checking whether the future gets dropped while it is being polled.
+//! Tokio catches the panic of the runner task and then drops the future, so
+//! this works without `catch_unwind`. The panic payload is lost, though.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+pin_project! {
+    pub(crate) struct InlineSubsystem<F> {
+        #[pin]
+        future: F,
+        // Set while the future is being polled, and once it is finished.
+        // If it is not set on drop, the future got cancelled.
+        settled: Arc<AtomicBool>,
+        finished: bool,
+        on_panic: Option<Box<dyn FnOnce() + Send>>,
+    }
+
+    impl<F> PinnedDrop for InlineSubsystem<F> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if this.settled.load(Ordering::Acquire) && !*this.finished {
+                // Runs before `future` gets dropped, so the subsystem
+                // is still alive while the panic gets reported.
+                if let Some(on_panic) = this.on_panic.take() {
+                    on_panic();
+                }
+            }
+        }
+    }
+}
+
+impl<F> InlineSubsystem<F> {
+    /// Wraps the given future.
+    ///
+    /// `on_panic` gets called if the future panics while being polled.
+    pub(crate) fn new(future: F, on_panic: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            future,
+            settled: Arc::new(AtomicBool::new(false)),
+            finished: false,
+            on_panic: Some(Box::new(on_panic)),
+        }
+    }
+
+    /// Whether the future finished or panicked, as opposed to being
+    /// cancelled while waiting.
+    pub(crate) fn settled(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.settled)
+    }
+}
+
+impl<F: Future> Future for InlineSubsystem<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        this.settled.store(true, Ordering::Release);
+        let result = this.future.poll(cx);
+        match result {
+            Poll::Ready(_) => *this.finished = true,
+            Poll::Pending => this.settled.store(false, Ordering::Release),
+        }
+
+        result
+    }
+}
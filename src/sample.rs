@@ -0,0 +1,107 @@
+use std::{
+    borrow::Cow,
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    sync::broadcast,
+    time::{interval, MissedTickBehavior},
+};
+
+/// The number of samples a lagging receiver can fall behind before
+/// it starts to miss samples.
+const SAMPLE_CHANNEL_CAPACITY: usize = 256;
+
+/// Produces the samples of a subsystem.
+pub(crate) type Sampler = Arc<dyn Fn() -> Sample + Send + Sync>;
+
+/// Custom metrics of a subsystem, recorded by its sampler.
+///
+/// Returned by the sampler registered through
+/// [`SubsystemBuilder::with_sampler()`](crate::SubsystemBuilder::with_sampler).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sample {
+    /// The recorded metrics, as pairs of name and value.
+    pub metrics: Vec<(Cow<'static, str>, f64)>,
+}
+
+impl Sample {
+    /// Creates an empty sample.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a metric to the sample.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the metric, for example `queue_depth`.
+    /// * `value` - The current value of the metric.
+    pub fn metric(mut self, name: impl Into<Cow<'static, str>>, value: f64) -> Self {
+        self.metrics.push((name.into(), value));
+        self
+    }
+
+    /// The value of the metric with the given name, if it got recorded.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.metrics
+            .iter()
+            .find(|(metric, _)| metric == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// A sample, together with the subsystem it belongs to.
+///
+/// Received through [`Toplevel::samples()`](crate::Toplevel::samples).
+#[derive(Clone, Debug)]
+pub struct SubsystemSample {
+    /// The name of the subsystem that got sampled.
+    pub name: Arc<str>,
+    /// The sample that got recorded.
+    pub sample: Sample,
+}
+
+/// Distributes the samples of all subsystems to all subscribers.
+///
+/// Sending never blocks; receivers that lag behind lose samples.
+#[derive(Clone)]
+pub(crate) struct Samples {
+    sender: broadcast::Sender<SubsystemSample>,
+}
+
+impl Samples {
+    pub(crate) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(SAMPLE_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    pub(crate) fn emit(&self, name: Arc<str>, sample: Sample) {
+        // Ignore errors; an error only means that nobody is listening.
+        let _ = self.sender.send(SubsystemSample { name, sample });
+    }
+
+    // Only returns by getting dropped.
+    pub(crate) async fn sample_periodically(
+        &self,
+        name: &Mutex<Arc<str>>,
+        period: Duration,
+        sampler: &Sampler,
+    ) -> Infallible {
+        let mut ticks = interval(period);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            ticks.tick().await;
+            // Read on every tick, to follow renames of the subsystem
+            let name = Arc::clone(&name.lock().unwrap());
+            self.emit(name, sampler());
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<SubsystemSample> {
+        self.sender.subscribe()
+    }
+}
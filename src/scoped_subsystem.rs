@@ -0,0 +1,70 @@
+use core::future::Future;
+
+use crate::{BoxedError, ErrTypeTraits, SubsystemHandle};
+
+/// A subsystem function that only borrows its [`SubsystemHandle`].
+///
+/// Subsystems that receive the handle by value can move it somewhere that outlives
+/// the subsystem, which is only detected at runtime by a panic. A scoped subsystem
+/// only receives a reference that is bound to the lifetime of the subsystem, so
+/// the borrow checker rejects leaking the handle at compile time.
+///
+/// This trait is implemented for all `async fn`s that take a `&SubsystemHandle`,
+/// and gets used through [`SubsystemBuilder::new_scoped()`](crate::SubsystemBuilder::new_scoped).
+///
+/// # Examples
+///
+/// ```
+/// use miette::Result;
+/// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+///
+/// async fn scoped_subsystem(subsys: &SubsystemHandle) -> Result<()> {
+///     subsys.on_shutdown_requested().await;
+///     Ok(())
+/// }
+///
+/// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+///     subsys.start(SubsystemBuilder::new_scoped("Scoped", scoped_subsystem));
+///
+///     subsys.on_shutdown_requested().await;
+///     Ok(())
+/// }
+/// ```
+///
+/// Leaking the handle does not compile:
+///
+/// ```compile_fail,E0521
+/// use std::sync::Mutex;
+///
+/// use miette::Result;
+/// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+///
+/// static LEAKED: Mutex<Option<&'static SubsystemHandle>> = Mutex::new(None);
+///
+/// async fn leaking_subsystem(subsys: &SubsystemHandle) -> Result<()> {
+///     *LEAKED.lock().unwrap() = Some(subsys);
+///     Ok(())
+/// }
+///
+/// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+///     subsys.start(SubsystemBuilder::new_scoped("Leaking", leaking_subsystem));
+///     Ok(())
+/// }
+/// ```
+pub trait ScopedSubsystem<'a, Err, ErrWrapper = BoxedError>:
+    FnOnce(&'a SubsystemHandle<ErrWrapper>) -> Self::Future
+where
+    ErrWrapper: ErrTypeTraits,
+{
+    /// The future returned by the subsystem function.
+    type Future: Future<Output = Result<(), Err>> + Send + 'a;
+}
+
+impl<'a, Err, ErrWrapper, Subsys, Fut> ScopedSubsystem<'a, Err, ErrWrapper> for Subsys
+where
+    ErrWrapper: ErrTypeTraits,
+    Subsys: FnOnce(&'a SubsystemHandle<ErrWrapper>) -> Fut,
+    Fut: Future<Output = Result<(), Err>> + Send + 'a,
+{
+    type Future = Fut;
+}
@@ -0,0 +1,18 @@
+/// How severe the errors of a subsystem are.
+///
+/// Set through [`SubsystemBuilder::error_severity()`](crate::SubsystemBuilder::error_severity),
+/// and queried through [`SubsystemError::severity()`](crate::errors::SubsystemError::severity)
+/// or [`GracefulShutdownError::max_severity()`](crate::errors::GracefulShutdownError::max_severity).
+///
+/// The variants are ordered from least to most severe, so the worst severity
+/// of multiple errors is their maximum.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    /// The error does not affect the core functionality, like a failing logging sink.
+    Warning,
+    /// A regular error. The default of every subsystem.
+    #[default]
+    Error,
+    /// The error affects the core functionality, like a failing engine.
+    Critical,
+}
@@ -0,0 +1,16 @@
+/// The order in which [`SubsystemHandle::shutdown_detached_children()`](crate::SubsystemHandle::shutdown_detached_children)
+/// shuts down the detached children of a subsystem.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ShutdownOrder {
+    /// Shut down one child after another, in the order they were started.
+    ///
+    /// Every child has to be finished before the next one receives the shutdown request.
+    Sequential,
+    /// Shut down one child after another, in the reverse order they were started.
+    ///
+    /// Useful if later children depend on earlier ones.
+    Reverse,
+    /// Shut down all children at once.
+    Concurrent,
+}
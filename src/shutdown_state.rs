@@ -0,0 +1,23 @@
+/// The phase of the program shutdown that
+/// [`Toplevel::handle_shutdown_requests()`](crate::Toplevel::handle_shutdown_requests)
+/// is currently in.
+///
+/// Observed through [`Toplevel::shutdown_state()`](crate::Toplevel::shutdown_state).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ShutdownState {
+    /// No shutdown was requested yet.
+    Running,
+    /// A shutdown was requested, but the subsystems are not awaited yet.
+    ShutdownRequested,
+    /// The shutdown is in progress and waits for the subsystems to finish.
+    WaitingForSubsystems {
+        /// The number of subsystems in the tree that are still running,
+        /// including the toplevel subsystem `/`.
+        remaining: usize,
+    },
+    /// All subsystems finished.
+    Finished,
+    /// The shutdown timed out and the remaining subsystems get cancelled.
+    TimedOut,
+}
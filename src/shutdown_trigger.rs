@@ -0,0 +1,44 @@
+use std::{fmt, sync::Arc};
+
+use crate::utils::ShutdownInitiator;
+
+/// Triggers a shutdown of the entire subsystem tree from outside of it.
+///
+/// Created through [`Toplevel::shutdown_trigger()`](crate::Toplevel::shutdown_trigger).
+///
+/// Unlike [`SubsystemHandle::request_shutdown()`](crate::SubsystemHandle::request_shutdown),
+/// this does not require access to a subsystem. [`trigger()`](ShutdownTrigger::trigger)
+/// is a plain synchronous function that can be called from any thread, even one that is
+/// not managed by tokio, like a C FFI callback or a custom signal handler.
+/// This is the intended way to initiate a shutdown from synchronous code.
+#[derive(Clone)]
+pub struct ShutdownTrigger {
+    shutdown_initiator: Arc<ShutdownInitiator>,
+}
+
+impl ShutdownTrigger {
+    pub(crate) fn new(shutdown_initiator: Arc<ShutdownInitiator>) -> Self {
+        Self { shutdown_initiator }
+    }
+
+    /// Triggers a shutdown of the entire subsystem tree.
+    ///
+    /// Does not block, and does nothing if a shutdown was already requested.
+    pub fn trigger(&self) {
+        self.shutdown_initiator.initiate();
+    }
+
+    /// Returns whether a shutdown of the subsystem tree was requested,
+    /// through this trigger or in any other way.
+    pub fn is_triggered(&self) -> bool {
+        self.shutdown_initiator.is_initiated()
+    }
+}
+
+impl fmt::Debug for ShutdownTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownTrigger")
+            .field("triggered", &self.is_triggered())
+            .finish()
+    }
+}
@@ -1,41 +1,269 @@
-/// Waits for a signal that requests a graceful shutdown, like SIGTERM or SIGINT.
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+/// The operating system signal that caused a shutdown request.
+///
+/// Passed to the callback registered through [`Toplevel::on_signal`](crate::Toplevel::on_signal).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SignalKind {
+    /// `SIGINT`; only on Unix.
+    Interrupt,
+    /// `SIGTERM`; only on Unix.
+    Terminate,
+    /// `CTRL_C`; only on Windows.
+    CtrlC,
+    /// `CTRL_BREAK`; only on Windows.
+    CtrlBreak,
+    /// `CTRL_CLOSE`; only on Windows.
+    CtrlClose,
+    /// `CTRL_SHUTDOWN`; only on Windows.
+    CtrlShutdown,
+    /// `CTRL_LOGOFF`; only on Windows.
+    CtrlLogoff,
+}
+
+/// Defines what happens with signals that are received before
+/// signal handling got armed through [`Toplevel::catch_signals_after`](crate::Toplevel::catch_signals_after).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EarlySignalPolicy {
+    /// Remember the signal and initiate the shutdown as soon as
+    /// signal handling gets armed.
+    Queue,
+    /// Ignore the signal.
+    Drop,
+}
+
+/// Allows pausing the signal handling of [`Toplevel::catch_signals()`](crate::Toplevel::catch_signals)
+/// at runtime.
+///
+/// While paused, received signals don't initiate a shutdown. Instead, they get held back
+/// and initiate the shutdown once the signal handling gets resumed.
+/// This is useful to protect critical operations, like database migrations, from being interrupted.
+///
+/// Obtained through [`Toplevel::signal_handler_control()`](crate::Toplevel::signal_handler_control)
+/// or [`SubsystemHandle::signal_handler_control()`](crate::SubsystemHandle::signal_handler_control).
+/// All clones control the same signal handling.
+///
+/// Only affects signals; shutdowns that get requested through other means happen regardless.
+#[derive(Clone, Default)]
+pub struct SignalHandlerControl {
+    inner: Arc<SignalHandlerControlInner>,
+}
+
+#[derive(Default)]
+struct SignalHandlerControlInner {
+    paused: Mutex<bool>,
+    resumed: Notify,
+}
+
+impl SignalHandlerControl {
+    /// Pauses the signal handling.
+    ///
+    /// Signals that are received from now on get held back until [`resume()`](Self::resume) is called.
+    pub fn pause(&self) {
+        *self.inner.paused.lock().unwrap() = true;
+    }
+
+    /// Resumes the signal handling.
+    ///
+    /// If a signal was received while the signal handling was paused,
+    /// the shutdown gets initiated now.
+    pub fn resume(&self) {
+        *self.inner.paused.lock().unwrap() = false;
+        self.inner.resumed.notify_waiters();
+    }
+
+    /// Whether the signal handling is currently paused.
+    pub fn is_paused(&self) -> bool {
+        *self.inner.paused.lock().unwrap()
+    }
+
+    /// Holds back the given signal while the signal handling is paused.
+    pub(crate) async fn admit(&self, signal: SignalKind) -> SignalKind {
+        let mut logged = false;
+        loop {
+            // Register before checking, to not miss a resume in between
+            let resumed = self.inner.resumed.notified();
+            if !self.is_paused() {
+                return signal;
+            }
+
+            if !logged {
+                tracing::info!(
+                    ?signal,
+                    "Signal handling is paused, delaying the signal until it gets resumed."
+                );
+                logged = true;
+            }
+            resumed.await;
+        }
+    }
+}
+
+/// Listens for signals that request a graceful shutdown, like SIGTERM or SIGINT.
 #[cfg(unix)]
-async fn wait_for_signal_impl() {
-    use tokio::signal::unix::{signal, SignalKind};
+pub(crate) struct SignalListener {
+    terminate: tokio::signal::unix::Signal,
+    interrupt: tokio::signal::unix::Signal,
+}
 
-    // Infos here:
-    // https://www.gnu.org/software/libc/manual/html_node/Termination-Signals.html
-    let mut signal_terminate = signal(SignalKind::terminate()).unwrap();
-    let mut signal_interrupt = signal(SignalKind::interrupt()).unwrap();
+#[cfg(unix)]
+impl SignalListener {
+    /// Registers the signal handlers.
+    pub(crate) fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind as UnixSignalKind};
 
-    tokio::select! {
-        _ = signal_terminate.recv() => tracing::debug!("Received SIGTERM."),
-        _ = signal_interrupt.recv() => tracing::debug!("Received SIGINT."),
-    };
+        // Infos here:
+        // https://www.gnu.org/software/libc/manual/html_node/Termination-Signals.html
+        Self {
+            terminate: signal(UnixSignalKind::terminate()).unwrap(),
+            interrupt: signal(UnixSignalKind::interrupt()).unwrap(),
+        }
+    }
+
+    /// Waits for the next signal.
+    pub(crate) async fn recv(&mut self) -> SignalKind {
+        tokio::select! {
+            _ = self.terminate.recv() => {
+                tracing::debug!("Received SIGTERM.");
+                SignalKind::Terminate
+            },
+            _ = self.interrupt.recv() => {
+                tracing::debug!("Received SIGINT.");
+                SignalKind::Interrupt
+            },
+        }
+    }
+}
+
+/// Whether a signal requests to pause or to resume the subsystem tree.
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PauseSignal {
+    /// `SIGTSTP`
+    Pause,
+    /// `SIGCONT`
+    Resume,
+}
+
+/// Listens for signals that pause or resume the subsystem tree, `SIGTSTP` and `SIGCONT`.
+#[cfg(unix)]
+pub(crate) struct PauseSignalListener {
+    stop: tokio::signal::unix::Signal,
+    cont: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl PauseSignalListener {
+    /// Registers the signal handlers.
+    ///
+    /// Note that this prevents `SIGTSTP` from stopping the process.
+    pub(crate) fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind as UnixSignalKind};
+
+        Self {
+            stop: signal(UnixSignalKind::from_raw(libc::SIGTSTP)).unwrap(),
+            cont: signal(UnixSignalKind::from_raw(libc::SIGCONT)).unwrap(),
+        }
+    }
+
+    /// Waits for the next signal.
+    pub(crate) async fn recv(&mut self) -> PauseSignal {
+        tokio::select! {
+            _ = self.stop.recv() => {
+                tracing::debug!("Received SIGTSTP.");
+                PauseSignal::Pause
+            },
+            _ = self.cont.recv() => {
+                tracing::debug!("Received SIGCONT.");
+                PauseSignal::Resume
+            },
+        }
+    }
+}
+
+/// Listens for signals that request a graceful shutdown, like Ctrl-C.
+#[cfg(windows)]
+pub(crate) struct SignalListener {
+    ctrl_c: tokio::signal::windows::CtrlC,
+    ctrl_break: tokio::signal::windows::CtrlBreak,
+    ctrl_close: tokio::signal::windows::CtrlClose,
+    ctrl_shutdown: tokio::signal::windows::CtrlShutdown,
+    ctrl_logoff: tokio::signal::windows::CtrlLogoff,
 }
 
-/// Waits for a signal that requests a graceful shutdown, Ctrl-C (SIGINT).
 #[cfg(windows)]
-async fn wait_for_signal_impl() {
-    use tokio::signal::windows;
+impl SignalListener {
+    /// Registers the signal handlers.
+    pub(crate) fn new() -> Self {
+        use tokio::signal::windows;
 
-    // Infos here:
-    // https://learn.microsoft.com/en-us/windows/console/handlerroutine
-    let mut signal_c = windows::ctrl_c().unwrap();
-    let mut signal_break = windows::ctrl_break().unwrap();
-    let mut signal_close = windows::ctrl_close().unwrap();
-    let mut signal_shutdown = windows::ctrl_shutdown().unwrap();
+        // Infos here:
+        // https://learn.microsoft.com/en-us/windows/console/handlerroutine
+        Self {
+            ctrl_c: windows::ctrl_c().unwrap(),
+            ctrl_break: windows::ctrl_break().unwrap(),
+            ctrl_close: windows::ctrl_close().unwrap(),
+            ctrl_shutdown: windows::ctrl_shutdown().unwrap(),
+            ctrl_logoff: windows::ctrl_logoff().unwrap(),
+        }
+    }
 
-    tokio::select! {
-        _ = signal_c.recv() => tracing::debug!("Received CTRL_C."),
-        _ = signal_break.recv() => tracing::debug!("Received CTRL_BREAK."),
-        _ = signal_close.recv() => tracing::debug!("Received CTRL_CLOSE."),
-        _ = signal_shutdown.recv() => tracing::debug!("Received CTRL_SHUTDOWN."),
-    };
+    /// Waits for the next signal.
+    pub(crate) async fn recv(&mut self) -> SignalKind {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => {
+                tracing::debug!("Received CTRL_C.");
+                SignalKind::CtrlC
+            },
+            _ = self.ctrl_break.recv() => {
+                tracing::debug!("Received CTRL_BREAK.");
+                SignalKind::CtrlBreak
+            },
+            _ = self.ctrl_close.recv() => {
+                tracing::debug!("Received CTRL_CLOSE.");
+                SignalKind::CtrlClose
+            },
+            _ = self.ctrl_shutdown.recv() => {
+                tracing::debug!("Received CTRL_SHUTDOWN.");
+                SignalKind::CtrlShutdown
+            },
+            _ = self.ctrl_logoff.recv() => {
+                tracing::debug!("Received CTRL_LOGOFF.");
+                SignalKind::CtrlLogoff
+            },
+        }
+    }
 }
 
-/// Registers signal handlers and waits for a signal that
+/// Waits until `gate` is finished, then waits for a signal that
 /// indicates a shutdown request.
-pub(crate) async fn wait_for_signal() {
-    wait_for_signal_impl().await
+///
+/// Signals that arrive while waiting for `gate` are handled
+/// according to `policy`.
+pub(crate) async fn wait_for_signal_after(
+    mut listener: SignalListener,
+    gate: impl std::future::Future<Output = ()>,
+    policy: EarlySignalPolicy,
+) -> SignalKind {
+    tokio::pin!(gate);
+
+    loop {
+        tokio::select! {
+            _ = &mut gate => break,
+            signal = listener.recv() => match policy {
+                EarlySignalPolicy::Queue => {
+                    gate.await;
+                    return signal;
+                }
+                EarlySignalPolicy::Drop => {
+                    tracing::debug!("Signal handling is not armed yet, ignoring signal.");
+                }
+            },
+        }
+    }
+
+    listener.recv().await
 }
@@ -0,0 +1,112 @@
+use crate::SubsystemHandle;
+
+use pin_project_lite::pin_project;
+
+use tokio_stream::Stream;
+use tokio_util::sync::WaitForCancellationFuture;
+
+pin_project! {
+    /// A stream that yields the items of the corresponding stream
+    /// until a shutdown is initiated.
+    #[must_use = "streams do nothing unless polled"]
+    pub struct TakeUntilShutdown<'a, S: Stream>{
+        #[pin]
+        stream: S,
+        #[pin]
+        cancellation: WaitForCancellationFuture<'a>,
+        shutdown_requested: bool,
+    }
+}
+
+impl<S: Stream> Stream for TakeUntilShutdown<'_, S> {
+    type Item = S::Item;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::future::Future;
+        use std::task::Poll;
+
+        let mut this = self.project();
+
+        // Once the shutdown was observed, the stream stays terminated
+        if *this.shutdown_requested {
+            return Poll::Ready(None);
+        }
+
+        // End the stream if there is a shutdown
+        match this.cancellation.as_mut().poll(cx) {
+            Poll::Ready(()) => {
+                *this.shutdown_requested = true;
+                return Poll::Ready(None);
+            }
+            Poll::Pending => (),
+        }
+
+        // If there is no shutdown, forward the next item
+        this.stream.poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.shutdown_requested {
+            (0, Some(0))
+        } else {
+            // The shutdown could end the stream at any point
+            (0, self.stream.size_hint().1)
+        }
+    }
+}
+
+/// Extends the [Stream] trait with useful utility functions.
+pub trait StreamExt {
+    /// The type of the stream.
+    type Stream: Stream;
+
+    /// Ends the stream when a shutdown is initiated.
+    ///
+    /// ## Returns
+    ///
+    /// A stream that yields the items of the original stream, until either
+    /// the original stream ends or a shutdown happened.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The [SubsystemHandle] to receive the shutdown request from.
+    ///
+    /// # Examples
+    /// ```
+    /// use miette::Result;
+    /// use std::pin::pin;
+    /// use tokio_graceful_shutdown::{StreamExt as _, SubsystemHandle};
+    /// use tokio_stream::{wrappers::IntervalStream, StreamExt as _};
+    /// use tokio::time::{interval, Duration};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let mut ticks = pin!(IntervalStream::new(interval(Duration::from_secs(1)))
+    ///         .take_until_shutdown(&subsys));
+    ///
+    ///     while let Some(tick) = ticks.next().await {
+    ///         println!("Tick at {:?}", tick);
+    ///     }
+    ///
+    ///     println!("Stream ended by shutdown.");
+    ///     Ok(())
+    /// }
+    /// ```
+    fn take_until_shutdown(self, subsys: &SubsystemHandle) -> TakeUntilShutdown<'_, Self::Stream>;
+}
+
+impl<S: Stream> StreamExt for S {
+    type Stream = S;
+
+    fn take_until_shutdown(self, subsys: &SubsystemHandle) -> TakeUntilShutdown<'_, S> {
+        let cancellation = subsys.get_cancellation_token().cancelled();
+
+        TakeUntilShutdown {
+            stream: self,
+            cancellation,
+            shutdown_requested: false,
+        }
+    }
+}
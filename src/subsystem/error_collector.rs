@@ -29,6 +29,20 @@ impl<ErrType: ErrTypeTraits> ErrorCollector<ErrType> {
             ErrorCollector::Finished(errors) => Arc::clone(errors),
         }
     }
+
+    /// Takes all errors that got collected so far, instead of sharing them.
+    pub(crate) fn take(&mut self) -> Box<[SubsystemError<ErrType>]> {
+        match self {
+            ErrorCollector::Collecting(receiver) => {
+                let mut errors = vec![];
+                while let Ok(e) = receiver.try_recv() {
+                    errors.push(e);
+                }
+                errors.into_boxed_slice()
+            }
+            ErrorCollector::Finished(_) => Box::new([]),
+        }
+    }
 }
 
 impl<ErrType: ErrTypeTraits> Drop for ErrorCollector<ErrType> {
@@ -36,7 +50,7 @@ impl<ErrType: ErrTypeTraits> Drop for ErrorCollector<ErrType> {
         if let Self::Collecting(receiver) = self {
             receiver.close();
             while let Ok(e) = receiver.try_recv() {
-                tracing::warn!("An error got dropped: {e:?}");
+                tracing::warn!(error = ?e, "An error got dropped.");
             }
         }
     }
@@ -8,12 +8,8 @@ fn normal() {
     let (sender, receiver) = mpsc::unbounded_channel();
     let mut error_collector = ErrorCollector::<String>::new(receiver);
 
-    sender
-        .send(SubsystemError::Panicked(Arc::from("ABC")))
-        .unwrap();
-    sender
-        .send(SubsystemError::Panicked(Arc::from("def")))
-        .unwrap();
+    sender.send(SubsystemError::Panicked("ABC".into())).unwrap();
+    sender.send(SubsystemError::Panicked("def".into())).unwrap();
 
     let received = error_collector.finish();
     assert_eq!(
@@ -28,12 +24,8 @@ fn double_finish() {
     let (sender, receiver) = mpsc::unbounded_channel();
     let mut error_collector = ErrorCollector::<String>::new(receiver);
 
-    sender
-        .send(SubsystemError::Panicked(Arc::from("ABC")))
-        .unwrap();
-    sender
-        .send(SubsystemError::Panicked(Arc::from("def")))
-        .unwrap();
+    sender.send(SubsystemError::Panicked("ABC".into())).unwrap();
+    sender.send(SubsystemError::Panicked("def".into())).unwrap();
 
     let received = error_collector.finish();
     assert_eq!(
@@ -54,15 +46,15 @@ fn no_finish() {
     let (sender, receiver) = mpsc::unbounded_channel();
     let error_collector = ErrorCollector::<String>::new(receiver);
 
-    sender
-        .send(SubsystemError::Panicked(Arc::from("ABC")))
-        .unwrap();
-    sender
-        .send(SubsystemError::Panicked(Arc::from("def")))
-        .unwrap();
+    sender.send(SubsystemError::Panicked("ABC".into())).unwrap();
+    sender.send(SubsystemError::Panicked("def".into())).unwrap();
 
     drop(error_collector);
 
-    assert!(logs_contain("An error got dropped: Panicked(\"ABC\")"));
-    assert!(logs_contain("An error got dropped: Panicked(\"def\")"));
+    assert!(logs_contain(
+        "An error got dropped. error=Panicked(\"ABC\")"
+    ));
+    assert!(logs_contain(
+        "An error got dropped. error=Panicked(\"def\")"
+    ));
 }
@@ -0,0 +1,92 @@
+use std::{future::Future, panic};
+
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, oneshot},
+    task::{self, LocalSet},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{ErrTypeTraits, SubsystemHandle};
+
+type LocalJob = Box<dyn FnOnce() + Send>;
+
+/// The [`LocalSet`] that runs the `!Send` children of a subsystem.
+///
+/// The set gets driven by a dedicated thread on the runtime of the tree.
+/// It keeps running until it got dropped and all of its tasks are finished.
+pub(crate) struct SubsystemLocalSet {
+    jobs: mpsc::UnboundedSender<LocalJob>,
+}
+
+impl SubsystemLocalSet {
+    pub(crate) fn new(name: &str, runtime: Handle) -> Self {
+        let (jobs, mut job_receiver) = mpsc::unbounded_channel::<LocalJob>();
+
+        std::thread::Builder::new()
+            .name(format!("local:{name}"))
+            .spawn(move || {
+                let local_set = LocalSet::new();
+                runtime.block_on(async {
+                    local_set
+                        .run_until(async {
+                            while let Some(job) = job_receiver.recv().await {
+                                job();
+                            }
+                        })
+                        .await;
+                    local_set.await;
+                });
+            })
+            .expect("a thread for the local subsystems should be spawned");
+
+        Self { jobs }
+    }
+
+    /// Runs `job` inside of the set, where it can use [`spawn_local()`](task::spawn_local).
+    fn run(&self, job: impl FnOnce() + Send + 'static) {
+        self.jobs
+            .send(Box::new(job))
+            .expect("the thread of the local subsystems should be running");
+    }
+}
+
+/// Runs a subsystem with a `!Send` future inside of the given [`SubsystemLocalSet`].
+///
+/// Panics of the subsystem get forwarded to the caller, so they are
+/// handled like the panics of any other subsystem.
+pub(crate) async fn run_local<ErrType, Err, Fut, Subsys>(
+    local_set: &SubsystemLocalSet,
+    subsys: SubsystemHandle<ErrType>,
+    subsystem: Subsys,
+) -> Result<(), Err>
+where
+    ErrType: ErrTypeTraits,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+    Fut: 'static + Future<Output = Result<(), Err>>,
+    Err: 'static + Send,
+{
+    let (result_sender, result_receiver) = oneshot::channel();
+
+    // Stops the subsystem if this future gets dropped, for example on a shutdown timeout
+    let abort = CancellationToken::new();
+    let _abort_guard = abort.clone().drop_guard();
+
+    local_set.run(move || {
+        let mut subsystem_task = task::spawn_local(subsystem(subsys));
+        task::spawn_local(async move {
+            tokio::select! {
+                result = &mut subsystem_task => {
+                    result_sender.send(result).ok();
+                }
+                _ = abort.cancelled() => subsystem_task.abort(),
+            }
+        });
+    });
+
+    match result_receiver.await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) if e.is_panic() => panic::resume_unwind(e.into_panic()),
+        _ => panic!("The local subsystem got dropped without a result"),
+    }
+}
@@ -1,23 +1,49 @@
 mod error_collector;
+mod local_subsystem;
 mod nested_subsystem;
 mod subsystem_builder;
 mod subsystem_finished_future;
 mod subsystem_handle;
+mod subsystem_pool;
+mod subsystem_span;
+mod subsystem_tree_builder;
+mod supervised_subsystem;
+mod supervisor;
+#[cfg(feature = "test-util")]
+mod test_controller;
+mod weak_nested_subsystem;
+mod wrapped_subsystem;
 
 use std::{
     future::Future,
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc, Mutex, Weak,
+    },
 };
 
 pub use subsystem_builder::SubsystemBuilder;
 pub use subsystem_handle::SubsystemHandle;
+pub use subsystem_tree_builder::{SubsystemTreeBuilder, SubsystemTreeNode};
+#[cfg(feature = "test-util")]
+pub use test_controller::TestController;
 
+pub(crate) use local_subsystem::{run_local, SubsystemLocalSet};
+pub(crate) use subsystem_builder::{filter_failure, map_error};
 pub(crate) use subsystem_handle::root_handle;
+pub(crate) use subsystem_pool::run_pool;
+pub(crate) use subsystem_span::subsystem_span;
+pub(crate) use supervised_subsystem::run_supervised;
+pub(crate) use supervisor::Supervisor;
+pub(crate) use wrapped_subsystem::WrappedSubsystem;
 
-use crate::{utils::JoinerTokenRef, BoxedError, ErrTypeTraits, ErrorAction};
+use crate::{
+    error_action::ErrorHistory, errors::SubsystemError, tree_snapshot::TreeNode,
+    utils::JoinerTokenRef, BoxedError, ErrTypeTraits, ErrorAction, SubsystemOutcome,
+};
 
-use atomic::Atomic;
+use tokio::sync::{watch, Notify};
 use tokio_util::sync::CancellationToken;
 
 /// A nested subsystem.
@@ -30,15 +56,97 @@ use tokio_util::sync::CancellationToken;
 /// For more information, look through the examples directory in
 /// the source code.
 pub struct NestedSubsystem<ErrType: ErrTypeTraits = BoxedError> {
+    name: Arc<Mutex<Arc<str>>>,
     joiner: JoinerTokenRef,
     cancellation_token: CancellationToken,
     errors: Mutex<error_collector::ErrorCollector<ErrType>>,
     error_actions: Arc<ErrorActions>,
+    runner: tokio::task::AbortHandle,
+    restart_trigger: Option<Arc<Notify>>,
+    shutdown_acknowledged: Arc<AtomicBool>,
+    ready: Arc<watch::Sender<bool>>,
+    outcomes: Option<Arc<OutcomeLog>>,
+    tree_node: Arc<TreeNode>,
+}
+
+/// The detailed result of joining a subsystem.
+///
+/// Returned by [`NestedSubsystem::join_detailed`].
+#[derive(Debug)]
+pub struct SubsystemJoinReport<ErrType: ErrTypeTraits = BoxedError> {
+    /// The absolute names of all subsystems that finished successfully,
+    /// out of the joined subsystem and all of its descendants.
+    pub succeeded: Vec<Arc<str>>,
+    /// The absolute names of all subsystems that failed or panicked,
+    /// out of the joined subsystem and all of its descendants.
+    ///
+    /// Also contains subsystems whose errors got caught on the way.
+    pub failed: Vec<Arc<str>>,
+    /// The errors that reached the joined subsystem,
+    /// like they would be returned by [`NestedSubsystem::join`].
+    pub errors: Arc<[SubsystemError<ErrType>]>,
+    outcomes: Vec<(Arc<str>, SubsystemOutcome)>,
+}
+
+/// Records the outcomes of a subsystem and all of its descendants,
+/// together with their absolute names.
+#[derive(Default)]
+pub(crate) struct OutcomeLog(Mutex<Vec<(Arc<str>, SubsystemOutcome)>>);
+
+impl OutcomeLog {
+    /// Records the outcome of a subsystem in all of the given logs that still exist.
+    pub(crate) fn record(logs: &[Weak<OutcomeLog>], name: &Arc<str>, outcome: SubsystemOutcome) {
+        for log in logs.iter().filter_map(Weak::upgrade) {
+            log.0.lock().unwrap().push((Arc::clone(name), outcome));
+        }
+    }
 }
 
 pub(crate) struct ErrorActions {
-    pub(crate) on_failure: Atomic<ErrorAction>,
-    pub(crate) on_panic: Atomic<ErrorAction>,
+    pub(crate) on_failure: Mutex<ErrorAction>,
+    pub(crate) on_panic: Mutex<ErrorAction>,
+    failure_history: ErrorHistory,
+    panic_history: ErrorHistory,
+}
+
+impl ErrorActions {
+    pub(crate) fn new(on_failure: ErrorAction, on_panic: ErrorAction) -> Self {
+        Self {
+            on_failure: Mutex::new(on_failure),
+            on_panic: Mutex::new(on_panic),
+            failure_history: Default::default(),
+            panic_history: Default::default(),
+        }
+    }
+
+    /// Determines how the given error should be handled.
+    ///
+    /// Never returns [`ErrorAction::CatchUntilThreshold`]; it gets
+    /// resolved to one of the other actions.
+    pub(crate) fn resolve<ErrType: ErrTypeTraits>(
+        &self,
+        error: &SubsystemError<ErrType>,
+    ) -> ErrorAction {
+        let (action, history) = match error {
+            SubsystemError::Failed(_, _) => {
+                (*self.on_failure.lock().unwrap(), &self.failure_history)
+            }
+            SubsystemError::Panicked(_) => (*self.on_panic.lock().unwrap(), &self.panic_history),
+            // Cancellations only get reported by the toplevel, never by the subsystems.
+            SubsystemError::Cancelled(_) => return ErrorAction::Forward,
+        };
+
+        match action {
+            ErrorAction::CatchUntilThreshold { count, window } => {
+                if history.record(count, window) {
+                    ErrorAction::Forward
+                } else {
+                    ErrorAction::CatchAndLocalShutdown
+                }
+            }
+            action => action,
+        }
+    }
 }
 
 /// A future that is resolved once the corresponding subsystem is finished.
@@ -48,3 +156,37 @@ pub(crate) struct ErrorActions {
 pub struct SubsystemFinishedFuture {
     future: Pin<Box<dyn Future<Output = ()> + Send + Sync>>,
 }
+
+/// A weak reference to a nested subsystem.
+///
+/// Created through [`NestedSubsystem::downgrade`].
+///
+/// Only allows observing the subsystem; it cannot be used to control it.
+/// Holding this value does not keep any resources of the subsystem alive.
+#[derive(Clone)]
+pub struct WeakNestedSubsystem {
+    name: Arc<Mutex<Arc<str>>>,
+    joiner: JoinerTokenRef,
+}
+
+/// A pool of identical worker subsystems.
+///
+/// Created through [`SubsystemHandle::spawn_pool()`].
+///
+/// The pool keeps the requested number of workers alive and respawns
+/// workers that finish, until a shutdown is requested.
+///
+/// Dropping this value does not perform any action - the pool
+/// will be neither cancelled nor shut down, and keeps its current size.
+pub struct SubsystemPool<ErrType: ErrTypeTraits = BoxedError> {
+    pool: NestedSubsystem<ErrType>,
+    size: watch::Sender<usize>,
+}
+
+/// A subsystem that gets restarted automatically once it fails.
+///
+/// Created through [`SubsystemHandle::spawn_supervised()`].
+pub struct SupervisedSubsystem<ErrType: ErrTypeTraits = BoxedError> {
+    subsystem: NestedSubsystem<ErrType>,
+    restarts: Arc<AtomicU64>,
+}
@@ -1,8 +1,18 @@
-use std::sync::atomic::Ordering;
+use std::{
+    future::Future,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
-use crate::{errors::SubsystemJoinError, ErrTypeTraits, ErrorAction};
+use tokio::time::error::Elapsed;
 
-use super::{NestedSubsystem, SubsystemFinishedFuture};
+use crate::{
+    errors::{NotRestartable, SubsystemError, SubsystemJoinError},
+    runner::CancelReason,
+    ErrTypeTraits, ErrorAction, HealthStatus, SubsystemOutcome,
+};
+
+use super::{NestedSubsystem, SubsystemFinishedFuture, SubsystemJoinReport, WeakNestedSubsystem};
 
 impl<ErrType: ErrTypeTraits> NestedSubsystem<ErrType> {
     /// Wait for the subsystem to be finished.
@@ -60,19 +70,142 @@ impl<ErrType: ErrTypeTraits> NestedSubsystem<ErrType> {
         }
     }
 
+    /// Wait for the subsystem to be finished, and report the outcome of
+    /// the subsystem and all of its descendants.
+    ///
+    /// Unlike [`join`](NestedSubsystem::join), this also lists the subsystems that
+    /// finished successfully, which is useful for batch jobs where a partial success matters.
+    ///
+    /// # Returns
+    ///
+    /// A [`SubsystemJoinReport`] with the outcomes of all subsystems.
+    pub async fn join_detailed(&self) -> SubsystemJoinReport<ErrType> {
+        let errors = match self.join().await {
+            Ok(()) => Arc::from([]),
+            Err(SubsystemJoinError::SubsystemsFailed(errors)) => errors,
+        };
+
+        let outcomes = self
+            .outcomes
+            .as_ref()
+            .map(|outcomes| outcomes.0.lock().unwrap().clone())
+            .unwrap_or_default();
+
+        let mut succeeded = Vec::new();
+        let mut failed = Vec::new();
+        for (name, outcome) in &outcomes {
+            if *outcome == SubsystemOutcome::Succeeded {
+                succeeded.push(Arc::clone(name));
+            } else {
+                failed.push(Arc::clone(name));
+            }
+        }
+
+        SubsystemJoinReport {
+            succeeded,
+            failed,
+            errors,
+            outcomes,
+        }
+    }
+
+    pub(crate) fn name(&self) -> Arc<str> {
+        Arc::clone(&self.name.lock().unwrap())
+    }
+
+    /// Observes the number of running descendants of this subsystem.
+    pub(crate) fn watch_descendants(&self) -> tokio::sync::watch::Receiver<(bool, u32)> {
+        self.joiner.subscribe()
+    }
+
+    /// Stops recording the outcomes of the subsystem and its descendants,
+    /// for subsystems that never get joined through [`join_detailed`](NestedSubsystem::join_detailed).
+    pub(crate) fn forget_outcomes(&mut self) {
+        self.outcomes = None;
+    }
+
+    /// Wait for the subsystem to be finished, but at most for the given duration.
+    ///
+    /// Behaves like [`join`](NestedSubsystem::join), but gives up once the
+    /// timeout elapses. A timeout does not affect the subsystem in any way;
+    /// it keeps running and can still be joined or shut down afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for the subsystem to finish.
+    ///
+    /// # Returns
+    ///
+    /// [`Elapsed`] if the timeout expired, otherwise the result of [`join`](NestedSubsystem::join).
+    pub async fn join_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Result<(), SubsystemJoinError<ErrType>>, Elapsed> {
+        tokio::time::timeout(timeout, self.join()).await
+    }
+
     /// Signals the subsystem and all of its children to shut down.
     pub fn initiate_shutdown(&self) {
         self.cancellation_token.cancel()
     }
 
+    /// Returns whether the subsystem has observed the shutdown request,
+    /// meaning whether [`on_shutdown_requested()`](crate::SubsystemHandle::on_shutdown_requested)
+    /// returned or [`is_shutdown_requested()`](crate::SubsystemHandle::is_shutdown_requested)
+    /// returned `true` inside of the subsystem.
+    ///
+    /// This helps to distinguish a subsystem that is busy cleaning up from
+    /// a subsystem that is stuck and never noticed the shutdown request.
+    pub fn shutdown_acknowledged(&self) -> bool {
+        self.shutdown_acknowledged.load(Ordering::Acquire)
+    }
+
+    /// Returns a future that resolves once the subsystem is ready.
+    ///
+    /// A subsystem is ready once it called
+    /// [`SubsystemHandle::mark_ready()`](crate::SubsystemHandle::mark_ready).
+    /// If the subsystem finishes without marking itself as ready, the future never resolves.
+    ///
+    /// See [`SubsystemBuilder::start_after()`](crate::SubsystemBuilder::start_after)
+    /// for how to delay the start of another subsystem until then.
+    pub fn ready(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut ready = self.ready.subscribe();
+        async move {
+            if ready.wait_for(|ready| *ready).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Returns the worst health reported by this subsystem or any of its descendants
+    /// that are still running.
+    ///
+    /// Stays at the last reported health once the subsystem is finished.
+    ///
+    /// For more information, see [`SubsystemHandle::set_health()`](crate::SubsystemHandle::set_health).
+    pub fn health(&self) -> HealthStatus {
+        self.tree_node.aggregate_health()
+    }
+
+    /// Returns how the subsystem ended.
+    ///
+    /// Returns `None` while the subsystem or any of its children are still running.
+    /// Unlike [`join`](NestedSubsystem::join), this also works for subsystems whose
+    /// errors get forwarded to their parent.
+    pub fn outcome(&self) -> Option<SubsystemOutcome> {
+        if self.joiner.is_finished() {
+            self.tree_node.outcome()
+        } else {
+            None
+        }
+    }
+
     /// Changes the way this subsystem should react to failures,
     /// meaning if it or one of its children returns an `Err` value.
     ///
     /// For more information, see [`ErrorAction`].
     pub fn change_failure_action(&self, action: ErrorAction) {
-        self.error_actions
-            .on_failure
-            .store(action, Ordering::Relaxed);
+        *self.error_actions.on_failure.lock().unwrap() = action;
     }
 
     /// Changes the way this subsystem should react if it or one
@@ -80,7 +213,7 @@ impl<ErrType: ErrTypeTraits> NestedSubsystem<ErrType> {
     ///
     /// For more information, see [`ErrorAction`].
     pub fn change_panic_action(&self, action: ErrorAction) {
-        self.error_actions.on_panic.store(action, Ordering::Relaxed);
+        *self.error_actions.on_panic.lock().unwrap() = action;
     }
 
     /// Returns a future that resolves once the subsystem is finished.
@@ -90,4 +223,81 @@ impl<ErrType: ErrTypeTraits> NestedSubsystem<ErrType> {
     pub fn finished(&self) -> SubsystemFinishedFuture {
         SubsystemFinishedFuture::new(self.joiner.clone())
     }
+
+    /// Returns the [`tokio::task::Id`] of the runner task of this subsystem.
+    ///
+    /// Every subsystem has its own runner task. Subsystems without
+    /// [`no_panic_isolation()`](crate::SubsystemBuilder::no_panic_isolation) run
+    /// their function in a further task that gets spawned by the runner task.
+    ///
+    /// Useful for correlating subsystems with the tasks shown in
+    /// debugging tools like [tokio-console](https://github.com/tokio-rs/console).
+    pub fn task_id(&self) -> tokio::task::Id {
+        self.runner.id()
+    }
+
+    /// Restarts the subsystem.
+    ///
+    /// Cancels the currently running instance of the subsystem including
+    /// all of its children, and starts a fresh one with the same name and configuration.
+    /// Has no effect if a shutdown of the subsystem was already requested.
+    ///
+    /// Only works for subsystems that were started with
+    /// [`SubsystemBuilder::restartable`](crate::SubsystemBuilder::restartable).
+    ///
+    /// # Returns
+    ///
+    /// [`NotRestartable`] if the subsystem was not started as restartable.
+    pub fn restart(&self) -> Result<(), NotRestartable> {
+        let restart_trigger = self.restart_trigger.as_ref().ok_or(NotRestartable)?;
+        restart_trigger.notify_one();
+        Ok(())
+    }
+
+    /// Creates a [`WeakNestedSubsystem`] that can observe this subsystem.
+    ///
+    /// Useful for monitoring subsystems that should not influence
+    /// the lifecycle of the subsystem they observe.
+    pub fn downgrade(&self) -> WeakNestedSubsystem {
+        WeakNestedSubsystem {
+            name: Arc::clone(&self.name),
+            joiner: self.joiner.clone(),
+        }
+    }
+
+    /// Takes the errors caught by the subsystem so far, instead of returning them at
+    /// [`join`](NestedSubsystem::join).
+    pub(crate) fn take_errors(&self) -> Box<[SubsystemError<ErrType>]> {
+        self.errors.lock().unwrap().take()
+    }
+
+    /// Cancels the subsystem and all of its children immediately.
+    pub(crate) fn abort(&self) {
+        self.tree_node.set_cancel_reason(CancelReason::Aborted);
+        self.runner.abort();
+    }
+}
+
+impl<ErrType: ErrTypeTraits> SubsystemJoinReport<ErrType> {
+    /// Looks up how the subsystem with the given absolute name ended.
+    ///
+    /// Useful for asserting the outcome of individual subsystems in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The absolute name of the subsystem, like `/subsys/nested`.
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the subsystem, or `None` if no subsystem with this name
+    /// finished within the joined subsystem.
+    /// If the subsystem ran multiple times, for example because it got restarted,
+    /// the outcome of its last run.
+    pub fn outcome_of(&self, name: &str) -> Option<SubsystemOutcome> {
+        self.outcomes
+            .iter()
+            .rev()
+            .find(|(outcome_name, _)| &**outcome_name == name)
+            .map(|(_, outcome)| *outcome)
+    }
 }
@@ -1,6 +1,29 @@
-use std::{borrow::Cow, future::Future, marker::PhantomData};
+use std::{
+    any::Any, borrow::Cow, future::Future, marker::PhantomData, pin::Pin, sync::Arc, time::Duration,
+};
 
-use crate::{ErrTypeTraits, ErrorAction, SubsystemHandle};
+use tokio::sync::Notify;
+
+use crate::{
+    errors::ExitedTooQuickly,
+    runner::{Finalizer, PanicMapper, RunnerHooks, RunnerOptions},
+    sample::Sampler,
+    ErrTypeTraits, ErrorAction, NestedSubsystem, Sample, ScopedSubsystem, Severity,
+    SubsystemFinishedFuture, SubsystemHandle, SupervisionStrategy,
+};
+
+use super::{run_local, ErrorActions, SubsystemLocalSet};
+
+pub(crate) type BoxedSubsystemFuture<Err> =
+    Pin<Box<dyn Future<Output = Result<(), Err>> + Send + 'static>>;
+pub(crate) type BoxedSubsystem<ErrType, Err> =
+    Box<dyn FnOnce(SubsystemHandle<ErrType>) -> BoxedSubsystemFuture<Err> + Send + 'static>;
+/// Resolves once a subsystem that has to start first is ready.
+pub(crate) type ReadyFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+pub(crate) type ErrorMapper<ErrType, Err> = Arc<dyn Fn(Err) -> ErrType + Send + Sync>;
+pub(crate) type FailureFilter<ErrType> = Arc<dyn Fn(&ErrType) -> bool + Send + Sync>;
+/// The minimum runtime and how to convert a violation of it into an error.
+pub(crate) type MinRuntime<ErrType> = (Duration, fn(ExitedTooQuickly) -> ErrType);
 
 /// Configures a subsystem before it gets spawned through
 /// [`SubsystemHandle::start`].
@@ -8,7 +31,7 @@ pub struct SubsystemBuilder<'a, ErrType, Err, Fut, Subsys>
 where
     ErrType: ErrTypeTraits,
     Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
-    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Fut: 'static + Future<Output = Result<(), Err>>,
     Err: Into<ErrType>,
 {
     pub(crate) name: Cow<'a, str>,
@@ -16,6 +39,25 @@ where
     pub(crate) failure_action: ErrorAction,
     pub(crate) panic_action: ErrorAction,
     pub(crate) detached: bool,
+    pub(crate) root_linked: bool,
+    pub(crate) quiet_cancel: bool,
+    pub(crate) panic_isolation: bool,
+    pub(crate) severity: Severity,
+    pub(crate) restart_trigger: Option<Arc<Notify>>,
+    pub(crate) panic_mapper: Option<PanicMapper<ErrType>>,
+    pub(crate) finalizer: Option<Finalizer>,
+    pub(crate) error_mapper: Option<ErrorMapper<ErrType, Err>>,
+    pub(crate) failure_filter: Option<FailureFilter<ErrType>>,
+    pub(crate) shutdown_dependencies: Vec<SubsystemFinishedFuture>,
+    pub(crate) startup_dependencies: Vec<ReadyFuture>,
+    pub(crate) failure_log_limit: Option<(usize, Duration)>,
+    pub(crate) span_fields: Vec<(&'static str, String)>,
+    pub(crate) min_runtime: Option<MinRuntime<ErrType>>,
+    pub(crate) children_outlive_parent: bool,
+    pub(crate) groups: Vec<Arc<str>>,
+    pub(crate) supervision: Option<SupervisionStrategy>,
+    pub(crate) sampler: Option<(Duration, Sampler)>,
+    pub(crate) shutdown_authority: bool,
     #[allow(clippy::type_complexity)]
     _phantom: PhantomData<fn() -> (Fut, ErrType, Err)>,
 }
@@ -24,7 +66,7 @@ impl<'a, ErrType, Err, Fut, Subsys> SubsystemBuilder<'a, ErrType, Err, Fut, Subs
 where
     ErrType: ErrTypeTraits,
     Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
-    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Fut: 'static + Future<Output = Result<(), Err>>,
     Err: Into<ErrType>,
 {
     /// Creates a new SubsystemBuilder from a given subsystem
@@ -33,7 +75,7 @@ where
     /// # Arguments
     ///
     /// * `name` - The name of the subsystem. Primarily to identify the
-    ///            subsystem in error messages.
+    ///   subsystem in error messages.
     /// * `subsystem` - The subsystem function that the subsystem will execute.
     pub fn new(name: impl Into<Cow<'a, str>>, subsystem: Subsys) -> Self {
         Self {
@@ -42,6 +84,25 @@ where
             failure_action: ErrorAction::Forward,
             panic_action: ErrorAction::Forward,
             detached: false,
+            root_linked: false,
+            quiet_cancel: false,
+            panic_isolation: true,
+            severity: Severity::default(),
+            restart_trigger: None,
+            panic_mapper: None,
+            finalizer: None,
+            error_mapper: None,
+            failure_filter: None,
+            shutdown_dependencies: Vec::new(),
+            startup_dependencies: Vec::new(),
+            failure_log_limit: None,
+            span_fields: Vec::new(),
+            min_runtime: None,
+            children_outlive_parent: false,
+            groups: Vec::new(),
+            supervision: None,
+            sampler: None,
+            shutdown_authority: true,
             _phantom: Default::default(),
         }
     }
@@ -78,4 +139,760 @@ where
         self.detached = true;
         self
     }
+
+    /// Links the shutdown of the subsystem to the toplevel instead of the parent.
+    ///
+    /// The subsystem ignores local shutdown requests of its parent, for example
+    /// through [`initiate_shutdown()`](crate::NestedSubsystem::initiate_shutdown)
+    /// or [`ErrorAction::CatchAndLocalShutdown`], and only shuts down once the
+    /// entire program shuts down.
+    ///
+    /// Unlike [`detached()`](Self::detached), errors still get forwarded to the parent,
+    /// and the parent still waits for the subsystem to finish.
+    pub fn root_linked(mut self) -> Self {
+        self.root_linked = true;
+        self
+    }
+
+    /// Delays the shutdown of this subsystem until the given subsystem is finished.
+    ///
+    /// Once a shutdown is requested, this subsystem will only receive the shutdown
+    /// request after `other` and all of its children are finished. Can be called multiple
+    /// times to wait for multiple subsystems.
+    ///
+    /// As `other` has to be started before this subsystem, circular dependencies are impossible.
+    ///
+    /// This does not affect [`NestedSubsystem::initiate_shutdown()`], which still
+    /// shuts down the subsystem immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The subsystem that has to finish before this subsystem shuts down.
+    pub fn shutdown_after(mut self, other: &NestedSubsystem<ErrType>) -> Self {
+        self.shutdown_dependencies.push(other.finished());
+        self
+    }
+
+    /// Delays the invocation of the subsystem function until `other` is ready.
+    ///
+    /// A subsystem is ready once it called [`SubsystemHandle::mark_ready()`].
+    /// Can be called multiple times to wait for multiple subsystems.
+    ///
+    /// If a shutdown is requested before that, the subsystem function never gets invoked.
+    /// The same happens if `other` finishes without ever marking itself as ready.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The subsystem that has to be ready before this subsystem starts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn database(subsys: SubsystemHandle) -> Result<()> {
+    ///     // Connect to the database
+    ///     subsys.mark_ready();
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn http_server(subsys: SubsystemHandle) -> Result<()> {
+    ///     // The database is up already
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let database = subsys.start(SubsystemBuilder::new("Database", database));
+    ///     subsys.start(SubsystemBuilder::new("HttpServer", http_server).start_after(&database));
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn start_after(mut self, other: &NestedSubsystem<ErrType>) -> Self {
+        self.startup_dependencies.push(Box::pin(other.ready()));
+        self
+    }
+
+    /// Converts a panic of this subsystem into an error.
+    ///
+    /// Instead of being reported as [`SubsystemError::Panicked`](crate::errors::SubsystemError::Panicked),
+    /// the panic will be reported as [`SubsystemError::Failed`](crate::errors::SubsystemError::Failed)
+    /// carrying the returned error, and gets handled according to
+    /// [`on_failure`](SubsystemBuilder::on_failure) instead of [`on_panic`](SubsystemBuilder::on_panic).
+    ///
+    /// Panics of nested subsystems are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `mapper` - Receives the name of the subsystem and the panic payload,
+    ///   and returns the error to report.
+    pub fn map_panic(
+        mut self,
+        mapper: impl FnOnce(&str, Box<dyn Any + Send>) -> ErrType + Send + 'static,
+    ) -> Self {
+        self.panic_mapper = Some(Box::new(mapper));
+        self
+    }
+
+    /// Registers cleanup work that runs once this subsystem is finished.
+    ///
+    /// Unlike cleanup code at the end of the subsystem function, the finalizer
+    /// also runs if the subsystem gets cancelled, for example because the shutdown
+    /// timed out. This gives critical cleanup, like releasing a distributed lock,
+    /// a chance to happen.
+    ///
+    /// The finalizer runs in a task of its own and gets abandoned if it takes
+    /// longer than one second. If the subsystem finished on its own, it only counts
+    /// as finished once its finalizer is done.
+    ///
+    /// # Arguments
+    ///
+    /// * `finalizer` - Returns the future that performs the cleanup.
+    pub fn finalizer<F, FinFut>(mut self, finalizer: F) -> Self
+    where
+        F: FnOnce() -> FinFut + Send + 'static,
+        FinFut: Future<Output = ()> + Send + 'static,
+    {
+        self.finalizer = Some(Box::new(move || Box::pin(finalizer())));
+        self
+    }
+
+    /// Sets the severity of the errors of this subsystem.
+    ///
+    /// The severity gets attached to the failures and panics of this subsystem, and allows
+    /// distinguishing unimportant failures from critical ones, for example through
+    /// [`GracefulShutdownError::max_severity()`](crate::errors::GracefulShutdownError::max_severity).
+    ///
+    /// The default is [`Severity::Error`].
+    ///
+    /// Failures of nested subsystems keep their own severity.
+    pub fn error_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Makes this subsystem supervise its restartable children.
+    ///
+    /// Children that get started through a [`restartable()`](SubsystemBuilder::restartable)
+    /// builder get restarted once they fail or panic, instead of passing the error
+    /// on according to their [`on_failure`](SubsystemBuilder::on_failure) and
+    /// [`on_panic`](SubsystemBuilder::on_panic) actions. The `strategy` determines
+    /// which of their siblings get restarted as well.
+    ///
+    /// Other children are not supervised. Failures that happen during a shutdown
+    /// do not cause restarts, and only get logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - Which children to restart if a child fails.
+    pub fn supervision(mut self, strategy: SupervisionStrategy) -> Self {
+        self.supervision = Some(strategy);
+        self
+    }
+
+    /// Converts the errors returned by this subsystem with the given function,
+    /// instead of through [`Into`].
+    ///
+    /// Useful to add context to the error, for example the role of the subsystem.
+    ///
+    /// Errors of nested subsystems are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `mapper` - Receives the error returned by the subsystem function,
+    ///   and returns the error to report.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::{miette, Result};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn database(_subsys: SubsystemHandle) -> std::io::Result<()> {
+    ///     Err(std::io::ErrorKind::ConnectionRefused.into())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start(
+    ///         SubsystemBuilder::new("Database", database)
+    ///             .map_error(|e| miette!("Primary database failed: {e}").into()),
+    ///     );
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn map_error(mut self, mapper: impl Fn(Err) -> ErrType + Send + Sync + 'static) -> Self {
+        self.error_mapper = Some(Arc::new(mapper));
+        self
+    }
+
+    /// Only treats the errors returned by this subsystem as failures if they pass the given filter.
+    ///
+    /// Errors that don't pass the filter get logged at `info` level, and the subsystem
+    /// counts as finished successfully instead. Useful for errors that represent
+    /// expected conditions, like a closed connection, which should not
+    /// trigger a shutdown of the program.
+    ///
+    /// The filter receives the error after [`map_error`](SubsystemBuilder::map_error) got applied.
+    /// Errors of nested subsystems are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Returns `true` if the given error is a failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn connection(_subsys: SubsystemHandle<std::io::Error>) -> std::io::Result<()> {
+    ///     Err(std::io::ErrorKind::ConnectionReset.into())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle<std::io::Error>) -> std::io::Result<()> {
+    ///     subsys.start(
+    ///         SubsystemBuilder::new("Connection", connection)
+    ///             .failure_filter(|e| e.kind() != std::io::ErrorKind::ConnectionReset),
+    ///     );
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn failure_filter(
+        mut self,
+        filter: impl Fn(&ErrType) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.failure_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Lowers the log level of the message that gets emitted when this
+    /// subsystem gets cancelled from `warn` to `debug`.
+    ///
+    /// Useful for subsystems whose cancellation is expected and therefore
+    /// not worth a warning.
+    pub fn quiet_cancel(mut self) -> Self {
+        self.quiet_cancel = true;
+        self
+    }
+
+    /// Runs the subsystem directly in the task that supervises it,
+    /// instead of spawning a separate task for it.
+    ///
+    /// By default, every subsystem uses two tasks: one that runs the subsystem function
+    /// and catches its panics, and one that supervises it. Skipping the inner task reduces
+    /// the overhead of starting a subsystem, which can matter on a `current_thread` runtime
+    /// with many subsystems.
+    ///
+    /// Intended for programs that are built with `panic = "abort"`, or whose subsystems
+    /// don't panic. A panic still gets reported as
+    /// [`SubsystemError::Panicked`](crate::errors::SubsystemError::Panicked), but:
+    /// - the panic payload is lost, so [`map_panic`](SubsystemBuilder::map_panic) has no effect
+    /// - the children of the subsystem are not waited for, and get cancelled if they are still running
+    pub fn no_panic_isolation(mut self) -> Self {
+        self.panic_isolation = false;
+        self
+    }
+
+    /// Limits how often uncaught errors of this subsystem get logged.
+    ///
+    /// Once more than `max_count` errors were logged within `per`, further errors
+    /// of this subsystem are not logged any more. Instead, the number of suppressed errors
+    /// gets logged together with the next error that is allowed to be logged again.
+    ///
+    /// Useful for subsystems that fail in a tight restart loop.
+    /// This only affects logging; the errors are still reported as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_count` - The maximum number of errors that get logged within `per`.
+    /// * `per` - The length of the time window.
+    pub fn log_failures_at_most(mut self, max_count: usize, per: Duration) -> Self {
+        self.failure_log_limit = Some((max_count, per));
+        self
+    }
+
+    /// Treats the subsystem as failed if it returns `Ok` before `min_runtime` elapsed.
+    ///
+    /// Intended for subsystems that are supposed to run until a shutdown is requested,
+    /// to detect misconfigurations that make them exit right away. Instead of finishing
+    /// silently, the subsystem gets reported as [`SubsystemError::Failed`](crate::errors::SubsystemError::Failed)
+    /// with an [`ExitedTooQuickly`] error.
+    ///
+    /// Returning after a shutdown was requested is never treated as a failure.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_runtime` - The minimum time the subsystem has to run.
+    pub fn min_runtime(mut self, min_runtime: Duration) -> Self
+    where
+        ErrType: From<ExitedTooQuickly>,
+    {
+        self.min_runtime = Some((min_runtime, ErrType::from));
+        self
+    }
+
+    /// Lets the children of this subsystem keep running once the subsystem function returned.
+    ///
+    /// By default, a subsystem is only finished once all of its children are finished.
+    /// With this option, the subsystem counts as finished as soon as its function returns,
+    /// and the children get handed over to the parent of this subsystem. They then keep
+    /// running until they finish on their own or a shutdown is requested, and their
+    /// errors get passed on to the parent directly.
+    ///
+    /// Useful for supervisors that only start other subsystems and have nothing left to do.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn supervisor(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start(SubsystemBuilder::new("Worker1", worker));
+    ///     subsys.start(SubsystemBuilder::new("Worker2", worker));
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let supervisor =
+    ///         subsys.start(SubsystemBuilder::new("Supervisor", supervisor).children_outlive_parent());
+    ///
+    ///     // Returns once the supervisor returned, while the workers keep running
+    ///     supervisor.join().await?;
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn children_outlive_parent(mut self) -> Self {
+        self.children_outlive_parent = true;
+        self
+    }
+
+    /// Adds the subsystem to the given group.
+    ///
+    /// All members of a group can be shut down at once through
+    /// [`SubsystemHandle::shutdown_group()`], independent of their position in the
+    /// subsystem tree. Can be called multiple times to add the subsystem to multiple groups.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The name of the group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn listener(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start(SubsystemBuilder::new("Public", listener).group("http"));
+    ///     subsys.start(SubsystemBuilder::new("Admin", listener).group("http"));
+    ///
+    ///     // Stop accepting http requests, wherever the listeners are in the tree
+    ///     subsys.shutdown_group("http");
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn group(mut self, group: impl Into<Arc<str>>) -> Self {
+        self.groups.push(group.into());
+        self
+    }
+
+    /// Attaches a key/value pair to the tracing span of the subsystem.
+    ///
+    /// If at least one field is set, the subsystem function runs inside of a
+    /// `subsystem` span that contains the subsystem name and every pair as a
+    /// field of its own, for example `name=/db component=db tenant=acme`.
+    /// All tracing events emitted by the subsystem function then carry those fields.
+    ///
+    /// Setting the same key again replaces its value.
+    /// Tasks spawned by the subsystem, including nested subsystems, do not
+    /// inherit the span.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the field.
+    /// * `value` - The value of the field.
+    pub fn with_span_field(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.span_fields.push((key, value.into()));
+        self
+    }
+
+    /// Periodically records custom metrics of this subsystem while it is running.
+    ///
+    /// Every `interval`, the `sampler` gets called and the returned [`Sample`] gets
+    /// published to all subscribers of [`Toplevel::samples()`](crate::Toplevel::samples),
+    /// together with the name of the subsystem. The first sample gets recorded right
+    /// after the subsystem started, and sampling stops once the subsystem function returns.
+    ///
+    /// The sampler runs in the task of the subsystem, so it should be cheap;
+    /// for example reading a few counters that the subsystem updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between two samples. Must be non-zero.
+    /// * `sampler` - Records the metrics of the subsystem.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     atomic::{AtomicUsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{Sample, SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn queue(subsys: SubsystemHandle, depth: Arc<AtomicUsize>) -> Result<()> {
+    ///     depth.fetch_add(1, Ordering::Relaxed);
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let depth = Arc::new(AtomicUsize::new(0));
+    ///
+    ///     subsys.start(
+    ///         SubsystemBuilder::new("Queue", {
+    ///             let depth = Arc::clone(&depth);
+    ///             |s| queue(s, depth)
+    ///         })
+    ///         .with_sampler(Duration::from_secs(1), move || {
+    ///             Sample::new().metric("queue_depth", depth.load(Ordering::Relaxed) as f64)
+    ///         }),
+    ///     );
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_sampler(
+        mut self,
+        interval: Duration,
+        sampler: impl Fn() -> Sample + Send + Sync + 'static,
+    ) -> Self {
+        assert!(
+            !interval.is_zero(),
+            "The sampling interval must be non-zero"
+        );
+        self.sampler = Some((interval, Arc::new(sampler)));
+        self
+    }
+
+    /// Prevents the subsystem and all of its children from shutting down the entire tree.
+    ///
+    /// A guardrail for large code bases, where an accidental
+    /// [`request_shutdown()`](SubsystemHandle::request_shutdown) deep inside of the tree
+    /// would stop the whole program. Within this subsystem, `request_shutdown()` does
+    /// nothing except logging a warning.
+    ///
+    /// Local shutdowns through
+    /// [`request_local_shutdown()`](SubsystemHandle::request_local_shutdown)
+    /// still work as usual.
+    pub fn without_shutdown_authority(mut self) -> Self {
+        self.shutdown_authority = false;
+        self
+    }
+
+    /// Replaces the subsystem function through `f`, keeping all other settings.
+    fn map_subsystem<NewFut, NewSubsys>(
+        self,
+        f: impl FnOnce(Subsys) -> NewSubsys,
+    ) -> SubsystemBuilder<'a, ErrType, Err, NewFut, NewSubsys>
+    where
+        NewSubsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> NewFut + Send,
+        NewFut: 'static + Future<Output = Result<(), Err>>,
+    {
+        SubsystemBuilder {
+            name: self.name,
+            subsystem: f(self.subsystem),
+            failure_action: self.failure_action,
+            panic_action: self.panic_action,
+            detached: self.detached,
+            root_linked: self.root_linked,
+            quiet_cancel: self.quiet_cancel,
+            panic_isolation: self.panic_isolation,
+            severity: self.severity,
+            restart_trigger: self.restart_trigger,
+            panic_mapper: self.panic_mapper,
+            finalizer: self.finalizer,
+            error_mapper: self.error_mapper,
+            failure_filter: self.failure_filter,
+            shutdown_dependencies: self.shutdown_dependencies,
+            startup_dependencies: self.startup_dependencies,
+            failure_log_limit: self.failure_log_limit,
+            span_fields: self.span_fields,
+            min_runtime: self.min_runtime,
+            children_outlive_parent: self.children_outlive_parent,
+            groups: self.groups,
+            supervision: self.supervision,
+            sampler: self.sampler,
+            shutdown_authority: self.shutdown_authority,
+            _phantom: Default::default(),
+        }
+    }
+}
+
+impl<'a, ErrType, Err>
+    SubsystemBuilder<'a, ErrType, Err, BoxedSubsystemFuture<Err>, BoxedSubsystem<ErrType, Err>>
+where
+    ErrType: ErrTypeTraits,
+    Err: 'static + Into<ErrType> + Send,
+{
+    /// Creates a new SubsystemBuilder from a subsystem function that
+    /// only borrows its [`SubsystemHandle`].
+    ///
+    /// As the handle can't outlive the subsystem function, leaking it is
+    /// a compile error instead of a runtime panic.
+    /// For more information, see [`ScopedSubsystem`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subsystem. Primarily to identify the
+    ///   subsystem in error messages.
+    /// * `subsystem` - The subsystem function that the subsystem will execute.
+    pub fn new_scoped<Subsys>(name: impl Into<Cow<'a, str>>, subsystem: Subsys) -> Self
+    where
+        Subsys: for<'s> ScopedSubsystem<'s, Err, ErrType> + Send + 'static,
+    {
+        Self::new(
+            name,
+            Box::new(move |subsys: SubsystemHandle<ErrType>| {
+                Box::pin(async move { subsystem(&subsys).await }) as BoxedSubsystemFuture<Err>
+            }),
+        )
+    }
+}
+
+impl<'a, ErrType, Err, Fut, Subsys> SubsystemBuilder<'a, ErrType, Err, Fut, Subsys>
+where
+    ErrType: ErrTypeTraits,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send + Clone,
+    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Err: 'static + Into<ErrType> + Send,
+{
+    /// Allows the subsystem to be restarted through [`NestedSubsystem::restart()`](crate::NestedSubsystem::restart).
+    ///
+    /// Requires the subsystem function to be [`Clone`], as every restart
+    /// runs a fresh copy of it.
+    pub fn restartable(
+        mut self,
+    ) -> SubsystemBuilder<'a, ErrType, Err, BoxedSubsystemFuture<Err>, BoxedSubsystem<ErrType, Err>>
+    {
+        let restart_trigger = Arc::new(Notify::new());
+        self.restart_trigger = Some(Arc::clone(&restart_trigger));
+        // Applied to the individual instances
+        let error_mapper = self.error_mapper.take();
+        let failure_filter = self.failure_filter.take();
+        let panic_isolation = self.panic_isolation;
+        let severity = self.severity;
+        let detached = self.detached;
+        // Supervises the children of the individual instances
+        let supervision = self.supervision.take();
+
+        self.map_subsystem(|subsystem| {
+            Box::new(move |subsys: SubsystemHandle<ErrType>| {
+                Box::pin(async move {
+                    let ready = subsys.readiness();
+                    let outcome = loop {
+                        // A supervised instance catches its errors, so it can be restarted
+                        let supervisor = subsys.parent_supervisor();
+                        let error_action = if supervisor.is_some() {
+                            ErrorAction::CatchAndLocalShutdown
+                        } else {
+                            ErrorAction::Forward
+                        };
+
+                        // The instance uses the same name as this subsystem and forwards
+                        // all of its errors, so it behaves like this subsystem itself.
+                        let instance = subsys.start_with_abs_name(
+                            subsys.name().into(),
+                            {
+                                let subsystem = subsystem.clone();
+                                let error_mapper = error_mapper.clone();
+                                let failure_filter = failure_filter.clone();
+                                let ready = Arc::clone(&ready);
+                                move |s| {
+                                    // The instances stand in for this subsystem
+                                    if detached {
+                                        s.mark_detached();
+                                    }
+                                    s.share_readiness(ready);
+                                    if let Some(strategy) = supervision {
+                                        s.set_supervision(strategy);
+                                    }
+                                    let name = s.name();
+                                    let instance = subsystem(s);
+                                    async move {
+                                        let result = instance
+                                            .await
+                                            .map_err(|e| map_error(e, error_mapper));
+                                        filter_failure(&name, result, failure_filter.as_deref())
+                                    }
+                                }
+                            },
+                            ErrorActions::new(error_action, error_action),
+                            false,
+                            RunnerOptions {
+                                quiet_cancel: true,
+                                panic_isolation,
+                                severity,
+                                // Inherited from this subsystem
+                                shutdown_authority: true,
+                            },
+                            RunnerHooks::default(),
+                        );
+
+                        tokio::select! {
+                            _ = instance.finished() => {
+                                let outcome = instance.outcome();
+                                let Some(supervisor) = &supervisor else { break outcome };
+                                let Err(e) = instance.join().await else { break outcome };
+                                if subsys.is_shutdown_requested() {
+                                    tracing::warn!(
+                                        subsystem = %subsys.name(),
+                                        error = ?e,
+                                        "Supervised subsystem failed during shutdown."
+                                    );
+                                    break outcome;
+                                }
+                                tracing::warn!(
+                                    subsystem = %subsys.name(),
+                                    error = ?e,
+                                    "Supervised subsystem failed, restarting ..."
+                                );
+                                supervisor.child_failed(&restart_trigger);
+                            }
+                            _ = restart_trigger.notified(), if !subsys.is_shutdown_requested() => {
+                                tracing::info!(subsystem = %subsys.name(), "Restarting subsystem ...");
+                                instance.abort();
+                                instance.finished().await;
+                            }
+                        }
+                    };
+
+                    // This subsystem ends the way its last instance did
+                    if let Some(outcome) = outcome {
+                        subsys.tree_node().set_outcome(outcome);
+                    }
+
+                    Result::<(), Err>::Ok(())
+                }) as BoxedSubsystemFuture<Err>
+            }) as BoxedSubsystem<ErrType, Err>
+        })
+    }
+}
+
+impl<'a, ErrType, Err, Fut, Subsys> SubsystemBuilder<'a, ErrType, Err, Fut, Subsys>
+where
+    ErrType: ErrTypeTraits,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Err: 'static + Into<ErrType> + Send,
+{
+    /// Delays the invocation of the subsystem function until `trigger` is finished.
+    ///
+    /// If a shutdown is requested before that, the subsystem function never gets invoked.
+    pub(crate) fn lazy(
+        self,
+        trigger: impl Future<Output = ()> + Send + 'static,
+    ) -> SubsystemBuilder<'a, ErrType, Err, BoxedSubsystemFuture<Err>, BoxedSubsystem<ErrType, Err>>
+    {
+        self.map_subsystem(|subsystem| {
+            Box::new(move |subsys: SubsystemHandle<ErrType>| {
+                Box::pin(async move {
+                    let triggered = tokio::select! {
+                        _ = trigger => true,
+                        _ = subsys.on_shutdown_requested() => false,
+                    };
+
+                    if triggered {
+                        subsystem(subsys).await
+                    } else {
+                        Ok(())
+                    }
+                }) as BoxedSubsystemFuture<Err>
+            }) as BoxedSubsystem<ErrType, Err>
+        })
+    }
+}
+
+impl<'a, ErrType, Err, Fut, Subsys> SubsystemBuilder<'a, ErrType, Err, Fut, Subsys>
+where
+    ErrType: ErrTypeTraits,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+    Fut: 'static + Future<Output = Result<(), Err>>,
+    Err: 'static + Into<ErrType> + Send,
+{
+    /// Moves the subsystem into the given [`SubsystemLocalSet`], which lifts the `Send`
+    /// requirement of its future.
+    pub(crate) fn local(
+        self,
+        local_set: Arc<SubsystemLocalSet>,
+    ) -> SubsystemBuilder<'a, ErrType, Err, BoxedSubsystemFuture<Err>, BoxedSubsystem<ErrType, Err>>
+    {
+        self.map_subsystem(|subsystem| {
+            Box::new(move |subsys: SubsystemHandle<ErrType>| {
+                Box::pin(async move { run_local(&local_set, subsys, subsystem).await })
+                    as BoxedSubsystemFuture<Err>
+            }) as BoxedSubsystem<ErrType, Err>
+        })
+    }
+}
+
+/// Converts an error of a subsystem, using the mapper if one is set.
+pub(crate) fn map_error<ErrType, Err: Into<ErrType>>(
+    error: Err,
+    error_mapper: Option<ErrorMapper<ErrType, Err>>,
+) -> ErrType {
+    match error_mapper {
+        Some(error_mapper) => error_mapper(error),
+        None => error.into(),
+    }
+}
+
+/// Turns errors that don't pass the filter into a clean exit.
+pub(crate) fn filter_failure<ErrType: ErrTypeTraits>(
+    name: &str,
+    result: Result<(), ErrType>,
+    failure_filter: Option<&(dyn Fn(&ErrType) -> bool + Send + Sync)>,
+) -> Result<(), ErrType> {
+    match (result, failure_filter) {
+        (Err(e), Some(failure_filter)) if !failure_filter(&e) => {
+            tracing::info!(
+                subsystem = %name,
+                error = %e,
+                "Subsystem returned an error that is not a failure."
+            );
+            Ok(())
+        }
+        (result, _) => result,
+    }
 }
@@ -1,28 +1,142 @@
 use std::{
+    borrow::Cow,
+    convert::Infallible,
     future::Future,
     mem::ManuallyDrop,
-    sync::{atomic::Ordering, Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, Weak,
+    },
+    time::Duration,
 };
 
-use atomic::Atomic;
-use tokio::sync::{mpsc, oneshot};
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, oneshot, watch, Notify},
+    task::{AbortHandle, JoinHandle},
+    time::{error::Elapsed, Instant, MissedTickBehavior},
+};
 use tokio_util::sync::CancellationToken;
+use tracing::Span;
 
 use crate::{
-    errors::{handle_dropped_error, SubsystemError},
-    runner::{AliveGuard, SubsystemRunner},
-    utils::{remote_drop_collection::RemotelyDroppableItems, JoinerToken},
-    BoxedError, ErrTypeTraits, ErrorAction, NestedSubsystem, SubsystemBuilder,
+    errors::{handle_dropped_error, SubsystemError, SubsystemNotFound},
+    lifecycle::LifecycleEvents,
+    runner::{AliveGuard, CancelReason, RunnerHooks, RunnerOptions, SubsystemRunner},
+    sample::Samples,
+    tree_snapshot::TreeNode,
+    utils::{
+        remote_drop_collection::RemotelyDroppableItems, FailureLogLimits, JoinerToken,
+        JoinerTokenFinisher, JoinerTokenRef, OnError, ShutdownInitiator, SubsystemRegistry,
+    },
+    BoxedError, ConnectionSet, ErrTypeTraits, ErrorAction, HealthStatus, LeakPolicy,
+    NestedSubsystem, RestartPolicy, ShutdownOrder, SignalHandlerControl, SubsystemBuilder,
+    SubsystemFinishedFuture, SubsystemName, SupervisionStrategy, WakeReason,
 };
 
-use super::{error_collector::ErrorCollector, ErrorActions};
+use super::{
+    error_collector::ErrorCollector, run_pool, run_supervised, ErrorActions, OutcomeLog,
+    SubsystemLocalSet, SubsystemPool, SupervisedSubsystem, Supervisor, WrappedSubsystem,
+};
 
-struct Inner<ErrType: ErrTypeTraits> {
+/// A child that gets started through `start_many_with_abs_name()`.
+struct ChildSpec<Subsys, ErrType> {
     name: Arc<str>,
+    subsystem: Subsys,
+    error_actions: Arc<ErrorActions>,
+    // Started through `SubsystemBuilder::detached()`
+    detached: bool,
+    options: RunnerOptions,
+    hooks: RunnerHooks<ErrType>,
+    post_start: PostStart,
+}
+
+impl<Subsys, ErrType> ChildSpec<Subsys, ErrType> {
+    fn map_subsystem<T>(self, f: impl FnOnce(Subsys) -> T) -> ChildSpec<T, ErrType> {
+        ChildSpec {
+            name: self.name,
+            subsystem: f(self.subsystem),
+            error_actions: self.error_actions,
+            detached: self.detached,
+            options: self.options,
+            hooks: self.hooks,
+            post_start: self.post_start,
+        }
+    }
+}
+
+/// The settings of a child that get applied once it is started.
+#[derive(Default)]
+struct PostStart {
+    // Receives the shutdown requests of its parent through a manual forwarding task
+    forward_shutdown: bool,
+    // Receives the shutdown requests of the toplevel instead of its parent
+    root_linked: bool,
+    // Only receives the shutdown request once these are finished
+    shutdown_dependencies: Vec<SubsystemFinishedFuture>,
+    // Notified by the supervisor of the parent to restart the child
+    restart_trigger: Option<Arc<Notify>>,
+    groups: Vec<Arc<str>>,
+}
+
+struct Inner<ErrType: ErrTypeTraits> {
+    name: Arc<Mutex<Arc<str>>>,
     cancellation_token: CancellationToken,
-    toplevel_cancellation_token: CancellationToken,
+    // Initiates the shutdown of the entire tree
+    shutdown_initiator: Arc<ShutdownInitiator>,
+    shutdown_deadline: Arc<watch::Sender<Option<Instant>>>,
+    events: LifecycleEvents,
+    samples: Samples,
+    shutdown_acknowledged: Arc<AtomicBool>,
+    // Created on demand by `shutdown_flag()`
+    shutdown_flag: Mutex<Option<Arc<AtomicBool>>>,
+    children_outlive_parent: Arc<AtomicBool>,
+    // Whether the subsystem got started through `SubsystemBuilder::detached()`
+    detached: AtomicBool,
+    // Cleared by `SubsystemBuilder::without_shutdown_authority()`, for all descendants
+    shutdown_authority: bool,
+    // Set by `mark_ready()`; shared with the instances of restartable subsystems
+    ready: Mutex<Arc<watch::Sender<bool>>>,
+    // Set if this subsystem supervises its children
+    supervisor: Mutex<Option<Arc<Supervisor>>>,
+    // Set if the parent supervises this subsystem
+    parent_supervisor: Option<Arc<Supervisor>>,
     joiner_token: JoinerToken<ErrType>,
     children: RemotelyDroppableItems<SubsystemRunner>,
+    // The detached children that are still running, and how many were started in total
+    detached_children: RemotelyDroppableItems<DetachedChild>,
+    detached_started: AtomicU64,
+    // All running subsystems of the entire tree
+    running: Arc<SubsystemRegistry>,
+    failure_log_limits: Arc<FailureLogLimits>,
+    leak_policy: Arc<Mutex<LeakPolicy>>,
+    signal_handler_control: SignalHandlerControl,
+    // Whether the tree got paused through `Toplevel::catch_pause_signals()`
+    paused: Arc<watch::Sender<bool>>,
+    // The number of ancestors of this subsystem
+    depth: usize,
+    max_depth: Arc<Mutex<Option<usize>>>,
+    // Joins the segments of the absolute names of the entire tree
+    name_separator: char,
+    tree_node: Arc<TreeNode>,
+    toplevel_tree_node: Arc<TreeNode>,
+    // The outcome logs of this subsystem and all of its ancestors
+    outcome_logs: Arc<[Weak<OutcomeLog>]>,
+    // The runtime all tasks of the tree get spawned on; `None` means the current one
+    runtime: Option<Handle>,
+    // Created on demand by `start_local()`
+    local_set: OnceLock<Arc<SubsystemLocalSet>>,
+    // The span the subsystem function runs in, to record renames in
+    span: Arc<Mutex<Span>>,
+}
+
+/// A detached child, as tracked by its parent.
+#[derive(Clone)]
+struct DetachedChild {
+    // The position in the start order of all detached children
+    index: u64,
+    cancellation_token: CancellationToken,
+    joiner: JoinerTokenRef,
 }
 
 /// The handle given to each subsystem through which the subsystem can interact with this crate.
@@ -40,6 +154,112 @@ pub(crate) struct WeakSubsystemHandle<ErrType: ErrTypeTraits> {
 }
 
 impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
+    /// Creates the root of a new subsystem tree, without a [`Toplevel`](crate::Toplevel).
+    ///
+    /// This is intended for embedding the subsystem machinery into a custom supervisor.
+    /// Most programs should use [`Toplevel`](crate::Toplevel) instead, as the returned
+    /// handle leaves the following responsibilities to the caller:
+    ///
+    /// - Errors that are not caught by any subsystem get sent to the returned receiver,
+    ///   and are neither logged nor collected anywhere else. Every such error
+    ///   also initiates a shutdown of the entire tree.
+    /// - Shutdowns have to be initiated manually, for example through
+    ///   [`request_shutdown()`](Self::request_shutdown). No signals are handled.
+    /// - The caller has to wait for the subsystems to finish, for example through
+    ///   [`wait_for_children()`](Self::wait_for_children), and enforce a timeout if needed.
+    ///   Dropping the root handle cancels all remaining subsystems immediately.
+    ///
+    /// # Returns
+    ///
+    /// The root handle and the receiver of all uncaught errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{timeout, Duration};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (root, mut errors) = SubsystemHandle::new_root();
+    ///     root.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///
+    ///     // Perform the shutdown
+    ///     root.request_shutdown();
+    ///     timeout(Duration::from_millis(1000), root.wait_for_children())
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     while let Ok(error) = errors.try_recv() {
+    ///         println!("Subsystem error: {error}");
+    ///     }
+    /// }
+    /// ```
+    pub fn new_root() -> (Self, mpsc::UnboundedReceiver<SubsystemError<ErrType>>) {
+        let (error_sender, errors) = mpsc::unbounded_channel();
+
+        let handle = root_handle(
+            move |e| {
+                // Ignore errors; an error only means that nobody is interested in them.
+                let _ = error_sender.send(e);
+            },
+            Arc::new(FailureLogLimits::default()),
+            None,
+            '/',
+        );
+
+        (handle, errors)
+    }
+
+    /// Creates a standalone handle to test a subsystem function in isolation.
+    ///
+    /// The subsystem function can be called directly with the returned handle,
+    /// without a [`Toplevel`](crate::Toplevel). The returned [`TestController`](crate::TestController)
+    /// requests the shutdown of the subsystem and collects the errors of its nested subsystems.
+    ///
+    /// Like in a real subsystem, nested subsystems get cancelled once the handle is dropped,
+    /// so the subsystem function should wait for them through
+    /// [`wait_for_children()`](Self::wait_for_children) before it returns.
+    ///
+    /// Requires the `test-util` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let (handle, controller) = SubsystemHandle::test_handle();
+    ///
+    ///     controller.request_shutdown();
+    ///     my_subsystem(handle).await.unwrap();
+    ///
+    ///     assert!(controller.take_errors().is_empty());
+    /// }
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn test_handle() -> (Self, crate::TestController<ErrType>) {
+        let (handle, errors) = Self::new_root();
+        let controller = crate::TestController {
+            cancellation_token: handle.inner.cancellation_token.clone(),
+            errors: Mutex::new(errors),
+        };
+        (handle, controller)
+    }
+
     /// Start a nested subsystem.
     ///
     /// Once called, the subsystem will be started immediately, similar to [`tokio::spawn`].
@@ -47,12 +267,17 @@ impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
     /// # Arguments
     ///
     /// * `builder` - The [`SubsystemBuilder`] that contains all the information
-    ///               about the subsystem that should be spawned.
+    ///   about the subsystem that should be spawned.
     ///
     /// # Returns
     ///
     /// A [`NestedSubsystem`] that can be used to control or join the subsystem.
     ///
+    /// # Panics
+    ///
+    /// Panics if the subsystem would exceed the maximum depth configured through
+    /// [`Toplevel::with_max_depth()`](crate::Toplevel::with_max_depth).
+    ///
     /// # Examples
     ///
     /// ```
@@ -80,214 +305,1411 @@ impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
     where
         Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
         Fut: 'static + Future<Output = Result<(), Err>> + Send,
-        Err: Into<ErrType>,
+        Err: 'static + Into<ErrType>,
     {
-        self.start_with_abs_name(
-            if self.inner.name.as_ref() == "/" {
-                Arc::from(format!("/{}", builder.name))
-            } else {
-                Arc::from(format!("{}/{}", self.inner.name, builder.name))
-            },
-            builder.subsystem,
-            ErrorActions {
-                on_failure: Atomic::new(builder.failure_action),
-                on_panic: Atomic::new(builder.panic_action),
-            },
-            builder.detached,
-        )
+        self.start_batch([builder])
+            .pop()
+            .expect("One subsystem per builder")
     }
 
+    /// Start multiple nested subsystems at once.
+    ///
+    /// Behaves like calling [`start()`](Self::start) for every builder, but registers
+    /// all of the subsystems together. This reduces the lock contention when
+    /// starting many subsystems in a hot loop, like one subsystem per connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `builders` - The [`SubsystemBuilder`]s of the subsystems that should be spawned.
+    ///
+    /// # Returns
+    ///
+    /// One [`NestedSubsystem`] per builder, in the same order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn connection(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let connections = subsys.start_batch(
+    ///         (0..100).map(|i| SubsystemBuilder::new(format!("Connection{i}"), connection)),
+    ///     );
+    ///     assert_eq!(connections.len(), 100);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
     #[track_caller]
-    pub(crate) fn start_with_abs_name<Err, Fut, Subsys>(
+    pub fn start_batch<'a, Err, Fut, Subsys>(
         &self,
-        name: Arc<str>,
-        subsystem: Subsys,
-        error_actions: ErrorActions,
-        detached: bool,
-    ) -> NestedSubsystem<ErrType>
+        builders: impl IntoIterator<Item = SubsystemBuilder<'a, ErrType, Err, Fut, Subsys>>,
+    ) -> Vec<NestedSubsystem<ErrType>>
     where
         Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
         Fut: 'static + Future<Output = Result<(), Err>> + Send,
-        Err: Into<ErrType>,
+        Err: 'static + Into<ErrType>,
     {
-        let alive_guard = AliveGuard::new();
-
-        let (error_sender, errors) = mpsc::unbounded_channel();
+        let specs = builders
+            .into_iter()
+            .map(|builder| {
+                self.child_spec(builder)
+                    .map_subsystem(|wrapped| move |s| wrapped.start(s))
+            })
+            .collect();
+        self.start_many_with_abs_name(specs)
+    }
 
-        let cancellation_token = if detached {
-            CancellationToken::new()
+    /// Converts a builder into the spec of a child of this subsystem.
+    fn child_spec<Err, Fut, Subsys>(
+        &self,
+        builder: SubsystemBuilder<'_, ErrType, Err, Fut, Subsys>,
+    ) -> ChildSpec<WrappedSubsystem<ErrType, Err, Subsys>, ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType>,
+    {
+        let parent_name = self.name();
+        let separator = self.name_separator();
+        // The toplevel subsystem is named after the separator, like `/`
+        let name: Arc<str> = if *parent_name == *separator.to_string() {
+            Arc::from(format!("{}{}", separator, builder.name))
         } else {
-            self.inner.cancellation_token.child_token()
-        };
-
-        let error_actions = Arc::new(error_actions);
-
-        let (joiner_token, joiner_token_ref) = self.inner.joiner_token.child_token({
-            let cancellation_token = cancellation_token.clone();
-            let error_actions = Arc::clone(&error_actions);
-            move |e| {
-                let error_action = match &e {
-                    SubsystemError::Failed(_, _) => {
-                        error_actions.on_failure.load(Ordering::Relaxed)
-                    }
-                    SubsystemError::Panicked(_) => error_actions.on_panic.load(Ordering::Relaxed),
-                };
-
-                match error_action {
-                    ErrorAction::Forward => Some(e),
-                    ErrorAction::CatchAndLocalShutdown => {
-                        handle_dropped_error(error_sender.send(e));
-                        cancellation_token.cancel();
-                        None
-                    }
-                }
-            }
-        });
-
-        let child_handle = SubsystemHandle {
-            inner: ManuallyDrop::new(Inner {
-                name: Arc::clone(&name),
-                cancellation_token: cancellation_token.clone(),
-                toplevel_cancellation_token: self.inner.toplevel_cancellation_token.clone(),
-                joiner_token,
-                children: RemotelyDroppableItems::new(),
-            }),
-            drop_redirect: None,
+            Arc::from(format!("{}{}{}", parent_name, separator, builder.name))
         };
 
-        let runner = SubsystemRunner::new(name, subsystem, child_handle, alive_guard.clone());
-
-        // Shenanigans to juggle child ownership
-        //
-        // RACE CONDITION SAFETY:
-        // If the subsystem ends before `on_finished` was able to be called, nothing bad happens.
-        // alive_guard will keep the guard alive and the callback will only be called inside of
-        // the guard's drop() implementation.
-        let child_dropper = self.inner.children.insert(runner);
-        alive_guard.on_finished(|| {
-            drop(child_dropper);
-        });
-
-        NestedSubsystem {
-            joiner: joiner_token_ref,
-            cancellation_token,
-            errors: Mutex::new(ErrorCollector::new(errors)),
-            error_actions,
+        if let Some((max_count, per)) = builder.failure_log_limit {
+            self.inner
+                .failure_log_limits
+                .set(Arc::clone(&name), max_count, per);
         }
-    }
 
-    /// Waits until all the children of this subsystem are finished.
-    pub async fn wait_for_children(&self) {
-        self.inner.joiner_token.join_children().await
+        ChildSpec {
+            name,
+            subsystem: WrappedSubsystem {
+                subsystem: builder.subsystem,
+                span_fields: builder.span_fields,
+                error_mapper: builder.error_mapper,
+                failure_filter: builder.failure_filter,
+                min_runtime: builder.min_runtime,
+                children_outlive_parent: builder.children_outlive_parent,
+                detached: builder.detached,
+                supervision: builder.supervision,
+                startup_dependencies: builder.startup_dependencies,
+                sampler: builder.sampler,
+            },
+            error_actions: Arc::new(ErrorActions::new(
+                builder.failure_action,
+                builder.panic_action,
+            )),
+            detached: builder.detached,
+            options: RunnerOptions {
+                quiet_cancel: builder.quiet_cancel,
+                panic_isolation: builder.panic_isolation,
+                severity: builder.severity,
+                shutdown_authority: builder.shutdown_authority,
+            },
+            hooks: RunnerHooks {
+                panic_mapper: builder.panic_mapper,
+                finalizer: builder.finalizer,
+            },
+            post_start: PostStart {
+                forward_shutdown: !builder.detached
+                    && (builder.root_linked || !builder.shutdown_dependencies.is_empty()),
+                root_linked: builder.root_linked,
+                shutdown_dependencies: builder.shutdown_dependencies,
+                restart_trigger: builder.restart_trigger,
+                groups: builder.groups,
+            },
+        }
     }
 
-    // For internal use only - should never be used by users.
-    // Required as a short-lived second reference inside of `runner`.
-    pub(crate) fn delayed_clone(&mut self) -> oneshot::Receiver<WeakSubsystemHandle<ErrType>> {
-        let (sender, receiver) = oneshot::channel();
+    /// Applies the settings of a child that require it to be started already.
+    fn apply_post_start(
+        &self,
+        nested: &mut NestedSubsystem<ErrType>,
+        post_start: PostStart,
+        supervisor: Option<&Arc<Supervisor>>,
+    ) {
+        let PostStart {
+            forward_shutdown,
+            root_linked,
+            shutdown_dependencies,
+            restart_trigger,
+            groups,
+        } = post_start;
 
-        let previous = self.drop_redirect.replace(sender);
-        assert!(previous.is_none());
+        if let (Some(supervisor), Some(restart_trigger)) = (supervisor, &restart_trigger) {
+            supervisor.register(restart_trigger);
+        }
+        nested.restart_trigger = restart_trigger;
+        if !groups.is_empty() {
+            nested.tree_node.set_groups(groups);
+        }
 
-        receiver
+        if forward_shutdown {
+            // The subsystem got started detached; forward the shutdown
+            // request manually once all dependencies are finished.
+            let parent_token = if root_linked {
+                self.inner.shutdown_initiator.token().clone()
+            } else {
+                self.inner.cancellation_token.clone()
+            };
+            let token = nested.cancellation_token.clone();
+            let finished = nested.finished();
+            crate::tokio_task::spawn(
+                async move {
+                    let forward_shutdown = async {
+                        parent_token.cancelled().await;
+                        for dependency in shutdown_dependencies {
+                            dependency.await;
+                        }
+                        token.cancel();
+                    };
+                    tokio::select! {
+                        _ = forward_shutdown => (),
+                        _ = finished => (),
+                    }
+                },
+                "shutdown_after",
+                self.runtime(),
+            );
+        }
     }
 
-    /// Wait for the shutdown mode to be triggered.
+    /// Start a nested subsystem and return a future that resolves once it is finished.
     ///
-    /// Once the shutdown mode is entered, all existing calls to this
-    /// method will be released and future calls to this method will
-    /// return immediately.
+    /// Shorthand for calling [`start()`](Self::start) followed by
+    /// [`NestedSubsystem::finished()`].
     ///
-    /// This is the primary method of subsystems to react to
-    /// the shutdown requests. Most often, it will be used in [`tokio::select`]
-    /// statements to cancel other code as soon as the shutdown is requested.
+    /// # Arguments
+    ///
+    /// * `builder` - The [`SubsystemBuilder`] that contains all the information
+    ///   about the subsystem that should be spawned.
+    ///
+    /// # Returns
+    ///
+    /// The [`NestedSubsystem`] and the [`SubsystemFinishedFuture`] of the started subsystem.
     ///
     /// # Examples
     ///
     /// ```
     /// use miette::Result;
-    /// use tokio::time::{sleep, Duration};
-    /// use tokio_graceful_shutdown::SubsystemHandle;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
     ///
-    /// async fn countdown() {
-    ///     for i in (1..10).rev() {
-    ///         tracing::info!("Countdown: {}", i);
-    ///         sleep(Duration::from_millis(1000)).await;
-    ///     }
+    /// async fn nested_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
     /// }
     ///
-    /// async fn countdown_subsystem(subsys: SubsystemHandle) -> Result<()> {
-    ///     tracing::info!("Starting countdown ...");
-    ///
-    ///     // This cancels the countdown as soon as shutdown
-    ///     // mode was entered
-    ///     tokio::select! {
-    ///         _ = subsys.on_shutdown_requested() => {
-    ///             tracing::info!("Countdown cancelled.");
-    ///         },
-    ///         _ = countdown() => {
-    ///             tracing::info!("Countdown finished.");
-    ///         }
-    ///     };
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let (_nested, nested_finished) =
+    ///         subsys.start_and_finished(SubsystemBuilder::new("Nested", nested_subsystem));
     ///
+    ///     nested_finished.await;
     ///     Ok(())
     /// }
     /// ```
-    pub async fn on_shutdown_requested(&self) {
-        self.inner.cancellation_token.cancelled().await
+    #[track_caller]
+    pub fn start_and_finished<Err, Fut, Subsys>(
+        &self,
+        builder: SubsystemBuilder<ErrType, Err, Fut, Subsys>,
+    ) -> (NestedSubsystem<ErrType>, SubsystemFinishedFuture)
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType>,
+    {
+        let nested = self.start(builder);
+        let finished = nested.finished();
+        (nested, finished)
     }
 
-    /// Returns whether a shutdown should be performed now.
+    /// Start a nested subsystem that only gets invoked once `trigger` is finished.
     ///
-    /// This method is provided for subsystems that need to query the shutdown
-    /// request state repeatedly.
+    /// The returned [`NestedSubsystem`] is available immediately and already
+    /// participates in the shutdown. If a shutdown is requested before `trigger`
+    /// is finished, the subsystem function never gets invoked and the subsystem
+    /// simply counts as finished.
     ///
-    /// This can be useful in scenarios where a subsystem depends on the graceful
-    /// shutdown of its nested coroutines before it can run final cleanup steps itself.
+    /// This is useful for rarely used subsystems that should only be started on demand.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The [`SubsystemBuilder`] that contains all the information
+    ///   about the subsystem that should be spawned.
+    /// * `trigger` - The future that has to finish before the subsystem function gets invoked.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the subsystem.
     ///
     /// # Examples
     ///
     /// ```
     /// use miette::Result;
-    /// use tokio::time::{sleep, Duration};
-    /// use tokio_graceful_shutdown::SubsystemHandle;
+    /// use tokio::sync::oneshot;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
     ///
-    /// async fn uncancellable_action(subsys: &SubsystemHandle) {
-    ///     tokio::select! {
-    ///         // Execute an action. A dummy `sleep` in this case.
-    ///         _ = sleep(Duration::from_millis(1000)) => {
-    ///             tracing::info!("Action finished.");
-    ///         }
-    ///         // Perform a shutdown if requested
-    ///         _ = subsys.on_shutdown_requested() => {
-    ///             tracing::info!("Action aborted.");
-    ///         },
-    ///     }
+    /// async fn plugin(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
     /// }
     ///
     /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
-    ///     tracing::info!("Starting subsystem ...");
+    ///     let (load_plugin, plugin_requested) = oneshot::channel::<()>();
     ///
-    ///     // We cannot do a `tokio::select` with `on_shutdown_requested`
-    ///     // here, because a shutdown would cancel the action without giving
-    ///     // it the chance to react first.
-    ///     while !subsys.is_shutdown_requested() {
-    ///         uncancellable_action(&subsys).await;
-    ///     }
+    ///     subsys.start_lazy(SubsystemBuilder::new("Plugin", plugin), async {
+    ///         plugin_requested.await.ok();
+    ///     });
     ///
-    ///     tracing::info!("Subsystem stopped.");
+    ///     // Load the plugin on demand
+    ///     load_plugin.send(()).ok();
     ///
+    ///     subsys.on_shutdown_requested().await;
     ///     Ok(())
     /// }
     /// ```
-    pub fn is_shutdown_requested(&self) -> bool {
-        self.inner.cancellation_token.is_cancelled()
-    }
-
-    /// Triggers a shutdown of the entire subsystem tree.
-    ///
-    /// # Examples
-    ///
+    #[track_caller]
+    pub fn start_lazy<Err, Fut, Subsys>(
+        &self,
+        builder: SubsystemBuilder<ErrType, Err, Fut, Subsys>,
+        trigger: impl Future<Output = ()> + Send + 'static,
+    ) -> NestedSubsystem<ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType> + Send,
+    {
+        self.start(builder.lazy(trigger))
+    }
+
+    /// Start a nested subsystem whose future is not [`Send`].
+    ///
+    /// Behaves like [`start()`](Self::start), but runs the subsystem inside of a
+    /// [`LocalSet`](tokio::task::LocalSet), which allows it to hold `!Send` values
+    /// like [`Rc`](std::rc::Rc) across `.await` points and to use
+    /// [`spawn_local()`](tokio::task::spawn_local). Cancellation and error
+    /// reporting work like for any other subsystem.
+    ///
+    /// # Runtime requirements
+    ///
+    /// All local subsystems started through the same handle share one `LocalSet`.
+    /// It gets created together with the first of them, and is driven by a dedicated
+    /// thread on the runtime of the tree until the handle and all of its local
+    /// subsystems are gone.
+    ///
+    /// - Timers, I/O resources and tasks spawned through [`tokio::spawn`] belong to
+    ///   the runtime of the tree, as do nested subsystems started through the handle.
+    ///   Tasks spawned through `spawn_local()` run in the shared `LocalSet`.
+    /// - On a current-thread runtime, timers and I/O only make progress while the runtime
+    ///   itself is being driven through [`Runtime::block_on`](tokio::runtime::Runtime::block_on),
+    ///   like `#[tokio::main]` does.
+    /// - Blocking the thread blocks all local subsystems of the handle. A subsystem that does
+    ///   not react to the shutdown timeout because it is blocked keeps the thread alive
+    ///   until it returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The [`SubsystemBuilder`] that contains all the information
+    ///   about the subsystem that should be spawned.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the subsystem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::{cell::Cell, rc::Rc};
+    ///
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn local_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let counter = Rc::new(Cell::new(0));
+    ///
+    ///     tokio::task::spawn_local({
+    ///         let counter = Rc::clone(&counter);
+    ///         async move { counter.set(counter.get() + 1) }
+    ///     });
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     tracing::info!("Counted to {}.", counter.get());
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start_local(SubsystemBuilder::new("Local", local_subsystem));
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn start_local<Err, Fut, Subsys>(
+        &self,
+        builder: SubsystemBuilder<ErrType, Err, Fut, Subsys>,
+    ) -> NestedSubsystem<ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>>,
+        Err: 'static + Into<ErrType> + Send,
+    {
+        let local_set = self.inner.local_set.get_or_init(|| {
+            let runtime = self.inner.runtime.clone().unwrap_or_else(Handle::current);
+            Arc::new(SubsystemLocalSet::new(&self.name(), runtime))
+        });
+        self.start(builder.local(Arc::clone(local_set)))
+    }
+
+    /// Start a nested subsystem in detached mode.
+    ///
+    /// Shorthand for starting a [`SubsystemBuilder`] with
+    /// [`detached()`](SubsystemBuilder::detached) applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subsystem.
+    /// * `subsystem` - The subsystem function that the subsystem will execute.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the subsystem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn nested_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let nested = subsys.start_detached("Nested", nested_subsystem);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///
+    ///     // Detached subsystems have to be shut down manually
+    ///     nested.initiate_shutdown();
+    ///     nested.join().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn start_detached<'a, Err, Fut, Subsys>(
+        &self,
+        name: impl Into<Cow<'a, str>>,
+        subsystem: Subsys,
+    ) -> NestedSubsystem<ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType>,
+    {
+        self.start(SubsystemBuilder::new(name, subsystem).detached())
+    }
+
+    /// Start a nested subsystem that runs the given task periodically
+    /// until a shutdown is requested.
+    ///
+    /// The task gets run for the first time immediately. A shutdown request
+    /// does not interrupt a task that is currently running; it only prevents
+    /// further runs. If the task returns an error, the subsystem stops and
+    /// fails with that error.
+    ///
+    /// The task receives the handle of the periodic subsystem, through which it can
+    /// start children or check for a shutdown. The returned future can't borrow
+    /// the handle, so everything that needs the handle has to happen before.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subsystem.
+    /// * `period` - The time between two runs of the task.
+    /// * `missed_tick_behavior` - How to catch up if a run took longer than `period`.
+    /// * `task` - The task to run periodically.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the subsystem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{Duration, MissedTickBehavior};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn flush(_subsys: SubsystemHandle) -> Result<()> {
+    ///     tracing::info!("Flushing metrics ...");
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.spawn_periodic(
+    ///         "Heartbeat",
+    ///         Duration::from_secs(1),
+    ///         MissedTickBehavior::Skip,
+    ///         |_| async {
+    ///             tracing::info!("Still alive.");
+    ///             Result::<()>::Ok(())
+    ///         },
+    ///     );
+    ///
+    ///     subsys.spawn_periodic(
+    ///         "Metrics",
+    ///         Duration::from_secs(10),
+    ///         MissedTickBehavior::Skip,
+    ///         |subsys| {
+    ///             // Every flush runs as a child of the periodic subsystem
+    ///             let flush = subsys.start(SubsystemBuilder::new("Flush", flush));
+    ///             async move { flush.join().await }
+    ///         },
+    ///     );
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn spawn_periodic<'a, Err, Fut, Task>(
+        &self,
+        name: impl Into<Cow<'a, str>>,
+        period: Duration,
+        missed_tick_behavior: MissedTickBehavior,
+        mut task: Task,
+    ) -> NestedSubsystem<ErrType>
+    where
+        Task: 'static + FnMut(&SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType> + Send,
+    {
+        self.start(SubsystemBuilder::new(
+            name,
+            move |subsys: SubsystemHandle<ErrType>| async move {
+                let mut interval = tokio::time::interval(period);
+                interval.set_missed_tick_behavior(missed_tick_behavior);
+
+                loop {
+                    tokio::select! {
+                        _ = subsys.on_shutdown_requested() => break,
+                        _ = interval.tick() => task(&subsys).await?,
+                    }
+                }
+
+                Result::<(), Err>::Ok(())
+            },
+        ))
+    }
+
+    /// Start a pool of identical worker subsystems.
+    ///
+    /// The workers are started as children of a pool subsystem called `name_prefix`,
+    /// and are named by their index, like `name_prefix/0`, `name_prefix/1`, etc.
+    ///
+    /// Workers that finish get respawned under the same name until a shutdown
+    /// of the pool is requested. The number of workers can be changed later
+    /// through [`SubsystemPool::resize()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `name_prefix` - The name of the pool subsystem.
+    /// * `count` - The initial number of workers.
+    /// * `factory` - The subsystem function that every worker will execute.
+    ///
+    /// # Returns
+    ///
+    /// A [`SubsystemPool`] that can be used to resize or join the pool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let pool = subsys.spawn_pool("Workers", 4, worker);
+    ///
+    ///     // Scale up under load
+    ///     pool.resize(8);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn spawn_pool<'a, Err, Fut, Factory>(
+        &self,
+        name_prefix: impl Into<Cow<'a, str>>,
+        count: usize,
+        factory: Factory,
+    ) -> SubsystemPool<ErrType>
+    where
+        Factory: 'static + Fn(SubsystemHandle<ErrType>) -> Fut + Send + Sync,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType> + Send,
+    {
+        let (size_sender, size_receiver) = watch::channel(count);
+        let factory = Arc::new(factory);
+
+        let pool = self.start(SubsystemBuilder::new(
+            name_prefix,
+            move |subsys: SubsystemHandle<ErrType>| run_pool(subsys, size_receiver, factory),
+        ));
+
+        SubsystemPool::new(pool, size_sender)
+    }
+
+    /// Creates a set of connection tasks that get drained once a shutdown is requested.
+    ///
+    /// See [`ConnectionSet`] for more information.
+    ///
+    /// # Arguments
+    ///
+    /// * `grace` - How long [`ConnectionSet::drain()`] waits for the running
+    ///   connections before cancelling them.
+    pub fn connection_set(&self, grace: Duration) -> ConnectionSet {
+        ConnectionSet::new(
+            self.inner.cancellation_token.clone(),
+            Arc::clone(&self.inner.shutdown_deadline),
+            grace,
+            self.inner.runtime.clone(),
+        )
+    }
+
+    /// Start a nested subsystem that gets restarted automatically once it fails.
+    ///
+    /// A light-weight alternative to [`SubsystemBuilder::supervision()`] for a single
+    /// resilient worker. Every failure or panic of the subsystem restarts it with a fresh
+    /// copy of `factory`, according to `policy`; its siblings are not affected.
+    /// Once the policy allows no more restarts, the next failure gets passed on
+    /// to this subsystem like the failure of any other nested subsystem.
+    ///
+    /// Failures that happen during a shutdown do not cause restarts, and only get logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subsystem.
+    /// * `factory` - The subsystem function; every restart runs a fresh copy of it.
+    /// * `policy` - How often and how quickly the subsystem gets restarted.
+    ///
+    /// # Returns
+    ///
+    /// A [`SupervisedSubsystem`] that can be used to observe, shut down or join the subsystem.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{RestartPolicy, SubsystemHandle};
+    ///
+    /// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let worker = subsys.spawn_supervised(
+    ///         "Worker",
+    ///         worker,
+    ///         RestartPolicy::new()
+    ///             .max_restarts(5)
+    ///             .delay(Duration::from_millis(100)),
+    ///     );
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     tracing::info!("The worker got restarted {} times.", worker.restart_count());
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn spawn_supervised<'a, Err, Fut, Factory>(
+        &self,
+        name: impl Into<Cow<'a, str>>,
+        factory: Factory,
+        policy: RestartPolicy,
+    ) -> SupervisedSubsystem<ErrType>
+    where
+        Factory: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send + Clone,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType> + Send,
+    {
+        let restarts = Arc::new(AtomicU64::new(0));
+
+        let subsystem = self.start(SubsystemBuilder::new(name, {
+            let restarts = Arc::clone(&restarts);
+            move |subsys: SubsystemHandle<ErrType>| {
+                run_supervised(subsys, factory, policy, restarts)
+            }
+        }));
+
+        SupervisedSubsystem::new(subsystem, restarts)
+    }
+
+    /// Registers a callback that runs once a shutdown of this subsystem is requested.
+    ///
+    /// Unlike [`on_shutdown_requested()`](SubsystemHandle::on_shutdown_requested), this
+    /// does not need to be awaited; the callback runs in a nested subsystem called `on_shutdown`.
+    /// Like every nested subsystem, it is covered by
+    /// [`wait_for_children()`](SubsystemHandle::wait_for_children), and this subsystem
+    /// is not considered finished before the callback ran.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function to run when the shutdown is requested.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the callback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown(|| async {
+    ///         tracing::info!("Shutdown requested, flushing caches ...");
+    ///     });
+    ///
+    ///     // Perform the actual work of the subsystem
+    ///     subsys.on_shutdown_requested().await;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn on_shutdown<Fut, Callback>(&self, callback: Callback) -> NestedSubsystem<ErrType>
+    where
+        Callback: 'static + FnOnce() -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        self.start(SubsystemBuilder::new(
+            "on_shutdown",
+            move |subsys: SubsystemHandle<ErrType>| async move {
+                subsys.on_shutdown_requested().await;
+                callback().await;
+                Result::<(), ErrType>::Ok(())
+            },
+        ))
+    }
+
+    /// Cancels an external [`CancellationToken`] once a shutdown of this subsystem is requested.
+    ///
+    /// Useful for libraries that manage their own [`CancellationToken`].
+    /// The forwarding happens in a nested subsystem called `mirror_shutdown`,
+    /// so this subsystem is not considered finished before the token got cancelled.
+    ///
+    /// Unlike [`create_cancellation_token()`](SubsystemHandle::create_cancellation_token),
+    /// this works with tokens that were not created by this crate.
+    ///
+    /// # Arguments
+    ///
+    /// * `external` - The token to cancel when the shutdown is requested.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the forwarding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     // A token owned by a third-party library
+    ///     let library_token = CancellationToken::new();
+    ///
+    ///     subsys.mirror_shutdown_to(library_token.clone());
+    ///
+    ///     library_token.cancelled().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn mirror_shutdown_to(&self, external: CancellationToken) -> NestedSubsystem<ErrType> {
+        self.start(SubsystemBuilder::new(
+            "mirror_shutdown",
+            move |subsys: SubsystemHandle<ErrType>| async move {
+                subsys.on_shutdown_requested().await;
+                external.cancel();
+                Result::<(), ErrType>::Ok(())
+            },
+        ))
+    }
+
+    /// Ties an already spawned tokio task to the lifecycle of this subsystem.
+    ///
+    /// The task gets wrapped in a nested subsystem called `task-<id>`, where `<id>`
+    /// is the [`tokio::task::Id`] of the task. Like every nested subsystem, it has to finish
+    /// before this subsystem is considered finished. If it gets cancelled, for example
+    /// because the shutdown timed out, the task gets aborted. A panic of the task
+    /// gets reported as a panic of the nested subsystem.
+    ///
+    /// Note that the task does not receive shutdown requests; it has to finish on its own
+    /// once a shutdown is requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The [`JoinHandle`] of the task to adopt.
+    ///
+    /// # Returns
+    ///
+    /// A [`NestedSubsystem`] that can be used to control or join the task.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     // A task spawned by third-party code
+    ///     let handle = tokio::spawn(async {
+    ///         sleep(Duration::from_millis(100)).await;
+    ///     });
+    ///
+    ///     subsys.adopt_task(handle);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn adopt_task(&self, handle: JoinHandle<()>) -> NestedSubsystem<ErrType> {
+        struct AbortOnDrop(AbortHandle);
+        impl Drop for AbortOnDrop {
+            fn drop(&mut self) {
+                self.0.abort();
+            }
+        }
+
+        self.start(SubsystemBuilder::new(
+            format!("task-{}", handle.id()),
+            move |_subsys: SubsystemHandle<ErrType>| async move {
+                let _abort_on_cancel = AbortOnDrop(handle.abort_handle());
+                match handle.await {
+                    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                    // A task that got aborted from the outside counts as finished
+                    Ok(()) | Err(_) => Result::<(), ErrType>::Ok(()),
+                }
+            },
+        ))
+    }
+
+    #[track_caller]
+    pub(crate) fn start_with_abs_name<Err, Fut, Subsys>(
+        &self,
+        name: Arc<str>,
+        subsystem: Subsys,
+        error_actions: ErrorActions,
+        detached: bool,
+        options: RunnerOptions,
+        hooks: RunnerHooks<ErrType>,
+    ) -> NestedSubsystem<ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: Into<ErrType>,
+    {
+        self.start_many_with_abs_name(vec![ChildSpec {
+            name,
+            subsystem,
+            error_actions: Arc::new(error_actions),
+            detached,
+            options,
+            hooks,
+            post_start: PostStart::default(),
+        }])
+        .pop()
+        .expect("One subsystem per spec")
+    }
+
+    /// Starts multiple children at once.
+    ///
+    /// Only takes the locks of the shared collections and counters once,
+    /// instead of once per child.
+    #[track_caller]
+    fn start_many_with_abs_name<Err, Fut, Subsys>(
+        &self,
+        specs: Vec<ChildSpec<Subsys, ErrType>>,
+    ) -> Vec<NestedSubsystem<ErrType>>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: Into<ErrType>,
+    {
+        let depth = self.inner.depth + 1;
+        if let Some(max_depth) = *self.inner.max_depth.lock().unwrap() {
+            for spec in &specs {
+                assert!(
+                    depth <= max_depth,
+                    "Subsystem '{}' exceeds the maximum subsystem depth of {}",
+                    spec.name,
+                    max_depth - 1
+                );
+            }
+        }
+        let parent_supervisor = self.inner.supervisor.lock().unwrap().clone();
+
+        let mut prepared = Vec::with_capacity(specs.len());
+        let mut on_errors = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let (error_sender, errors) = mpsc::unbounded_channel();
+
+            let cancellation_token = if spec.detached || spec.post_start.forward_shutdown {
+                CancellationToken::new()
+            } else {
+                self.inner.cancellation_token.child_token()
+            };
+
+            on_errors.push({
+                let cancellation_token = cancellation_token.clone();
+                let error_actions = Arc::clone(&spec.error_actions);
+                Box::new(move |e| match error_actions.resolve(&e) {
+                    ErrorAction::Forward | ErrorAction::CatchUntilThreshold { .. } => Some(e),
+                    ErrorAction::CatchAndLocalShutdown => {
+                        handle_dropped_error(error_sender.send(e));
+                        cancellation_token.cancel();
+                        None
+                    }
+                }) as OnError<ErrType>
+            });
+            prepared.push((spec, cancellation_token, errors));
+        }
+        let joiner_tokens = self.inner.joiner_token.child_tokens(on_errors);
+
+        let mut started = Vec::with_capacity(prepared.len());
+        let mut runners = Vec::with_capacity(prepared.len());
+        for ((spec, cancellation_token, errors), (joiner_token, joiner_token_ref)) in
+            prepared.into_iter().zip(joiner_tokens)
+        {
+            let alive_guard = AliveGuard::new();
+            let name = Arc::new(Mutex::new(spec.name));
+            let shutdown_acknowledged = Arc::new(AtomicBool::new(false));
+            let ready = Arc::new(watch::channel(false).0);
+            let tree_node = Arc::new(TreeNode::new(Arc::clone(&name), cancellation_token.clone()));
+            let outcomes = Arc::new(OutcomeLog::default());
+            let outcome_logs = self
+                .inner
+                .outcome_logs
+                .iter()
+                .cloned()
+                .chain(std::iter::once(Arc::downgrade(&outcomes)))
+                .collect();
+
+            let child_handle = SubsystemHandle {
+                inner: ManuallyDrop::new(Inner {
+                    name: Arc::clone(&name),
+                    cancellation_token: cancellation_token.clone(),
+                    shutdown_initiator: Arc::clone(&self.inner.shutdown_initiator),
+                    shutdown_deadline: Arc::clone(&self.inner.shutdown_deadline),
+                    events: self.inner.events.clone(),
+                    samples: self.inner.samples.clone(),
+                    shutdown_acknowledged: Arc::clone(&shutdown_acknowledged),
+                    shutdown_flag: Default::default(),
+                    children_outlive_parent: Default::default(),
+                    detached: AtomicBool::new(false),
+                    shutdown_authority: self.inner.shutdown_authority
+                        && spec.options.shutdown_authority,
+                    ready: Mutex::new(Arc::clone(&ready)),
+                    supervisor: Default::default(),
+                    parent_supervisor: parent_supervisor.clone(),
+                    joiner_token,
+                    children: RemotelyDroppableItems::new(),
+                    detached_children: RemotelyDroppableItems::new(),
+                    detached_started: AtomicU64::new(0),
+                    running: Arc::clone(&self.inner.running),
+                    failure_log_limits: Arc::clone(&self.inner.failure_log_limits),
+                    leak_policy: Arc::clone(&self.inner.leak_policy),
+                    signal_handler_control: self.inner.signal_handler_control.clone(),
+                    paused: Arc::clone(&self.inner.paused),
+                    depth,
+                    max_depth: Arc::clone(&self.inner.max_depth),
+                    name_separator: self.inner.name_separator,
+                    tree_node: Arc::clone(&tree_node),
+                    toplevel_tree_node: Arc::clone(&self.inner.toplevel_tree_node),
+                    outcome_logs,
+                    runtime: self.inner.runtime.clone(),
+                    local_set: OnceLock::new(),
+                    span: Arc::new(Mutex::new(Span::none())),
+                }),
+                drop_redirect: None,
+            };
+
+            let runner = SubsystemRunner::new(
+                Arc::clone(&name),
+                spec.subsystem,
+                child_handle,
+                alive_guard.clone(),
+                spec.options,
+                self.inner.events.clone(),
+                spec.hooks,
+            );
+            let runner_abort_handle = runner.abort_handle();
+            tree_node.set_runner(runner_abort_handle.clone());
+            runners.push(runner);
+
+            let detached_dropper = spec.detached.then(|| {
+                self.inner.detached_children.insert(DetachedChild {
+                    index: self.inner.detached_started.fetch_add(1, Ordering::Relaxed),
+                    cancellation_token: cancellation_token.clone(),
+                    joiner: joiner_token_ref.clone(),
+                })
+            });
+
+            let mut nested = NestedSubsystem {
+                name,
+                joiner: joiner_token_ref,
+                cancellation_token,
+                errors: Mutex::new(ErrorCollector::new(errors)),
+                error_actions: spec.error_actions,
+                runner: runner_abort_handle,
+                shutdown_acknowledged,
+                ready,
+                restart_trigger: None,
+                outcomes: Some(outcomes),
+                tree_node,
+            };
+            self.apply_post_start(&mut nested, spec.post_start, parent_supervisor.as_ref());
+
+            started.push((alive_guard, detached_dropper, nested));
+        }
+
+        // Shenanigans to juggle child ownership
+        //
+        // RACE CONDITION SAFETY:
+        // If the subsystem ends before `on_finished` was able to be called, nothing bad happens.
+        // alive_guard will keep the guard alive and the callback will only be called inside of
+        // the guard's drop() implementation.
+        let child_droppers = self.inner.children.insert_many(runners);
+        let running_droppers = self.inner.running.register_many(
+            started
+                .iter()
+                .map(|(_, _, nested)| Arc::clone(&nested.name)),
+        );
+        let tree_node_droppers = self.inner.tree_node.insert_children(
+            started
+                .iter()
+                .map(|(_, _, nested)| Arc::clone(&nested.tree_node)),
+        );
+
+        started
+            .into_iter()
+            .zip(child_droppers)
+            .zip(running_droppers)
+            .zip(tree_node_droppers)
+            .map(
+                |(
+                    (((alive_guard, detached_dropper, nested), child_dropper), running_dropper),
+                    tree_node_dropper,
+                )| {
+                    alive_guard.on_finished(move || {
+                        drop(child_dropper);
+                        drop(detached_dropper);
+                        drop(tree_node_dropper);
+                        drop(running_dropper);
+                    });
+                    nested
+                },
+            )
+            .collect()
+    }
+
+    /// Waits until all the children of this subsystem are finished.
+    pub async fn wait_for_children(&self) {
+        self.inner.joiner_token.join_children().await
+    }
+
+    /// Waits until either a shutdown of this subsystem is requested,
+    /// or all of its children are finished, whichever happens first.
+    ///
+    /// Intended for manager subsystems that have to decide what to do once
+    /// their children exited on their own. If both happened already, a shutdown
+    /// request takes precedence. The returned future is cancellation safe.
+    ///
+    /// # Returns
+    ///
+    /// Which of the two happened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, WakeReason};
+    ///
+    /// async fn job(_subsys: SubsystemHandle) -> Result<()> {
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn manager(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start(SubsystemBuilder::new("Job", job));
+    ///
+    ///     match subsys.wait_shutdown_or_children_done().await {
+    ///         WakeReason::ShutdownRequested => tracing::info!("Stopping jobs ..."),
+    ///         WakeReason::ChildrenFinished => tracing::info!("All jobs done."),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_shutdown_or_children_done(&self) -> WakeReason {
+        tokio::select! {
+            biased;
+            _ = self.on_shutdown_requested() => WakeReason::ShutdownRequested,
+            _ = self.wait_for_children() => WakeReason::ChildrenFinished,
+        }
+    }
+
+    /// Waits until all the children of this subsystem are finished, but at most for the given duration.
+    ///
+    /// Behaves like [`wait_for_children()`](SubsystemHandle::wait_for_children), but gives up
+    /// once the timeout elapses. A timeout does not affect the children in any way; they keep
+    /// running and still get awaited before this subsystem is considered finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for the children to finish.
+    ///
+    /// # Returns
+    ///
+    /// [`Elapsed`] if the timeout expired.
+    pub async fn wait_for_children_with_timeout(&self, timeout: Duration) -> Result<(), Elapsed> {
+        tokio::time::timeout(timeout, self.wait_for_children()).await
+    }
+
+    /// Shuts down all detached children of this subsystem and waits until they are finished.
+    ///
+    /// Detached children do not receive the shutdown requests of their parent,
+    /// see [`start_detached()`](SubsystemHandle::start_detached). This method initiates their
+    /// shutdown in the given order, which is a shorthand for calling
+    /// [`NestedSubsystem::initiate_shutdown()`] and [`NestedSubsystem::join()`] on every one of them.
+    ///
+    /// Errors of the children are handled like usual, according to their configured
+    /// [`ErrorAction`]s. Children that get started while this method runs are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order in which the children get shut down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::{ShutdownOrder, SubsystemHandle};
+    ///
+    /// async fn nested_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start_detached("Database", nested_subsystem);
+    ///     subsys.start_detached("Server", nested_subsystem);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///
+    ///     // Stop the server before the database it depends on
+    ///     subsys.shutdown_detached_children(ShutdownOrder::Reverse).await;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn shutdown_detached_children(&self, order: ShutdownOrder) {
+        let mut children = self.inner.detached_children.items();
+        children.sort_by_key(|child| child.index);
+
+        match order {
+            ShutdownOrder::Sequential => (),
+            ShutdownOrder::Reverse => children.reverse(),
+            ShutdownOrder::Concurrent => {
+                for child in &children {
+                    child.cancellation_token.cancel();
+                }
+            }
+        }
+
+        for child in children {
+            child.cancellation_token.cancel();
+            child.joiner.join().await;
+        }
+    }
+
+    /// Waits until `count` direct children of this subsystem are finished.
+    ///
+    /// Only children that finish after this method was called are counted,
+    /// including children that get started while waiting. A child counts as
+    /// finished once it and all of its own children are finished.
+    ///
+    /// If fewer than `count` children finish, this method waits forever,
+    /// so it is usually combined with [`on_shutdown_requested()`](SubsystemHandle::on_shutdown_requested)
+    /// in a [`tokio::select`].
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of children to wait for.
+    pub async fn wait_for_children_count(&self, count: usize) {
+        self.inner.joiner_token.join_direct_children(count).await
+    }
+
+    /// Returns the number of direct children of this subsystem that are still running.
+    ///
+    /// A child counts as running until it and all of its own children are finished.
+    pub fn child_count(&self) -> u32 {
+        self.inner.joiner_token.direct_children_count()
+    }
+
+    /// Returns the number of subsystems in the entire subtree below this subsystem
+    /// that are still running, meaning children, grandchildren and so on.
+    pub fn descendant_count(&self) -> u32 {
+        self.inner.joiner_token.count()
+    }
+
+    /// Waits until the subsystem with the given absolute name is finished.
+    ///
+    /// The subsystem can be located anywhere in the tree, like a cousin of this subsystem,
+    /// which allows coordinating with it without holding its [`NestedSubsystem`].
+    /// If multiple running subsystems share the name, waits for all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The absolute name of the subsystem, like `/subsys/nested`.
+    ///
+    /// # Returns
+    ///
+    /// [`SubsystemNotFound`] if no subsystem with the given name is running,
+    /// either because it never existed or because it is already finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
+    ///
+    /// async fn database(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     // Flush pending writes
+    ///     sleep(Duration::from_millis(100)).await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn backup(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     // The backup must only start once the database is flushed
+    ///     subsys.wait_for_subsystem("/storage/database").await?;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn storage(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.start(SubsystemBuilder::new("database", database));
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_for_subsystem(&self, name: &str) -> Result<(), SubsystemNotFound> {
+        let finished = self.inner.running.finished_tokens(name);
+        if finished.is_empty() {
+            return Err(SubsystemNotFound {
+                name: Arc::from(name),
+            });
+        }
+        for finished in finished {
+            finished.cancelled().await;
+        }
+        Ok(())
+    }
+
+    // For internal use only - should never be used by users.
+    // Required as a short-lived second reference inside of `runner`.
+    pub(crate) fn delayed_clone(&mut self) -> oneshot::Receiver<WeakSubsystemHandle<ErrType>> {
+        let (sender, receiver) = oneshot::channel();
+
+        let previous = self.drop_redirect.replace(sender);
+        assert!(previous.is_none());
+
+        receiver
+    }
+
+    /// Wait for the shutdown mode to be triggered.
+    ///
+    /// Once the shutdown mode is entered, all existing calls to this
+    /// method will be released and future calls to this method will
+    /// return immediately.
+    ///
+    /// This is the primary method of subsystems to react to
+    /// the shutdown requests. Most often, it will be used in [`tokio::select`]
+    /// statements to cancel other code as soon as the shutdown is requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn countdown() {
+    ///     for i in (1..10).rev() {
+    ///         tracing::info!("Countdown: {}", i);
+    ///         sleep(Duration::from_millis(1000)).await;
+    ///     }
+    /// }
+    ///
+    /// async fn countdown_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     tracing::info!("Starting countdown ...");
+    ///
+    ///     // This cancels the countdown as soon as shutdown
+    ///     // mode was entered
+    ///     tokio::select! {
+    ///         _ = subsys.on_shutdown_requested() => {
+    ///             tracing::info!("Countdown cancelled.");
+    ///         },
+    ///         _ = countdown() => {
+    ///             tracing::info!("Countdown finished.");
+    ///         }
+    ///     };
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn on_shutdown_requested(&self) {
+        self.inner.cancellation_token.cancelled().await;
+        self.acknowledge_shutdown();
+    }
+
+    /// Returns whether a shutdown should be performed now.
+    ///
+    /// This method is provided for subsystems that need to query the shutdown
+    /// request state repeatedly.
+    ///
+    /// This can be useful in scenarios where a subsystem depends on the graceful
+    /// shutdown of its nested coroutines before it can run final cleanup steps itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn uncancellable_action(subsys: &SubsystemHandle) {
+    ///     tokio::select! {
+    ///         // Execute an action. A dummy `sleep` in this case.
+    ///         _ = sleep(Duration::from_millis(1000)) => {
+    ///             tracing::info!("Action finished.");
+    ///         }
+    ///         // Perform a shutdown if requested
+    ///         _ = subsys.on_shutdown_requested() => {
+    ///             tracing::info!("Action aborted.");
+    ///         },
+    ///     }
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     tracing::info!("Starting subsystem ...");
+    ///
+    ///     // We cannot do a `tokio::select` with `on_shutdown_requested`
+    ///     // here, because a shutdown would cancel the action without giving
+    ///     // it the chance to react first.
+    ///     while !subsys.is_shutdown_requested() {
+    ///         uncancellable_action(&subsys).await;
+    ///     }
+    ///
+    ///     tracing::info!("Subsystem stopped.");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn is_shutdown_requested(&self) -> bool {
+        let requested = self.inner.cancellation_token.is_cancelled();
+        if requested {
+            self.acknowledge_shutdown();
+        }
+        requested
+    }
+
+    /// Returns a flag that gets set once a shutdown of this subsystem is requested.
+    ///
+    /// Meant for blocking code, like [`spawn_blocking`](tokio::task::spawn_blocking) work
+    /// or FFI calls, that cannot await [`on_shutdown_requested()`](Self::on_shutdown_requested)
+    /// and has to poll for the shutdown request periodically instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::{IntoDiagnostic, Result};
+    /// use std::sync::atomic::Ordering;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn blocking_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let shutdown_flag = subsys.shutdown_flag();
+    ///
+    ///     tokio::task::spawn_blocking(move || {
+    ///         while !shutdown_flag.load(Ordering::Acquire) {
+    ///             std::thread::sleep(std::time::Duration::from_millis(10));
+    ///         }
+    ///     })
+    ///     .await
+    ///     .into_diagnostic()
+    /// }
+    /// ```
+    pub fn shutdown_flag(&self) -> Arc<AtomicBool> {
+        let mut shutdown_flag = self.inner.shutdown_flag.lock().unwrap();
+        let flag = shutdown_flag.get_or_insert_with(|| {
+            let token = self.inner.cancellation_token.clone();
+            let flag = Arc::new(AtomicBool::new(token.is_cancelled()));
+            if !token.is_cancelled() {
+                // Only hold a weak reference, so the flag gets freed
+                // once nobody is interested in it any more
+                let weak_flag = Arc::downgrade(&flag);
+                crate::tokio_task::spawn(
+                    async move {
+                        token.cancelled().await;
+                        if let Some(flag) = weak_flag.upgrade() {
+                            flag.store(true, Ordering::Release);
+                        }
+                    },
+                    "shutdown_flag",
+                    self.runtime(),
+                );
+            }
+            flag
+        });
+        Arc::clone(flag)
+    }
+
+    fn acknowledge_shutdown(&self) {
+        self.inner
+            .shutdown_acknowledged
+            .store(true, Ordering::Release);
+    }
+
+    /// Triggers a shutdown of the entire subsystem tree.
+    ///
+    /// # Examples
+    ///
     /// ```
     /// use miette::Result;
     /// use tokio::time::{sleep, Duration};
@@ -303,8 +1725,93 @@ impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
     ///     Ok(())
     /// }
     /// ```
+    ///
+    /// Does nothing except logging a warning if the subsystem got started through
+    /// [`SubsystemBuilder::without_shutdown_authority()`].
     pub fn request_shutdown(&self) {
-        self.inner.toplevel_cancellation_token.cancel();
+        if !self.inner.shutdown_authority {
+            tracing::warn!(
+                subsystem = %self.name(),
+                "Ignoring shutdown request of subsystem without shutdown authority."
+            );
+            return;
+        }
+        self.inner.shutdown_initiator.initiate();
+    }
+
+    /// Triggers a shutdown of all subsystems of the given group,
+    /// including their children.
+    ///
+    /// Affects all subsystems of the entire tree that were added to the group through
+    /// [`SubsystemBuilder::group()`] and are still running. Like
+    /// [`NestedSubsystem::initiate_shutdown()`], this only shuts down the members
+    /// themselves; the rest of the tree keeps running.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The name of the group.
+    pub fn shutdown_group(&self, group: &str) {
+        self.inner.toplevel_tree_node.shutdown_group(group);
+    }
+
+    /// Triggers a shutdown of the entire subsystem tree, but only if this
+    /// is the last running child of its parent.
+    ///
+    /// Intended for pools of workers where a failing worker should only stop itself,
+    /// unless no other worker is left. Calling this method announces that this subsystem
+    /// is about to finish, so the subsystem should return soon after; siblings that
+    /// called this method as well no longer count as running.
+    ///
+    /// # Returns
+    ///
+    /// Whether the shutdown was triggered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+    ///     tokio::select! {
+    ///         _ = subsys.on_shutdown_requested() => (),
+    ///         _ = sleep(Duration::from_millis(1000)) => {
+    ///             tracing::error!("Worker failed.");
+    ///             // Only stop the program once all workers are gone
+    ///             subsys.request_shutdown_if_last();
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn request_shutdown_if_last(&self) -> bool {
+        let is_last = self.inner.joiner_token.leave_siblings();
+        if is_last {
+            self.request_shutdown();
+        }
+        is_last
+    }
+
+    /// Aborts all subsystems of the entire subsystem tree immediately.
+    ///
+    /// Unlike [`request_shutdown()`](Self::request_shutdown), this does not give
+    /// the subsystems a chance to shut down gracefully; all of them get cancelled
+    /// at their next `.await` point, and in-flight work is lost. Intended as an
+    /// emergency stop.
+    ///
+    /// This also aborts the subsystem that calls this function. Aborted subsystems
+    /// get logged as cancelled, but are not reported as errors. The only exception is
+    /// the root subsystem of the [`Toplevel`](crate::Toplevel): if it gets aborted before
+    /// it returned, it could not produce its output, so it gets reported as
+    /// [`SubsystemError::Cancelled`] in a
+    /// [`GracefulShutdownError::SubsystemsFailed`](crate::errors::GracefulShutdownError::SubsystemsFailed) error.
+    pub fn abort_all(&self) {
+        self.inner.shutdown_initiator.token().cancel();
+        self.inner
+            .toplevel_tree_node
+            .abort_all(CancelReason::Aborted);
     }
 
     /// Triggers a shutdown of the current subsystem and all
@@ -317,6 +1824,10 @@ impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
         &self.inner.cancellation_token
     }
 
+    pub(crate) fn get_toplevel_cancellation_token(&self) -> &CancellationToken {
+        self.inner.shutdown_initiator.token()
+    }
+
     /// Creates a cancellation token that will get triggered once the
     /// subsystem shuts down.
     ///
@@ -329,14 +1840,514 @@ impl<ErrType: ErrTypeTraits> SubsystemHandle<ErrType> {
         self.inner.cancellation_token.child_token()
     }
 
+    /// Returns the cancellation token of this subsystem.
+    ///
+    /// The token gets cancelled once the subsystem shuts down. Unlike the tokens
+    /// returned by [`create_cancellation_token()`](Self::create_cancellation_token),
+    /// this is the subsystem's own token and not a child of it, so it can be passed
+    /// to third-party APIs that should observe the exact local shutdown signal.
+    ///
+    /// Note that cancelling the returned token initiates a shutdown of this subsystem,
+    /// like [`NestedSubsystem::initiate_shutdown()`] does.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.inner.cancellation_token.clone()
+    }
+
+    /// Runs `f` with a handle whose shutdown can additionally be triggered through `token`.
+    ///
+    /// This creates an independently cancellable region, for example to test the
+    /// shutdown behavior of a block of work in isolation, without starting a
+    /// detached subsystem. Inside of `f`, [`on_shutdown_requested()`](Self::on_shutdown_requested)
+    /// and [`is_shutdown_requested()`](Self::is_shutdown_requested) reflect `token`,
+    /// and nested subsystems started through the given handle shut down once `token`
+    /// gets cancelled. A shutdown of this subsystem still reaches the region as well.
+    /// Everything else, like the name and error propagation, is shared with this subsystem.
+    ///
+    /// Like in a subsystem, nested subsystems get cancelled once the given handle
+    /// is dropped, so `f` should wait for them through
+    /// [`wait_for_children()`](Self::wait_for_children) before it returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token that additionally shuts down the region inside of `f`.
+    /// * `f` - The work to run with the overridden shutdown.
+    ///
+    /// # Returns
+    ///
+    /// The output of `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{timeout, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let batch_token = CancellationToken::new();
+    ///
+    ///     // Stop the batch after one second, independent of the shutdown of the subsystem
+    ///     let _ = timeout(
+    ///         Duration::from_secs(1),
+    ///         subsys.with_shutdown_token(batch_token.clone(), |batch| async move {
+    ///             batch.on_shutdown_requested().await;
+    ///         }),
+    ///     )
+    ///     .await;
+    ///     batch_token.cancel();
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn with_shutdown_token<F, Fut>(&self, token: CancellationToken, f: F) -> Fut::Output
+    where
+        F: FnOnce(SubsystemHandle<ErrType>) -> Fut,
+        Fut: Future,
+    {
+        let scoped_token = self.inner.cancellation_token.child_token();
+
+        let forward_shutdown = async {
+            token.cancelled().await;
+            scoped_token.cancel();
+            std::future::pending::<Infallible>().await
+        };
+
+        tokio::select! {
+            output = f(self.scoped_handle(scoped_token.clone())) => output,
+            never = forward_shutdown => match never {},
+        }
+    }
+
+    /// Creates a second handle of this subsystem with a different cancellation token.
+    ///
+    /// Its children are accounted to this subsystem, and their errors get forwarded to it.
+    fn scoped_handle(&self, cancellation_token: CancellationToken) -> SubsystemHandle<ErrType> {
+        let (joiner_token, _) = self
+            .inner
+            .joiner_token
+            .child_tokens(vec![Box::new(Some) as OnError<ErrType>])
+            .pop()
+            .expect("One token per callback");
+
+        SubsystemHandle {
+            inner: ManuallyDrop::new(Inner {
+                name: Arc::clone(&self.inner.name),
+                cancellation_token,
+                shutdown_initiator: Arc::clone(&self.inner.shutdown_initiator),
+                shutdown_deadline: Arc::clone(&self.inner.shutdown_deadline),
+                events: self.inner.events.clone(),
+                samples: self.inner.samples.clone(),
+                shutdown_acknowledged: Default::default(),
+                shutdown_flag: Default::default(),
+                children_outlive_parent: Default::default(),
+                detached: AtomicBool::new(false),
+                shutdown_authority: self.inner.shutdown_authority,
+                ready: Mutex::new(self.readiness()),
+                supervisor: Default::default(),
+                parent_supervisor: self.inner.parent_supervisor.clone(),
+                joiner_token,
+                children: RemotelyDroppableItems::new(),
+                detached_children: RemotelyDroppableItems::new(),
+                detached_started: AtomicU64::new(0),
+                running: Arc::clone(&self.inner.running),
+                failure_log_limits: Arc::clone(&self.inner.failure_log_limits),
+                leak_policy: Arc::clone(&self.inner.leak_policy),
+                signal_handler_control: self.inner.signal_handler_control.clone(),
+                paused: Arc::clone(&self.inner.paused),
+                depth: self.inner.depth,
+                max_depth: Arc::clone(&self.inner.max_depth),
+                name_separator: self.inner.name_separator,
+                tree_node: Arc::clone(&self.inner.tree_node),
+                toplevel_tree_node: Arc::clone(&self.inner.toplevel_tree_node),
+                outcome_logs: Arc::clone(&self.inner.outcome_logs),
+                runtime: self.inner.runtime.clone(),
+                local_set: OnceLock::new(),
+                span: Arc::clone(&self.inner.span),
+            }),
+            drop_redirect: None,
+        }
+    }
+
+    /// Returns a future that resolves once this subsystem shuts down.
+    ///
+    /// The future does not borrow the subsystem handle, which makes it
+    /// usable as the shutdown signal of an [axum](https://docs.rs/axum) server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use axum::{routing::get, Router};
+    /// use miette::{IntoDiagnostic, Result};
+    /// use tokio::net::TcpListener;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn axum_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:12345").await.into_diagnostic()?;
+    ///     let app = Router::new().route("/", get(|| async { "Hello World!" }));
+    ///
+    ///     axum::serve(listener, app)
+    ///         .with_graceful_shutdown(subsys.axum_shutdown_signal())
+    ///         .await
+    ///         .into_diagnostic()
+    /// }
+    /// ```
+    #[cfg(feature = "axum")]
+    pub fn axum_shutdown_signal(&self) -> impl Future<Output = ()> + Send + 'static {
+        let cancellation_token = self.inner.cancellation_token.clone();
+        async move { cancellation_token.cancelled().await }
+    }
+
+    /// Returns the point in time at which the shutdown of the entire
+    /// subsystem tree will time out.
+    ///
+    /// Returns `None` as long as no shutdown is in progress.
+    ///
+    /// See [`Toplevel::handle_shutdown_requests()`](crate::Toplevel::handle_shutdown_requests)
+    /// for how to set the shutdown timeout.
+    pub fn shutdown_deadline(&self) -> Option<Instant> {
+        *self.inner.shutdown_deadline.borrow()
+    }
+
+    /// Returns the time that is left until the shutdown of the entire
+    /// subsystem tree times out.
+    ///
+    /// Returns `None` as long as no shutdown is in progress.
+    ///
+    /// This can be used to decide between a fast and a thorough cleanup.
+    pub fn time_until_shutdown_timeout(&self) -> Option<Duration> {
+        self.shutdown_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns a future that resolves once only `threshold` is left until the
+    /// shutdown of the entire subsystem tree times out.
+    ///
+    /// Resolves immediately if less than `threshold` is left once the shutdown starts.
+    /// Never resolves if the shutdown has no timeout, for example in a tree
+    /// created through [`new_root()`](Self::new_root).
+    ///
+    /// This can be used to skip optional cleanup work once the shutdown timeout is near.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The time that should be left when the future resolves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn optional_cleanup() {}
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///
+    ///     tokio::select! {
+    ///         _ = optional_cleanup() => (),
+    ///         _ = subsys.on_shutdown_deadline_warning(Duration::from_millis(100)) => {
+    ///             tracing::warn!("Skipping the rest of the cleanup.");
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_shutdown_deadline_warning(
+        &self,
+        threshold: Duration,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let mut shutdown_deadline = self.inner.shutdown_deadline.subscribe();
+        async move {
+            let Ok(deadline) = shutdown_deadline
+                .wait_for(Option::is_some)
+                .await
+                .map(|deadline| *deadline)
+            else {
+                // The tree is gone without ever starting a shutdown
+                return std::future::pending().await;
+            };
+            if let Some(warning) = deadline.and_then(|deadline| deadline.checked_sub(threshold)) {
+                tokio::time::sleep_until(warning).await;
+            }
+        }
+    }
+
+    pub(crate) fn get_lifecycle_events(&self) -> &LifecycleEvents {
+        &self.inner.events
+    }
+
+    pub(crate) fn get_samples(&self) -> &Samples {
+        &self.inner.samples
+    }
+
+    pub(crate) fn outcome_logs(&self) -> Arc<[Weak<OutcomeLog>]> {
+        Arc::clone(&self.inner.outcome_logs)
+    }
+
+    /// Whether the children should keep running on their own once this subsystem returned.
+    pub(crate) fn children_outlive_parent(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.inner.children_outlive_parent)
+    }
+
+    pub(crate) fn shutdown_initiator(&self) -> &Arc<ShutdownInitiator> {
+        &self.inner.shutdown_initiator
+    }
+
+    pub(crate) fn runtime(&self) -> Option<&Handle> {
+        self.inner.runtime.as_ref()
+    }
+
+    pub(crate) fn tree_node(&self) -> &Arc<TreeNode> {
+        &self.inner.tree_node
+    }
+
+    /// The name of this subsystem, as it gets changed by [`set_name()`](Self::set_name).
+    pub(crate) fn name_cell(&self) -> Arc<Mutex<Arc<str>>> {
+        Arc::clone(&self.inner.name)
+    }
+
+    /// Sets the span the subsystem function runs in, to record renames in.
+    pub(crate) fn set_span(&self, span: Span) {
+        *self.inner.span.lock().unwrap() = span;
+    }
+
+    /// The names of all subsystems of the entire tree that are still running.
+    pub(crate) fn running_subsystems(&self) -> Vec<Arc<str>> {
+        self.inner.running.names()
+    }
+
+    /// Returns the control to pause and resume the signal handling
+    /// of the [`Toplevel`](crate::Toplevel) this subsystem belongs to.
+    ///
+    /// See [`SignalHandlerControl`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn migrate_database() {
+    ///     // A critical operation that must not be interrupted
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     let signal_handler_control = subsys.signal_handler_control();
+    ///
+    ///     signal_handler_control.pause();
+    ///     migrate_database().await;
+    ///     signal_handler_control.resume();
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn signal_handler_control(&self) -> SignalHandlerControl {
+        self.inner.signal_handler_control.clone()
+    }
+
+    /// Returns whether the subsystem tree is currently paused.
+    ///
+    /// The tree gets paused and resumed through `SIGTSTP` and `SIGCONT`
+    /// if the [`Toplevel`](crate::Toplevel) was configured with
+    /// [`catch_pause_signals()`](crate::Toplevel::catch_pause_signals).
+    ///
+    /// Pausing is purely cooperative; subsystems should check this in their loops
+    /// and stop picking up new work while paused.
+    pub fn is_paused(&self) -> bool {
+        *self.inner.paused.borrow()
+    }
+
+    /// Wait until the subsystem tree is no longer paused.
+    ///
+    /// Returns immediately if the tree is not paused. A shutdown request
+    /// resumes the tree, so this never blocks a shutdown.
+    ///
+    /// For more information, see [`is_paused()`](SubsystemHandle::is_paused).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::SubsystemHandle;
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     while !subsys.is_shutdown_requested() {
+    ///         subsys.on_resume().await;
+    ///
+    ///         // Process the next batch of work
+    ///         sleep(Duration::from_millis(100)).await;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_resume(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut paused = self.inner.paused.subscribe();
+        async move {
+            // The sender lives as long as the subsystem tree
+            let _ = paused.wait_for(|paused| !paused).await;
+        }
+    }
+
+    /// Whether the subsystem tree is paused; shared by the entire tree.
+    pub(crate) fn pause_state(&self) -> Arc<watch::Sender<bool>> {
+        Arc::clone(&self.inner.paused)
+    }
+
+    pub(crate) fn leak_policy(&self) -> Arc<Mutex<LeakPolicy>> {
+        Arc::clone(&self.inner.leak_policy)
+    }
+
+    pub(crate) fn set_leak_policy(&self, policy: LeakPolicy) {
+        *self.inner.leak_policy.lock().unwrap() = policy;
+    }
+
+    /// Limits the number of ancestors that subsystems of the entire tree can have.
+    pub(crate) fn set_max_depth(&self, max_depth: usize) {
+        *self.inner.max_depth.lock().unwrap() = Some(max_depth);
+    }
+
+    pub(crate) fn name_separator(&self) -> char {
+        self.inner.name_separator
+    }
+
+    /// Allows marking this subsystem as finished even if the handle gets leaked.
+    pub(crate) fn joiner_token_finisher(&self) -> JoinerTokenFinisher<ErrType> {
+        self.inner.joiner_token.finisher()
+    }
+
+    /// Gets notified whenever a subsystem of the entire tree stopped running.
+    pub(crate) fn running_changed(&self) -> &Notify {
+        self.inner.running.changed()
+    }
+
+    pub(crate) fn set_shutdown_deadline(&self, deadline: Instant) {
+        self.inner.shutdown_deadline.send_replace(Some(deadline));
+    }
+
+    /// Reports the health of this subsystem.
+    ///
+    /// The health is purely informational and does not influence the lifecycle
+    /// of any subsystem. It can be observed by the parent through
+    /// [`NestedSubsystem::health()`], or for the entire tree through
+    /// [`Toplevel::aggregate_health()`](crate::Toplevel::aggregate_health),
+    /// for example to implement a readiness probe.
+    ///
+    /// Every subsystem starts out as [`HealthStatus::Healthy`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::{HealthStatus, SubsystemHandle};
+    ///
+    /// async fn connect() -> Result<()> {
+    ///     sleep(Duration::from_millis(100)).await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.set_health(HealthStatus::Unhealthy);
+    ///     connect().await?;
+    ///     subsys.set_health(HealthStatus::Healthy);
+    ///
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_health(&self, health: HealthStatus) {
+        self.inner.tree_node.set_health(health);
+    }
+
     /// Get the name associated with this subsystem.
     ///
     /// Note that the names of nested subsystems are built unix-path alike,
-    /// starting and delimited by slashes (e.g. `/a/b/c`).
+    /// starting and delimited by slashes (e.g. `/a/b/c`). The separator can be changed
+    /// through [`ToplevelBuilder::with_name_separator()`](crate::ToplevelBuilder::with_name_separator).
     ///
     /// See [`SubsystemBuilder::new()`] how to set this name.
-    pub fn name(&self) -> &str {
-        &self.inner.name
+    pub fn name(&self) -> SubsystemName {
+        SubsystemName::new(Arc::clone(&self.inner.name.lock().unwrap()))
+    }
+
+    /// Whether this subsystem got started detached.
+    ///
+    /// A detached subsystem does not receive the shutdown requests of its parent;
+    /// see [`SubsystemBuilder::detached()`].
+    pub fn is_detached(&self) -> bool {
+        self.inner.detached.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn mark_detached(&self) {
+        self.inner.detached.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn set_supervision(&self, strategy: SupervisionStrategy) {
+        *self.inner.supervisor.lock().unwrap() = Some(Arc::new(Supervisor::new(strategy)));
+    }
+
+    pub(crate) fn parent_supervisor(&self) -> Option<Arc<Supervisor>> {
+        self.inner.parent_supervisor.clone()
+    }
+
+    /// Signals that this subsystem finished its initialization.
+    ///
+    /// Starts the subsystems that were configured to
+    /// [`start_after()`](SubsystemBuilder::start_after) this subsystem,
+    /// and resolves [`NestedSubsystem::ready()`]. Calling it more than once has no effect.
+    pub fn mark_ready(&self) {
+        self.inner.ready.lock().unwrap().send_replace(true);
+    }
+
+    /// Makes [`mark_ready()`](Self::mark_ready) mark the given readiness instead,
+    /// for subsystems that stand in for another one.
+    pub(crate) fn share_readiness(&self, ready: Arc<watch::Sender<bool>>) {
+        *self.inner.ready.lock().unwrap() = ready;
+    }
+
+    pub(crate) fn readiness(&self) -> Arc<watch::Sender<bool>> {
+        Arc::clone(&self.inner.ready.lock().unwrap())
+    }
+
+    /// Renames this subsystem.
+    ///
+    /// The given name replaces the last segment of the absolute name,
+    /// analogous to the name given to [`SubsystemBuilder::new()`].
+    ///
+    /// The new name will be used by all errors, log messages and samples that get
+    /// produced afterwards, by the tracing span of the subsystem,
+    /// and by all nested subsystems that get started afterwards.
+    /// It does not retroactively change messages that were already emitted,
+    /// and it does not rename nested subsystems that are already running.
+    pub fn set_name(&self, name: impl Into<Arc<str>>) {
+        let name: Arc<str> = name.into();
+        let mut current = self.inner.name.lock().unwrap();
+        let separator = self.name_separator();
+        let parent_len = current
+            .rfind(separator)
+            .map_or(0, |pos| pos + separator.len_utf8());
+        let renamed: Arc<str> = Arc::from(format!("{}{}", &current[..parent_len], name));
+
+        // The errors of the subsystem get reported under the new name
+        self.inner
+            .failure_log_limits
+            .rename(&current, Arc::clone(&renamed));
+        self.inner
+            .span
+            .lock()
+            .unwrap()
+            .record("name", tracing::field::display(&renamed));
+        // `wait_for_subsystem()` finds the subsystem under the new name
+        self.inner
+            .running
+            .rename(&current, Arc::clone(&renamed), &self.inner.name);
+        *current = renamed;
     }
 }
 
@@ -365,21 +2376,58 @@ impl<ErrType: ErrTypeTraits> Drop for SubsystemHandle<ErrType> {
 
 pub(crate) fn root_handle<ErrType: ErrTypeTraits>(
     on_error: impl Fn(SubsystemError<ErrType>) + Sync + Send + 'static,
+    failure_log_limits: Arc<FailureLogLimits>,
+    runtime: Option<Handle>,
+    name_separator: char,
 ) -> SubsystemHandle<ErrType> {
     let cancellation_token = CancellationToken::new();
+    let shutdown_initiator = Arc::new(ShutdownInitiator::new(
+        cancellation_token.clone(),
+        // Handles created outside of a runtime only get a prestop hook through a Toplevel
+        runtime.clone().or_else(|| Handle::try_current().ok()),
+    ));
+    let name = Arc::new(Mutex::new(Arc::from("")));
+    let tree_node = Arc::new(TreeNode::new(Arc::clone(&name), cancellation_token.clone()));
 
     SubsystemHandle {
         inner: ManuallyDrop::new(Inner {
-            name: Arc::from(""),
+            name,
             cancellation_token: cancellation_token.clone(),
-            toplevel_cancellation_token: cancellation_token.clone(),
+            shutdown_initiator: Arc::clone(&shutdown_initiator),
+            shutdown_deadline: Arc::new(watch::channel(None).0),
+            events: LifecycleEvents::new(),
+            samples: Samples::new(),
+            shutdown_acknowledged: Default::default(),
+            shutdown_flag: Default::default(),
+            children_outlive_parent: Default::default(),
+            detached: AtomicBool::new(false),
+            shutdown_authority: true,
+            ready: Mutex::new(Arc::new(watch::channel(false).0)),
+            supervisor: Default::default(),
+            parent_supervisor: None,
             joiner_token: JoinerToken::new(move |e| {
                 on_error(e);
-                cancellation_token.cancel();
+                shutdown_initiator.initiate();
                 None
             })
             .0,
             children: RemotelyDroppableItems::new(),
+            detached_children: RemotelyDroppableItems::new(),
+            detached_started: AtomicU64::new(0),
+            running: Arc::new(SubsystemRegistry::new()),
+            failure_log_limits,
+            leak_policy: Default::default(),
+            signal_handler_control: Default::default(),
+            paused: Arc::new(watch::channel(false).0),
+            depth: 0,
+            max_depth: Default::default(),
+            name_separator,
+            toplevel_tree_node: Arc::clone(&tree_node),
+            outcome_logs: Arc::from([]),
+            tree_node,
+            runtime,
+            local_set: OnceLock::new(),
+            span: Arc::new(Mutex::new(Span::none())),
         }),
         drop_redirect: None,
     }
@@ -4,7 +4,7 @@ use super::*;
 
 #[tokio::test]
 async fn recursive_cancellation() {
-    let root_handle = root_handle::<BoxedError>(|_| {});
+    let root_handle = root_handle::<BoxedError>(|_| {}, Default::default(), None, '/');
 
     let (drop_sender, mut drop_receiver) = tokio::sync::mpsc::channel::<()>(1);
 
@@ -30,7 +30,7 @@ async fn recursive_cancellation() {
 
 #[tokio::test]
 async fn recursive_cancellation_2() {
-    let root_handle = root_handle(|_| {});
+    let root_handle = root_handle(|_| {}, Default::default(), None, '/');
 
     let (drop_sender, mut drop_receiver) = tokio::sync::mpsc::channel::<()>(1);
 
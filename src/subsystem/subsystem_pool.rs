@@ -0,0 +1,112 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::sync::{mpsc, watch};
+
+use crate::{
+    errors::SubsystemJoinError, ErrTypeTraits, NestedSubsystem, SubsystemBuilder, SubsystemHandle,
+};
+
+use super::SubsystemPool;
+
+impl<ErrType: ErrTypeTraits> SubsystemPool<ErrType> {
+    pub(crate) fn new(pool: NestedSubsystem<ErrType>, size: watch::Sender<usize>) -> Self {
+        Self { pool, size }
+    }
+
+    /// Changes the number of workers in the pool.
+    ///
+    /// Missing workers get started immediately. Surplus workers get shut down,
+    /// starting with the one with the highest index.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_count` - The new number of workers.
+    pub fn resize(&self, new_count: usize) {
+        self.size.send_replace(new_count);
+    }
+
+    /// Returns the number of workers the pool currently tries to keep alive.
+    pub fn size(&self) -> usize {
+        *self.size.borrow()
+    }
+
+    /// Wait for the pool and all of its workers to be finished.
+    ///
+    /// # Returns
+    ///
+    /// A [`SubsystemJoinError`] on failure.
+    pub async fn join(&self) -> Result<(), SubsystemJoinError<ErrType>> {
+        self.pool.join().await
+    }
+}
+
+pub(crate) async fn run_pool<ErrType, Err, Fut, Factory>(
+    subsys: SubsystemHandle<ErrType>,
+    mut size: watch::Receiver<usize>,
+    factory: Arc<Factory>,
+) -> Result<(), ErrType>
+where
+    ErrType: ErrTypeTraits,
+    Factory: 'static + Fn(SubsystemHandle<ErrType>) -> Fut + Send + Sync,
+    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Err: 'static + Into<ErrType>,
+{
+    let (finished_sender, mut finished_receiver) = mpsc::unbounded_channel();
+    let mut workers: Vec<(u64, NestedSubsystem<ErrType>)> = Vec::new();
+    let mut generation = 0;
+
+    let mut start_worker = |index: usize| {
+        generation += 1;
+
+        let factory = Arc::clone(&factory);
+        let worker = subsys.start(SubsystemBuilder::new(
+            index.to_string(),
+            move |s: SubsystemHandle<ErrType>| factory(s),
+        ));
+
+        // Report back once the worker is finished, so it can be respawned.
+        // The generation makes sure that stale reports of workers that
+        // were already replaced get ignored.
+        let finished = worker.finished();
+        let finished_sender = finished_sender.clone();
+        let worker_generation = generation;
+        crate::tokio_task::spawn(
+            async move {
+                finished.await;
+                let _ = finished_sender.send((index, worker_generation));
+            },
+            "pool_worker_watcher",
+            subsys.runtime(),
+        );
+
+        (worker_generation, worker)
+    };
+
+    loop {
+        let target = *size.borrow_and_update();
+        while workers.len() > target {
+            if let Some((_, worker)) = workers.pop() {
+                worker.initiate_shutdown();
+            }
+        }
+        while workers.len() < target {
+            let worker = start_worker(workers.len());
+            workers.push(worker);
+        }
+
+        tokio::select! {
+            _ = subsys.on_shutdown_requested() => break,
+            Ok(()) = size.changed() => (),
+            Some((index, worker_generation)) = finished_receiver.recv() => {
+                let is_current = workers
+                    .get(index)
+                    .is_some_and(|(generation, _)| *generation == worker_generation);
+                if is_current && !subsys.is_shutdown_requested() {
+                    workers[index] = start_worker(index);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
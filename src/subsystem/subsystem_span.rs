@@ -0,0 +1,98 @@
+//! The tracing span that subsystems with span fields run in.
+//!
+//! Tracing requires the field names of a span to be known at compile time,
+//! in the form of a static callsite. The names of the span fields only get
+//! known at runtime, so a callsite gets created for every distinct set of names.
+//! Those callsites have to be `'static` and therefore get leaked; they get
+//! reused for all subsystems with the same field names.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use tracing::{field::display, level_filters::LevelFilter, Level, Metadata, Span};
+use tracing_core::{
+    callsite::{self, Callsite, Identifier},
+    field::FieldSet,
+    metadata::Kind,
+    subscriber::Interest,
+};
+
+struct SpanCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+}
+
+impl Callsite for SpanCallsite {
+    fn set_interest(&self, _interest: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("the metadata is set before the callsite gets registered")
+    }
+}
+
+static CALLSITES: Mutex<Option<HashMap<Vec<&'static str>, &'static SpanCallsite>>> =
+    Mutex::new(None);
+
+/// Returns the callsite of the `subsystem` span with the given field names.
+fn callsite(field_names: Vec<&'static str>) -> &'static SpanCallsite {
+    let mut callsites = CALLSITES.lock().unwrap();
+    let callsites = callsites.get_or_insert_with(HashMap::new);
+    if let Some(callsite) = callsites.get(&field_names) {
+        return callsite;
+    }
+
+    let callsite: &'static SpanCallsite = Box::leak(Box::new(SpanCallsite {
+        metadata: OnceLock::new(),
+    }));
+    let names: &'static [&'static str] = Box::leak(field_names.clone().into_boxed_slice());
+    callsite
+        .metadata
+        .set(Metadata::new(
+            "subsystem",
+            module_path!(),
+            Level::INFO,
+            Some(file!()),
+            Some(line!()),
+            Some(module_path!()),
+            FieldSet::new(names, Identifier(callsite)),
+            Kind::SPAN,
+        ))
+        .expect("the callsite is new");
+    callsite::register(callsite);
+
+    callsites.insert(field_names, callsite);
+    callsite
+}
+
+/// Creates the tracing span the subsystem function runs in.
+///
+/// Contains the subsystem name and every given field as a field of its own;
+/// if a key is given multiple times, the last value wins.
+/// Disabled if no fields were given, to not clutter the logs.
+pub(crate) fn subsystem_span(name: &str, fields: &[(&'static str, String)]) -> Span {
+    if fields.is_empty() || Level::INFO > LevelFilter::current() {
+        return Span::none();
+    }
+
+    let mut values: Vec<(&'static str, &str)> = vec![("name", name)];
+    for (key, value) in fields {
+        match values.iter_mut().find(|(existing, _)| existing == key) {
+            Some((_, existing_value)) => *existing_value = value,
+            None => values.push((key, value)),
+        }
+    }
+
+    let metadata = callsite(values.iter().map(|(key, _)| *key).collect()).metadata();
+    if !tracing::dispatcher::get_default(|dispatch| dispatch.enabled(metadata)) {
+        return Span::none();
+    }
+
+    let span = Span::new(metadata, &metadata.fields().value_set(&[]));
+    for (key, value) in values {
+        span.record(key, display(value));
+    }
+    span
+}
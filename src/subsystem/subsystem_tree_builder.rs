@@ -0,0 +1,197 @@
+use std::future::Future;
+
+use crate::{
+    BoxedError, ErrTypeTraits, ErrorAction, NestedSubsystem, SubsystemBuilder, SubsystemHandle,
+};
+
+use super::subsystem_builder::{BoxedSubsystem, BoxedSubsystemFuture};
+
+/// Describes a subsystem and its children, to be started through a [`SubsystemTreeBuilder`].
+pub struct SubsystemTreeNode<ErrType: ErrTypeTraits = BoxedError> {
+    name: String,
+    subsystem: BoxedSubsystem<ErrType, ErrType>,
+    failure_action: ErrorAction,
+    panic_action: ErrorAction,
+    shutdown_after: Vec<String>,
+    children: SubsystemTreeBuilder<ErrType>,
+}
+
+impl<ErrType: ErrTypeTraits> SubsystemTreeNode<ErrType> {
+    /// Creates the description of a subsystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the subsystem.
+    /// * `subsystem` - The subsystem function that the subsystem will execute.
+    ///   The children of the node are already started when it gets invoked.
+    pub fn new<Err, Fut, Subsys>(name: impl Into<String>, subsystem: Subsys) -> Self
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Result<(), Err>> + Send,
+        Err: 'static + Into<ErrType>,
+    {
+        Self {
+            name: name.into(),
+            subsystem: Box::new(move |subsys: SubsystemHandle<ErrType>| {
+                let subsystem = subsystem(subsys);
+                Box::pin(async move { subsystem.await.map_err(Into::into) })
+                    as BoxedSubsystemFuture<ErrType>
+            }),
+            failure_action: ErrorAction::Forward,
+            panic_action: ErrorAction::Forward,
+            shutdown_after: Vec::new(),
+            children: SubsystemTreeBuilder::new(),
+        }
+    }
+
+    /// Sets the way this subsystem should react to failures.
+    ///
+    /// See [`SubsystemBuilder::on_failure()`].
+    pub fn on_failure(mut self, action: ErrorAction) -> Self {
+        self.failure_action = action;
+        self
+    }
+
+    /// Sets the way this subsystem should react if it or one
+    /// of its children panic.
+    ///
+    /// See [`SubsystemBuilder::on_panic()`].
+    pub fn on_panic(mut self, action: ErrorAction) -> Self {
+        self.panic_action = action;
+        self
+    }
+
+    /// Delays the shutdown of this subsystem until the sibling with the given name is finished.
+    ///
+    /// See [`SubsystemBuilder::shutdown_after()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `sibling` - The name of the sibling that has to finish before this subsystem shuts down.
+    ///   It has to be added to the parent before this subsystem.
+    pub fn shutdown_after(mut self, sibling: impl Into<String>) -> Self {
+        self.shutdown_after.push(sibling.into());
+        self
+    }
+
+    /// Adds a child to this subsystem.
+    ///
+    /// Children get started in the order they were added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the child depends on a sibling that was not added before it,
+    /// see [`shutdown_after()`](Self::shutdown_after).
+    pub fn child(mut self, child: SubsystemTreeNode<ErrType>) -> Self {
+        self.children = self.children.subsystem(child);
+        self
+    }
+}
+
+/// Starts an entire tree of subsystems in one call.
+///
+/// Intended for programs that derive their subsystem tree from data,
+/// for example a configuration file, instead of imperative
+/// [`SubsystemHandle::start()`] calls.
+///
+/// # Examples
+///
+/// ```
+/// use miette::Result;
+/// use tokio_graceful_shutdown::{
+///     ErrorAction, SubsystemHandle, SubsystemTreeBuilder, SubsystemTreeNode,
+/// };
+///
+/// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+///     subsys.on_shutdown_requested().await;
+///     Ok(())
+/// }
+///
+/// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+///     SubsystemTreeBuilder::new()
+///         .subsystem(
+///             SubsystemTreeNode::new("Group", worker)
+///                 .on_failure(ErrorAction::CatchAndLocalShutdown)
+///                 .child(SubsystemTreeNode::new("Worker1", worker))
+///                 .child(SubsystemTreeNode::new("Worker2", worker)),
+///         )
+///         .subsystem(SubsystemTreeNode::new("Database", worker).shutdown_after("Group"))
+///         .start(&subsys);
+///
+///     subsys.on_shutdown_requested().await;
+///     Ok(())
+/// }
+/// ```
+pub struct SubsystemTreeBuilder<ErrType: ErrTypeTraits = BoxedError> {
+    nodes: Vec<SubsystemTreeNode<ErrType>>,
+}
+
+impl<ErrType: ErrTypeTraits> SubsystemTreeBuilder<ErrType> {
+    /// Creates an empty tree.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Adds a subsystem at the top of the tree.
+    ///
+    /// Subsystems get started in the order they were added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subsystem depends on a sibling that was not added before it,
+    /// see [`SubsystemTreeNode::shutdown_after()`].
+    pub fn subsystem(mut self, node: SubsystemTreeNode<ErrType>) -> Self {
+        for dependency in &node.shutdown_after {
+            assert!(
+                self.nodes.iter().any(|sibling| &sibling.name == dependency),
+                "Subsystem '{}' depends on '{}', which has to be added before it",
+                node.name,
+                dependency
+            );
+        }
+        self.nodes.push(node);
+        self
+    }
+
+    /// Starts all subsystems of the tree as children of the given subsystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The subsystem the tree gets attached to.
+    ///
+    /// # Returns
+    ///
+    /// The subsystems at the top of the tree, in the order they were added.
+    #[track_caller]
+    pub fn start(self, subsys: &SubsystemHandle<ErrType>) -> Vec<NestedSubsystem<ErrType>> {
+        let mut started: Vec<(String, NestedSubsystem<ErrType>)> = Vec::new();
+
+        for node in self.nodes {
+            let children = node.children;
+            let subsystem = node.subsystem;
+
+            let mut builder = SubsystemBuilder::new(
+                node.name.clone(),
+                move |s: SubsystemHandle<ErrType>| async move {
+                    children.start(&s);
+                    subsystem(s).await
+                },
+            )
+            .on_failure(node.failure_action)
+            .on_panic(node.panic_action);
+
+            for dependency in &node.shutdown_after {
+                // Existence got verified when the node was added
+                if let Some((_, sibling)) = started.iter().find(|(name, _)| name == dependency) {
+                    builder = builder.shutdown_after(sibling);
+                }
+            }
+
+            let nested = subsys.start(builder);
+            started.push((node.name, nested));
+        }
+
+        started.into_iter().map(|(_, nested)| nested).collect()
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    errors::SubsystemJoinError,
+    runner::{RunnerHooks, RunnerOptions},
+    ErrTypeTraits, ErrorAction, NestedSubsystem, RestartPolicy, SubsystemHandle,
+};
+
+use super::{ErrorActions, SupervisedSubsystem};
+
+impl<ErrType: ErrTypeTraits> SupervisedSubsystem<ErrType> {
+    pub(crate) fn new(subsystem: NestedSubsystem<ErrType>, restarts: Arc<AtomicU64>) -> Self {
+        Self {
+            subsystem,
+            restarts,
+        }
+    }
+
+    /// Returns how often the subsystem got restarted so far.
+    pub fn restart_count(&self) -> u64 {
+        self.restarts.load(Ordering::Acquire)
+    }
+
+    /// Signals the subsystem and all of its children to shut down.
+    ///
+    /// The subsystem does not get restarted any more afterwards.
+    pub fn initiate_shutdown(&self) {
+        self.subsystem.initiate_shutdown()
+    }
+
+    /// Wait for the subsystem and all of its children to be finished.
+    ///
+    /// # Returns
+    ///
+    /// A [`SubsystemJoinError`] on failure.
+    pub async fn join(&self) -> Result<(), SubsystemJoinError<ErrType>> {
+        self.subsystem.join().await
+    }
+}
+
+pub(crate) async fn run_supervised<ErrType, Err, Fut, Factory>(
+    subsys: SubsystemHandle<ErrType>,
+    factory: Factory,
+    policy: RestartPolicy,
+    restarts: Arc<AtomicU64>,
+) -> Result<(), ErrType>
+where
+    ErrType: ErrTypeTraits,
+    Factory: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send + Clone,
+    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Err: 'static + Into<ErrType>,
+{
+    loop {
+        // Once no restart is left, the failure gets passed on like any other
+        let restart_allowed = policy.allows_restart(restarts.load(Ordering::Acquire));
+        let error_action = if restart_allowed {
+            ErrorAction::CatchAndLocalShutdown
+        } else {
+            ErrorAction::Forward
+        };
+
+        // The instance uses the same name as this subsystem,
+        // so it behaves like this subsystem itself.
+        let instance = subsys.start_with_abs_name(
+            subsys.name().into(),
+            factory.clone(),
+            ErrorActions::new(error_action, error_action),
+            false,
+            RunnerOptions {
+                quiet_cancel: true,
+                ..Default::default()
+            },
+            RunnerHooks::default(),
+        );
+
+        let Err(e) = instance.join().await else {
+            break;
+        };
+        if subsys.is_shutdown_requested() {
+            tracing::warn!(
+                subsystem = %subsys.name(),
+                error = ?e,
+                "Supervised subsystem failed during shutdown."
+            );
+            break;
+        }
+
+        let restart = restarts.fetch_add(1, Ordering::AcqRel) + 1;
+        tracing::warn!(
+            subsystem = %subsys.name(),
+            error = ?e,
+            restart,
+            "Supervised subsystem failed, restarting ..."
+        );
+
+        if !policy.delay.is_zero() {
+            tokio::select! {
+                _ = tokio::time::sleep(policy.delay) => (),
+                _ = subsys.on_shutdown_requested() => break,
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex, Weak};
+
+use tokio::sync::Notify;
+
+use crate::SupervisionStrategy;
+
+/// Restarts the supervised children of a subsystem according to its strategy.
+pub(crate) struct Supervisor {
+    strategy: SupervisionStrategy,
+    // The restart triggers of the supervised children, in start order
+    children: Mutex<Vec<Weak<Notify>>>,
+}
+
+impl Supervisor {
+    pub(crate) fn new(strategy: SupervisionStrategy) -> Self {
+        Self {
+            strategy,
+            children: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn register(&self, restart_trigger: &Arc<Notify>) {
+        let mut children = self.children.lock().unwrap();
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(restart_trigger));
+    }
+
+    /// Restarts the siblings of the failed child, as required by the strategy.
+    ///
+    /// The failed child is responsible for restarting itself.
+    pub(crate) fn child_failed(&self, restart_trigger: &Arc<Notify>) {
+        let children = self.children.lock().unwrap();
+        let Some(position) = children
+            .iter()
+            .position(|child| std::ptr::eq(child.as_ptr(), Arc::as_ptr(restart_trigger)))
+        else {
+            return;
+        };
+
+        let siblings = children
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| match self.strategy {
+                SupervisionStrategy::OneForOne => false,
+                SupervisionStrategy::OneForAll => *index != position,
+                SupervisionStrategy::RestForOne => *index > position,
+            })
+            .filter_map(|(_, sibling)| sibling.upgrade());
+
+        for sibling in siblings {
+            sibling.notify_one();
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Mutex;
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{errors::SubsystemError, BoxedError, ErrTypeTraits};
+
+/// Controls a subsystem under test.
+///
+/// Created together with its handle through
+/// [`SubsystemHandle::test_handle()`](crate::SubsystemHandle::test_handle).
+pub struct TestController<ErrType: ErrTypeTraits = BoxedError> {
+    pub(crate) cancellation_token: CancellationToken,
+    pub(crate) errors: Mutex<mpsc::UnboundedReceiver<SubsystemError<ErrType>>>,
+}
+
+impl<ErrType: ErrTypeTraits> TestController<ErrType> {
+    /// Requests a shutdown of the subsystem under test,
+    /// as if the program got shut down.
+    pub fn request_shutdown(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Whether a shutdown got requested, either by the subsystem under test
+    /// or through [`request_shutdown()`](Self::request_shutdown).
+    pub fn is_shutdown_requested(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
+
+    /// Takes all errors of nested subsystems that were not caught by the subsystem under test.
+    ///
+    /// The errors of the subsystem under test itself are its return value
+    /// and therefore not part of this list.
+    pub fn take_errors(&self) -> Vec<SubsystemError<ErrType>> {
+        let mut errors = self.errors.lock().unwrap();
+        std::iter::from_fn(|| errors.try_recv().ok()).collect()
+    }
+}
@@ -0,0 +1,22 @@
+use std::sync::Arc;
+
+use super::{SubsystemFinishedFuture, WeakNestedSubsystem};
+
+impl WeakNestedSubsystem {
+    /// Returns the absolute name of the subsystem.
+    pub fn name(&self) -> Arc<str> {
+        Arc::clone(&self.name.lock().unwrap())
+    }
+
+    /// Returns whether the subsystem and all of its children are finished.
+    pub fn is_finished(&self) -> bool {
+        self.joiner.is_finished()
+    }
+
+    /// Returns a future that resolves once the subsystem is finished.
+    ///
+    /// See [`NestedSubsystem::finished`](crate::NestedSubsystem::finished).
+    pub fn finished(&self) -> SubsystemFinishedFuture {
+        SubsystemFinishedFuture::new(self.joiner.clone())
+    }
+}
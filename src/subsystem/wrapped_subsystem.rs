@@ -0,0 +1,118 @@
+use std::{
+    future::Future,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+use tracing::Instrument;
+
+use crate::{
+    errors::ExitedTooQuickly, sample::Sampler, ErrTypeTraits, SubsystemHandle, SupervisionStrategy,
+};
+
+use super::{
+    filter_failure, map_error,
+    subsystem_builder::{ErrorMapper, FailureFilter, MinRuntime, ReadyFuture},
+    subsystem_span,
+};
+
+/// The function of a subsystem started through a [`SubsystemBuilder`](crate::SubsystemBuilder),
+/// together with everything the builder configured to happen around it.
+pub(crate) struct WrappedSubsystem<ErrType: ErrTypeTraits, Err, Subsys> {
+    pub(crate) subsystem: Subsys,
+    pub(crate) span_fields: Vec<(&'static str, String)>,
+    pub(crate) error_mapper: Option<ErrorMapper<ErrType, Err>>,
+    pub(crate) failure_filter: Option<FailureFilter<ErrType>>,
+    pub(crate) min_runtime: Option<MinRuntime<ErrType>>,
+    pub(crate) children_outlive_parent: bool,
+    pub(crate) detached: bool,
+    pub(crate) supervision: Option<SupervisionStrategy>,
+    pub(crate) startup_dependencies: Vec<ReadyFuture>,
+    pub(crate) sampler: Option<(Duration, Sampler)>,
+}
+
+impl<ErrType, Err, Fut, Subsys> WrappedSubsystem<ErrType, Err, Subsys>
+where
+    ErrType: ErrTypeTraits,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+    Fut: 'static + Future<Output = Result<(), Err>> + Send,
+    Err: 'static + Into<ErrType>,
+{
+    /// Configures the handle of the subsystem and returns the future the runner executes.
+    pub(crate) fn start(
+        self,
+        s: SubsystemHandle<ErrType>,
+    ) -> impl Future<Output = Result<(), ErrType>> + Send + 'static {
+        let Self {
+            subsystem,
+            span_fields,
+            error_mapper,
+            failure_filter,
+            min_runtime,
+            children_outlive_parent,
+            detached,
+            supervision,
+            startup_dependencies,
+            sampler,
+        } = self;
+
+        if detached {
+            s.mark_detached();
+        }
+        if let Some(strategy) = supervision {
+            s.set_supervision(strategy);
+        }
+        if children_outlive_parent {
+            s.children_outlive_parent().store(true, Ordering::Release);
+        }
+        let cancellation_token = s.get_cancellation_token().clone();
+        let samples = s.get_samples().clone();
+        // Shared with the handle, to follow renames through `set_name()`
+        let name = s.name_cell();
+        let span = subsystem_span(&s.name(), &span_fields);
+        s.set_span(span.clone());
+
+        async move {
+            if !startup_dependencies.is_empty() {
+                let dependencies_ready = async {
+                    for dependency in startup_dependencies {
+                        dependency.await;
+                    }
+                };
+                tokio::select! {
+                    _ = dependencies_ready => (),
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                }
+            }
+
+            let started = Instant::now();
+            let subsystem = subsystem(s).instrument(span);
+            let result = match sampler {
+                Some((interval, sampler)) => {
+                    let sampling = samples.sample_periodically(&name, interval, &sampler);
+                    tokio::select! {
+                        result = subsystem => result,
+                        never = sampling => match never {},
+                    }
+                }
+                None => subsystem.await,
+            };
+            let result = result.map_err(|e| map_error(e, error_mapper));
+            let current_name = Arc::clone(&name.lock().unwrap());
+            filter_failure(&current_name, result, failure_filter.as_deref())?;
+
+            match min_runtime {
+                Some((min_runtime, into_error))
+                    if started.elapsed() < min_runtime && !cancellation_token.is_cancelled() =>
+                {
+                    Err(into_error(ExitedTooQuickly {
+                        elapsed: started.elapsed(),
+                        min_runtime,
+                    }))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+}
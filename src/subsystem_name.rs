@@ -0,0 +1,82 @@
+use std::{borrow::Borrow, fmt, ops::Deref, sync::Arc};
+
+/// The absolute name of a subsystem.
+///
+/// Returned by [`SubsystemHandle::name()`](crate::SubsystemHandle::name).
+/// Dereferences to [`str`] and compares equal to string slices, so it can be
+/// used like the `&str` that was returned previously.
+///
+/// This is a snapshot of the name at the time it got queried;
+/// renaming the subsystem through
+/// [`SubsystemHandle::set_name()`](crate::SubsystemHandle::set_name)
+/// does not change already returned names.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SubsystemName(Arc<str>);
+
+impl SubsystemName {
+    pub(crate) fn new(name: Arc<str>) -> Self {
+        Self(name)
+    }
+}
+
+impl Deref for SubsystemName {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SubsystemName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for SubsystemName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<SubsystemName> for Arc<str> {
+    fn from(name: SubsystemName) -> Self {
+        name.0
+    }
+}
+
+impl fmt::Debug for SubsystemName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SubsystemName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl PartialEq<str> for SubsystemName {
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for SubsystemName {
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<SubsystemName> for str {
+    fn eq(&self, other: &SubsystemName) -> bool {
+        *self == *other.0
+    }
+}
+
+impl PartialEq<SubsystemName> for &str {
+    fn eq(&self, other: &SubsystemName) -> bool {
+        **self == *other.0
+    }
+}
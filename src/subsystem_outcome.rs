@@ -0,0 +1,37 @@
+/// How a subsystem ended.
+///
+/// Queried through [`NestedSubsystem::outcome()`](crate::NestedSubsystem::outcome)
+/// and [`SubsystemJoinReport::outcome_of()`](crate::SubsystemJoinReport::outcome_of).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum SubsystemOutcome {
+    /// The subsystem returned `Ok`.
+    Succeeded,
+    /// The subsystem returned an `Err` value.
+    Failed,
+    /// The subsystem panicked.
+    Panicked,
+    /// The subsystem got cancelled before it returned.
+    Cancelled,
+}
+
+impl SubsystemOutcome {
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            SubsystemOutcome::Succeeded => 1,
+            SubsystemOutcome::Failed => 2,
+            SubsystemOutcome::Panicked => 3,
+            SubsystemOutcome::Cancelled => 4,
+        }
+    }
+
+    /// The inverse of [`to_u8`](SubsystemOutcome::to_u8); `0` means that there is no outcome yet.
+    pub(crate) fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(SubsystemOutcome::Succeeded),
+            2 => Some(SubsystemOutcome::Failed),
+            3 => Some(SubsystemOutcome::Panicked),
+            4 => Some(SubsystemOutcome::Cancelled),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+/// Which subsystems get restarted if a supervised subsystem fails.
+///
+/// Inspired by the supervisors of Erlang/OTP.
+///
+/// See [`SubsystemBuilder::supervision()`](crate::SubsystemBuilder::supervision).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SupervisionStrategy {
+    /// Only restart the failed subsystem.
+    OneForOne,
+    /// Restart the failed subsystem and all of its siblings.
+    OneForAll,
+    /// Restart the failed subsystem and all siblings that were started after it.
+    ///
+    /// Useful if later subsystems depend on earlier ones.
+    RestForOne,
+}
@@ -1,23 +1,36 @@
 use std::future::Future;
-use tokio::task::JoinHandle;
+use tokio::{runtime::Handle, task::JoinHandle};
 
 #[cfg(not(all(tokio_unstable, feature = "tracing")))]
 #[track_caller]
-pub(crate) fn spawn<F: Future + Send + 'static>(f: F, _name: &str) -> JoinHandle<F::Output>
+pub(crate) fn spawn<F: Future + Send + 'static>(
+    f: F,
+    _name: &str,
+    runtime: Option<&Handle>,
+) -> JoinHandle<F::Output>
 where
     <F as Future>::Output: Send + 'static,
 {
-    tokio::spawn(f)
+    match runtime {
+        Some(runtime) => runtime.spawn(f),
+        None => tokio::spawn(f),
+    }
 }
 
 #[cfg(all(tokio_unstable, feature = "tracing"))]
 #[track_caller]
-pub(crate) fn spawn<F: Future + Send + 'static>(f: F, name: &str) -> JoinHandle<F::Output>
+pub(crate) fn spawn<F: Future + Send + 'static>(
+    f: F,
+    name: &str,
+    runtime: Option<&Handle>,
+) -> JoinHandle<F::Output>
 where
     <F as Future>::Output: Send + 'static,
 {
-    tokio::task::Builder::new()
-        .name(name)
-        .spawn(f)
-        .expect("a task should be spawned")
+    let builder = tokio::task::Builder::new().name(name);
+    match runtime {
+        Some(runtime) => builder.spawn_on(f, runtime),
+        None => builder.spawn(f),
+    }
+    .expect("a task should be spawned")
 }
@@ -1,16 +1,39 @@
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
-use atomic::Atomic;
-use tokio::sync::mpsc;
+use tokio::{
+    runtime::Handle,
+    sync::{broadcast, oneshot, watch},
+    time::{Instant, MissedTickBehavior},
+};
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    errors::{handle_dropped_error, GracefulShutdownError, SubsystemError},
-    signal_handling::wait_for_signal,
+    error_stream::{ErrorQueue, ErrorStream},
+    errors::{GracefulShutdownError, SubsystemError},
+    runner::{CancelReason, RunnerHooks, RunnerOptions},
+    signal_handling::{wait_for_signal_after, EarlySignalPolicy, SignalKind, SignalListener},
     subsystem::{self, ErrorActions},
-    BoxedError, ErrTypeTraits, ErrorAction, NestedSubsystem, SubsystemHandle,
+    utils::FailureLogLimits,
+    BoxedError, ErrTypeTraits, ErrorAction, ErrorCounter, HealthStatus, LeakPolicy, LifecycleEvent,
+    NestedSubsystem, ShutdownState, ShutdownTrigger, SignalHandlerControl, SubsystemHandle,
+    SubsystemSample, ToplevelBuilder, TreeSnapshot,
 };
 
+#[cfg(unix)]
+use crate::signal_handling::{PauseSignal, PauseSignalListener};
+
+type SignalHook = Box<dyn FnMut(SignalKind) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+type ShutdownFinishedHook =
+    Box<dyn FnOnce(Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
 /// Acts as the root of the subsystem tree and forms the entry point for
 /// any interaction with this crate.
 ///
@@ -41,10 +64,37 @@ use crate::{
 /// ```
 ///
 #[must_use = "This toplevel must be consumed by calling `handle_shutdown_requests` on it."]
-pub struct Toplevel<ErrType: ErrTypeTraits = BoxedError> {
+pub struct Toplevel<ErrType: ErrTypeTraits = BoxedError, Output = ()> {
     root_handle: SubsystemHandle<ErrType>,
     toplevel_subsys: NestedSubsystem<ErrType>,
-    errors: mpsc::UnboundedReceiver<SubsystemError<ErrType>>,
+    // The running descendants of `toplevel_subsys`; `None` while reloading
+    root_descendants: watch::Sender<Option<watch::Receiver<(bool, u32)>>>,
+    errors: Arc<ErrorQueue<ErrType>>,
+    total_errors: Arc<AtomicU64>,
+    signal_hook: Arc<Mutex<Option<SignalHook>>>,
+    shutdown_finished_hook: Option<ShutdownFinishedHook>,
+    output: oneshot::Receiver<Output>,
+    report_cancelled: bool,
+    shutdown_progress_interval: Option<Duration>,
+    shutdown_state: watch::Sender<ShutdownState>,
+}
+
+impl Toplevel {
+    /// Creates a token that gets cancelled once the given subsystem shuts down.
+    ///
+    /// Intended to be passed to [`use_external_shutdown()`](Toplevel::use_external_shutdown)
+    /// of a [`Toplevel`] that is nested inside of the given subsystem.
+    /// The token is a child of the token of the subsystem, so cancelling it
+    /// does not affect the subsystem itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsys` - The subsystem the nested [`Toplevel`] runs in.
+    pub fn subsystem_shutdown_token<ErrType: ErrTypeTraits>(
+        subsys: &SubsystemHandle<ErrType>,
+    ) -> CancellationToken {
+        subsys.create_cancellation_token()
+    }
 }
 
 impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
@@ -55,7 +105,7 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
     /// # Arguments
     ///
     /// * `subsystem` - The subsystem that should be spawned as the root node.
-    ///                 Usually the job of this subsystem is to spawn further subsystems.
+    ///   Usually the job of this subsystem is to spawn further subsystems.
     #[allow(clippy::new_without_default)]
     #[track_caller]
     pub fn new<Fut, Subsys>(subsystem: Subsys) -> Self
@@ -63,38 +113,149 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
         Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
         Fut: 'static + Future<Output = ()> + Send,
     {
-        let (error_sender, errors) = mpsc::unbounded_channel();
+        Self::new_with_output(subsystem)
+    }
 
-        let root_handle = subsystem::root_handle(move |e| {
-            match &e {
-                SubsystemError::Panicked(name) => {
-                    tracing::error!("Uncaught panic from subsystem '{name}'.")
-                }
-                SubsystemError::Failed(name, e) => {
-                    tracing::error!("Uncaught error from subsystem '{name}': {e}",)
-                }
-            };
+    /// Creates a new Toplevel object whose subsystem tree runs on the given runtime.
+    ///
+    /// All tasks of this crate, including the ones of nested subsystems, get
+    /// spawned on `runtime` instead of the runtime this function is called from.
+    /// This allows the subsystem tree to live on a dedicated runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - The runtime the subsystem tree should run on.
+    /// * `subsystem` - The subsystem that should be spawned as the root node.
+    ///   Usually the job of this subsystem is to spawn further subsystems.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.request_shutdown();
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let runtime = tokio::runtime::Builder::new_multi_thread()
+    ///         .enable_all()
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     let result = Toplevel::new_on(runtime.handle().clone(), |s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///     })
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await;
+    ///
+    ///     runtime.shutdown_background();
+    ///     result.map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn new_on<Fut, Subsys>(runtime: Handle, subsystem: Subsys) -> Self
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        Self::from_builder(Self::builder().with_runtime(runtime), subsystem)
+    }
 
-            handle_dropped_error(error_sender.send(e));
-        });
+    /// Creates a builder to configure the Toplevel before its root subsystem gets started.
+    ///
+    /// See [`ToplevelBuilder`] for more information.
+    pub fn builder() -> ToplevelBuilder<ErrType> {
+        ToplevelBuilder::new()
+    }
 
-        let toplevel_subsys = root_handle.start_with_abs_name(
-            Arc::from("/"),
-            move |s| async move {
-                subsystem(s).await;
-                Result::<(), ErrType>::Ok(())
-            },
-            ErrorActions {
-                on_failure: Atomic::new(ErrorAction::Forward),
-                on_panic: Atomic::new(ErrorAction::Forward),
+    /// Creates a new Toplevel object without any subsystems.
+    ///
+    /// Unlike a Toplevel created through [`new()`](Toplevel::new) with an empty
+    /// subsystem, this Toplevel does not shut down immediately because all
+    /// subsystems finished. Instead, it idles until a shutdown is requested,
+    /// for example through [`catch_signals()`](Toplevel::catch_signals).
+    #[track_caller]
+    pub fn new_idle() -> Self {
+        Self::new(|s: SubsystemHandle<ErrType>| async move {
+            s.on_shutdown_requested().await;
+        })
+    }
+}
+
+impl<ErrType: ErrTypeTraits, Output: 'static + Send> Toplevel<ErrType, Output> {
+    /// Creates a new Toplevel object whose root subsystem returns a value.
+    ///
+    /// The value can be retrieved through
+    /// [`handle_shutdown_requests_with_output()`](Toplevel::handle_shutdown_requests_with_output).
+    ///
+    /// # Arguments
+    ///
+    /// * `subsystem` - The subsystem that should be spawned as the root node.
+    ///   Its return value will be the output of the Toplevel.
+    #[track_caller]
+    pub fn new_with_output<Fut, Subsys>(subsystem: Subsys) -> Self
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Output> + Send,
+    {
+        Self::from_builder(Toplevel::builder(), subsystem)
+    }
+
+    #[track_caller]
+    pub(crate) fn from_builder<Fut, Subsys>(
+        builder: ToplevelBuilder<ErrType>,
+        subsystem: Subsys,
+    ) -> Self
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Output> + Send,
+    {
+        let errors = Arc::new(ErrorQueue::new());
+
+        let failure_log_limits = Arc::new(FailureLogLimits::default());
+        let total_errors = Arc::new(AtomicU64::new(0));
+
+        let root_handle = subsystem::root_handle(
+            {
+                let failure_log_limits = Arc::clone(&failure_log_limits);
+                let errors = Arc::downgrade(&errors);
+                let total_errors = Arc::clone(&total_errors);
+                move |e| {
+                    total_errors.fetch_add(1, Ordering::Relaxed);
+                    log_error(&e, &failure_log_limits);
+                    match errors.upgrade() {
+                        Some(errors) => errors.push(e),
+                        None => tracing::warn!(error = ?e, "An error got dropped."),
+                    }
+                }
             },
-            false,
+            failure_log_limits,
+            builder.runtime,
+            builder.name_separator,
         );
+        // By default, the toplevel subsystem is named after the separator, like `/`
+        let root_name = builder
+            .root_name
+            .unwrap_or_else(|| Arc::from(builder.name_separator.to_string()));
+        let (toplevel_subsys, output) = start_root_subsystem(&root_handle, root_name, subsystem);
 
         Self {
             root_handle,
+            root_descendants: watch::channel(Some(toplevel_subsys.watch_descendants())).0,
             toplevel_subsys,
             errors,
+            total_errors,
+            signal_hook: Default::default(),
+            shutdown_finished_hook: None,
+            output,
+            report_cancelled: false,
+            shutdown_progress_interval: None,
+            shutdown_state: watch::channel(ShutdownState::Running).0,
         }
     }
 
@@ -108,11 +269,16 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
     ///     - `CTRL_BREAK`
     ///     - `CTRL_CLOSE`
     ///     - `CTRL_SHUTDOWN`
+    ///     - `CTRL_LOGOFF`
     ///
     /// - On Unix:
     ///     - `SIGINT`
     ///     - `SIGTERM`
     ///
+    /// The signal handlers are registered before this function returns,
+    /// so no signal that arrives afterwards can get lost, even if it arrives
+    /// before the shutdown handling started.
+    ///
     /// # Caveats
     ///
     /// This function internally uses [tokio::signal] with all of its caveats.
@@ -121,19 +287,880 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
     ///
     #[track_caller]
     pub fn catch_signals(self) -> Self {
-        let shutdown_token = self.root_handle.get_cancellation_token().clone();
+        self.catch_signals_after(async {}, EarlySignalPolicy::Queue)
+    }
+
+    /// Registers signal handlers like [`catch_signals()`](Toplevel::catch_signals),
+    /// but only reacts to signals once the given future is finished.
+    ///
+    /// This is useful to prevent a shutdown during initialization.
+    /// The signal handlers get registered immediately; signals that are received
+    /// before `gate` is finished are handled according to `policy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `gate` - The future that has to finish before signals initiate a shutdown.
+    /// * `policy` - What to do with signals that are received before `gate` is finished.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::{sync::oneshot, time::Duration};
+    /// use tokio_graceful_shutdown::{EarlySignalPolicy, SubsystemHandle, Toplevel};
+    ///
+    /// async fn init(subsys: SubsystemHandle, ready: oneshot::Sender<()>) {
+    ///     // Perform a lengthy initialization
+    ///     ready.send(()).ok();
+    ///     subsys.request_shutdown();
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let (ready_sender, ready_receiver) = oneshot::channel();
+    ///
+    ///     Toplevel::new(|s| init(s, ready_sender))
+    ///         .catch_signals_after(
+    ///             async {
+    ///                 ready_receiver.await.ok();
+    ///             },
+    ///             EarlySignalPolicy::Queue,
+    ///         )
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await
+    ///         .map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn catch_signals_after(
+        self,
+        gate: impl Future<Output = ()> + Send + 'static,
+        policy: EarlySignalPolicy,
+    ) -> Self {
+        let shutdown_initiator = Arc::clone(self.root_handle.shutdown_initiator());
+        let signal_handler_control = self.root_handle.signal_handler_control();
+        let signal_hook = Arc::clone(&self.signal_hook);
+        // Register the handlers synchronously instead of inside of the task,
+        // otherwise signals that arrive before the task first runs would get lost.
+        let listener = {
+            let _runtime = self.root_handle.runtime().map(Handle::enter);
+            SignalListener::new()
+        };
 
         crate::tokio_task::spawn(
             async move {
-                wait_for_signal().await;
-                shutdown_token.cancel();
+                let signal = wait_for_signal_after(listener, gate, policy).await;
+                let signal = signal_handler_control.admit(signal).await;
+                let hook = signal_hook.lock().unwrap().take();
+                if let Some(mut hook) = hook {
+                    hook(signal).await;
+                }
+                shutdown_initiator.initiate();
             },
             "catch_signals",
+            self.root_handle.runtime(),
         );
 
         self
     }
 
+    /// Pauses the subsystem tree on `SIGTSTP` and resumes it on `SIGCONT`.
+    ///
+    /// This is useful for debugging long-running services from a shell,
+    /// where `Ctrl-Z` then pauses the work instead of stopping the process.
+    /// Pausing is distinct from a shutdown and purely cooperative; subsystems
+    /// query it through [`SubsystemHandle::is_paused()`] and [`SubsystemHandle::on_resume()`].
+    ///
+    /// A shutdown request resumes the tree, so paused subsystems can shut down.
+    ///
+    /// Only has an effect on Unix.
+    ///
+    /// # Caveats
+    ///
+    /// This function internally uses [tokio::signal] with all of its caveats.
+    /// Especially, `SIGTSTP` no longer stops the process once this got called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn worker(subsys: SubsystemHandle) -> Result<()> {
+    ///     while !subsys.is_shutdown_requested() {
+    ///         subsys.on_resume().await;
+    ///
+    ///         // Process the next batch of work
+    ///         sleep(Duration::from_millis(100)).await;
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Worker", worker));
+    ///         s.request_shutdown();
+    ///     })
+    ///     .catch_signals()
+    ///     .catch_pause_signals()
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn catch_pause_signals(self) -> Self {
+        #[cfg(unix)]
+        {
+            let shutdown_token = self.root_handle.get_cancellation_token().clone();
+            let pause_state = self.root_handle.pause_state();
+            // Register the handlers synchronously, like in `catch_signals_after()`
+            let mut listener = {
+                let _runtime = self.root_handle.runtime().map(Handle::enter);
+                PauseSignalListener::new()
+            };
+
+            crate::tokio_task::spawn(
+                async move {
+                    loop {
+                        tokio::select! {
+                            signal = listener.recv() => {
+                                let paused = signal == PauseSignal::Pause;
+                                if pause_state.send_replace(paused) != paused {
+                                    if paused {
+                                        tracing::info!("Pausing subsystems.");
+                                    } else {
+                                        tracing::info!("Resuming subsystems.");
+                                    }
+                                }
+                            }
+                            _ = shutdown_token.cancelled() => {
+                                pause_state.send_replace(false);
+                                break;
+                            }
+                        }
+                    }
+                },
+                "catch_pause_signals",
+                self.root_handle.runtime(),
+            );
+        }
+
+        self
+    }
+
+    /// Initiates a program shutdown once the given token gets cancelled.
+    ///
+    /// This allows nesting a [`Toplevel`] inside of a subsystem of another
+    /// [`Toplevel`], for example in plugins that use this crate themselves.
+    /// As signal handlers are process-wide, only the outermost [`Toplevel`]
+    /// should call [`catch_signals()`](Toplevel::catch_signals); the nested ones
+    /// receive the shutdown request through a token created with
+    /// [`Toplevel::subsystem_shutdown_token()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token that triggers the shutdown when cancelled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn plugin_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn plugin(subsys: SubsystemHandle) -> Result<()> {
+    ///     // The plugin runs its own subsystem tree
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("PluginSubsys", plugin_subsystem));
+    ///     })
+    ///     .use_external_shutdown(Toplevel::subsystem_shutdown_token(&subsys))
+    ///     .handle_shutdown_requests(Duration::from_millis(500))
+    ///     .await?;
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Plugin", plugin));
+    ///         s.request_shutdown();
+    ///     })
+    ///     .catch_signals()
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn use_external_shutdown(self, token: CancellationToken) -> Self {
+        let shutdown_initiator = Arc::clone(self.root_handle.shutdown_initiator());
+        let shutdown_token = self.root_handle.get_cancellation_token().clone();
+
+        crate::tokio_task::spawn(
+            async move {
+                tokio::select! {
+                    _ = token.cancelled() => shutdown_initiator.initiate(),
+                    // Stop listening once the tree shuts down on its own
+                    _ = shutdown_token.cancelled() => (),
+                }
+            },
+            "external_shutdown",
+            self.root_handle.runtime(),
+        );
+
+        self
+    }
+
+    /// Performs a shutdown once no subsystems were running for the given duration.
+    ///
+    /// Intended for serverless-style workloads that should exit once there
+    /// is no more work to do. Unlike finishing all subsystems, the tree may
+    /// temporarily be empty while new work arrives; the idle timer restarts
+    /// every time a new subsystem gets started.
+    ///
+    /// Only the subsystems below the root subsystem are counted;
+    /// detached subsystems are not part of the tree and therefore ignored.
+    /// After a [`reload()`](Toplevel::reload), the new root subsystem gets observed,
+    /// and the reload itself counts as activity.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle_timeout` - How long the tree has to be idle before it shuts down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::{sleep, Duration};
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn job(_subsys: SubsystemHandle) -> Result<()> {
+    ///     sleep(Duration::from_millis(100)).await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Job", job));
+    ///         s.on_shutdown_requested().await;
+    ///     })
+    ///     .exit_when_idle(Duration::from_millis(200))
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn exit_when_idle(self, idle_timeout: Duration) -> Self {
+        let shutdown_initiator = Arc::clone(self.root_handle.shutdown_initiator());
+        let shutdown_token = self.root_handle.get_cancellation_token().clone();
+        let mut descendants = DescendantsWatch::new(&self.root_descendants);
+
+        crate::tokio_task::spawn(
+            async move {
+                let idle = async {
+                    loop {
+                        // Errors mean the tree is gone, so there is nothing left to shut down.
+                        // A reload counts as activity.
+                        if descendants
+                            .wait_for(|count| count == Some(0))
+                            .await
+                            .is_err()
+                        {
+                            return false;
+                        }
+                        tokio::select! {
+                            _ = tokio::time::sleep(idle_timeout) => return true,
+                            busy = descendants.wait_for(|count| count != Some(0)) => {
+                                if busy.is_err() {
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                };
+
+                tokio::select! {
+                    true = idle => {
+                        tracing::info!(?idle_timeout, "No subsystems running, shutting down ...");
+                        shutdown_initiator.initiate();
+                    }
+                    // Stop monitoring once the tree shuts down in another way
+                    _ = shutdown_token.cancelled() => (),
+                }
+            },
+            "exit_when_idle",
+            self.root_handle.runtime(),
+        );
+
+        self
+    }
+
+    /// Registers a callback that gets invoked whenever the subsystem tree becomes idle,
+    /// meaning the last running subsystem below the root subsystem finished.
+    ///
+    /// Unlike a shutdown, this does not stop anything; it is intended for
+    /// application-level logic once all work is done, like flushing a final report.
+    /// If new subsystems get started afterwards, the callback gets invoked again
+    /// once they are finished. A tree that never started any subsystems is not
+    /// considered to become idle.
+    ///
+    /// The callback is awaited before the tree is observed again, and it is no longer
+    /// invoked once a shutdown got requested. Detached subsystems are not part of the
+    /// tree and therefore ignored. After a [`reload()`](Toplevel::reload), the new root
+    /// subsystem gets observed; the shutdown of the old tree does not count as becoming idle.
+    /// Calling this method again registers an additional callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to invoke when the tree becomes idle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn job(_subsys: SubsystemHandle) -> Result<()> {
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Job", job));
+    ///         s.on_shutdown_requested().await;
+    ///     })
+    ///     .on_idle(|| async move {
+    ///         tracing::info!("All jobs done, flushing the report ...");
+    ///     })
+    ///     .exit_when_idle(Duration::from_millis(100))
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn on_idle<Fut, Hook>(self, mut hook: Hook) -> Self
+    where
+        Hook: 'static + FnMut() -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        let shutdown_token = self.root_handle.get_cancellation_token().clone();
+        let mut descendants = DescendantsWatch::new(&self.root_descendants);
+
+        crate::tokio_task::spawn(
+            async move {
+                loop {
+                    let idle = async {
+                        descendants
+                            .wait_for(|count| count.is_some_and(|count| count != 0))
+                            .await?;
+                        descendants.wait_for(|count| count.unwrap_or(0) == 0).await
+                    };
+
+                    tokio::select! {
+                        biased;
+                        // A tree that is shutting down is not idle, it is finished
+                        _ = shutdown_token.cancelled() => return,
+                        idle = idle => match idle {
+                            Ok(Some(_)) => (),
+                            // A tree that is reloading is not idle either; it becomes busy again
+                            Ok(None) => continue,
+                            // The tree is gone, so it can't become idle any more
+                            Err(_) => return,
+                        },
+                    }
+
+                    hook().await;
+                }
+            },
+            "on_idle",
+            self.root_handle.runtime(),
+        );
+
+        self
+    }
+
+    /// Returns a trigger that initiates a shutdown of the subsystem tree
+    /// from synchronous code on any thread.
+    ///
+    /// See [`ShutdownTrigger`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let toplevel = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///     });
+    ///
+    ///     // For example a callback of a C library
+    ///     let shutdown_trigger = toplevel.shutdown_trigger();
+    ///     std::thread::spawn(move || shutdown_trigger.trigger());
+    ///
+    ///     toplevel
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await
+    ///         .map_err(Into::into)
+    /// }
+    /// ```
+    pub fn shutdown_trigger(&self) -> ShutdownTrigger {
+        ShutdownTrigger::new(Arc::clone(self.root_handle.shutdown_initiator()))
+    }
+
+    /// Returns the control to pause and resume the signal handling
+    /// of [`catch_signals()`](Toplevel::catch_signals) at runtime.
+    ///
+    /// See [`SignalHandlerControl`] for more information. Subsystems can obtain the same
+    /// control through [`SubsystemHandle::signal_handler_control()`].
+    pub fn signal_handler_control(&self) -> SignalHandlerControl {
+        self.root_handle.signal_handler_control()
+    }
+
+    /// Registers a callback that gets invoked when one of the signals
+    /// handled by [`catch_signals()`](Toplevel::catch_signals) is received.
+    ///
+    /// The callback receives the kind of signal that was received and is
+    /// awaited before the shutdown gets initiated, so it should finish quickly.
+    ///
+    /// Has no effect unless [`catch_signals()`](Toplevel::catch_signals) is called as well.
+    /// Calling this method again replaces the previously registered callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to invoke when a signal is received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SignalKind, SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.request_shutdown();
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///     })
+    ///     .catch_signals()
+    ///     .on_signal(|signal: SignalKind| async move {
+    ///         tracing::info!("Received signal: {signal:?}");
+    ///     })
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    pub fn on_signal<Fut, Hook>(self, mut hook: Hook) -> Self
+    where
+        Hook: 'static + FnMut(SignalKind) -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        *self.signal_hook.lock().unwrap() = Some(Box::new(move |signal| Box::pin(hook(signal))));
+        self
+    }
+
+    /// Registers a callback that gets invoked once at the start of the shutdown,
+    /// before any subsystem gets notified about it.
+    ///
+    /// This is useful for draining, for example to deregister from a service
+    /// discovery registry before the subsystems stop accepting requests.
+    /// The callback is awaited before the shutdown gets initiated, so it should finish quickly.
+    ///
+    /// The callback gets invoked no matter how the shutdown got initiated, be it through
+    /// a signal, a [`ShutdownTrigger`], [`SubsystemHandle::request_shutdown()`] or a failing
+    /// subsystem. Shutdown requests that arrive while the callback is running are merged
+    /// into the running shutdown. Only [`SubsystemHandle::abort_all()`] skips the callback.
+    ///
+    /// Calling this method again replaces the previously registered callback;
+    /// once the shutdown got initiated, registering a callback has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to invoke before the shutdown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///         s.request_shutdown();
+    ///     })
+    ///     .catch_signals()
+    ///     .on_prestop(|| async move {
+    ///         tracing::info!("Deregistering from service discovery ...");
+    ///     })
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    pub fn on_prestop<Fut, Hook>(self, hook: Hook) -> Self
+    where
+        Hook: 'static + FnOnce() -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        self.root_handle
+            .shutdown_initiator()
+            .set_prestop_hook(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// Registers a callback that gets invoked once the shutdown is over.
+    ///
+    /// The callback receives the time that passed between the shutdown request and
+    /// the moment all subsystems finished or the shutdown timed out, which is useful
+    /// for tracking how long shutdowns take. It is awaited before
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests) returns.
+    ///
+    /// The callback is not invoked if all subsystems finished on their own
+    /// without a shutdown being requested.
+    ///
+    /// Calling this method again replaces the previously registered callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The callback to invoke after the shutdown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///         s.request_shutdown();
+    ///     })
+    ///     .on_shutdown_finished(|elapsed: Duration| async move {
+    ///         tracing::info!("Shutdown took {elapsed:?}.");
+    ///     })
+    ///     .handle_shutdown_requests(Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    pub fn on_shutdown_finished<Fut, Hook>(mut self, hook: Hook) -> Self
+    where
+        Hook: 'static + FnOnce(Duration) -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        self.shutdown_finished_hook = Some(Box::new(move |elapsed| Box::pin(hook(elapsed))));
+        self
+    }
+
+    /// Limits the number of subsystem errors that get buffered until they are consumed.
+    ///
+    /// By default, all errors get buffered until the shutdown is finished, which can
+    /// accumulate a lot of memory in long-running programs where subsystems fail repeatedly.
+    /// With a limit set, the oldest buffered error gets dropped with a warning once the
+    /// buffer is full.
+    ///
+    /// Errors that got consumed through an [`ErrorStream`] free up space in the buffer.
+    /// Dropped errors still cause [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests)
+    /// to return an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of buffered errors. Must be at least 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_error_buffer(self, capacity: usize) -> Self {
+        assert!(capacity > 0, "The error buffer capacity must be at least 1");
+        self.errors.set_capacity(capacity);
+        self
+    }
+
+    /// Sets what happens if a [`SubsystemHandle`] gets leaked out of its subsystem.
+    ///
+    /// The default is [`LeakPolicy::Panic`].
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - How to react to a leaked handle.
+    pub fn on_handle_leak(self, policy: LeakPolicy) -> Self {
+        self.root_handle.set_leak_policy(policy);
+        self
+    }
+
+    /// Limits how deeply subsystems can be nested.
+    ///
+    /// Guards against runaway recursive spawning. Subsystems started by the root
+    /// subsystem of the Toplevel have a depth of 1, their children a depth of 2, and so on.
+    /// Starting a subsystem that would exceed the limit panics, which gets reported
+    /// as a panic of the subsystem that attempted to start it.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - The maximum depth of nested subsystems.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        // The root subsystem of the Toplevel is itself nested in the root handle
+        self.root_handle.set_max_depth(max_depth.saturating_add(1));
+        self
+    }
+
+    /// Reports subsystems that get cancelled because the shutdown timed out.
+    ///
+    /// If enabled, every subsystem whose function is still running when the shutdown timeout
+    /// is reached gets reported as a [`SubsystemError::Cancelled`] in the
+    /// [`GracefulShutdownError::ShutdownTimeout`] error. Subsystems that already returned
+    /// and only wait for their children are not reported.
+    pub fn report_cancelled_subsystems(mut self) -> Self {
+        self.report_cancelled = true;
+        self
+    }
+
+    /// Periodically logs the progress of the shutdown.
+    ///
+    /// While [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests) waits
+    /// for the subsystems to finish, it logs the names of the subsystems that are
+    /// still running every `interval`, until the shutdown is finished or timed out.
+    /// Useful for long shutdown timeouts, where silence would otherwise make it look
+    /// like the program hangs.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between two progress logs.
+    pub fn with_shutdown_progress_interval(mut self, interval: Duration) -> Self {
+        self.shutdown_progress_interval = Some(interval);
+        self
+    }
+
+    /// Returns a stream that receives the uncaught errors of the subsystem tree
+    /// while it is running.
+    ///
+    /// Errors received through the stream are not contained in the result of
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests) any more, which
+    /// only returns the remaining ones. The result still reports a failure, though.
+    ///
+    /// See [`ErrorStream`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(_subsys: SubsystemHandle) -> Result<()> {
+    ///     Err(miette::miette!("Oh no!"))
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let toplevel = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Subsys", my_subsystem));
+    ///     });
+    ///
+    ///     let errors = toplevel.error_stream();
+    ///     tokio::spawn(async move {
+    ///         while let Some(error) = errors.recv().await {
+    ///             println!("Live error: {error}");
+    ///         }
+    ///     });
+    ///
+    ///     let result = toplevel
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await;
+    ///     assert!(result.is_err());
+    /// }
+    /// ```
+    pub fn error_stream(&self) -> ErrorStream<ErrType> {
+        ErrorStream::new(Arc::clone(&self.errors))
+    }
+
+    /// Returns a counter of all errors that reached the toplevel during the
+    /// lifetime of the subsystem tree.
+    ///
+    /// Unlike the errors returned by [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests),
+    /// the counter also includes errors that got consumed through an [`ErrorStream`]
+    /// or dropped because of [`with_error_buffer()`](Toplevel::with_error_buffer).
+    /// It stays readable after the shutdown finished, which makes it usable as
+    /// an aggregate failure metric without keeping the errors themselves in memory.
+    ///
+    /// Errors that got caught on the way, for example through
+    /// [`ErrorAction::CatchAndLocalShutdown`], are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn failing_subsystem(_subsys: SubsystemHandle) -> miette::Result<()> {
+    ///     Err(miette::miette!("Failed"))
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let toplevel = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Failing", failing_subsystem));
+    ///     });
+    ///     let total_errors = toplevel.total_errors_seen();
+    ///
+    ///     let result = toplevel
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await;
+    ///     assert!(result.is_err());
+    ///     assert_eq!(total_errors.get(), 1);
+    /// }
+    /// ```
+    pub fn total_errors_seen(&self) -> ErrorCounter {
+        ErrorCounter::new(Arc::clone(&self.total_errors))
+    }
+
+    /// Aborts all subsystems immediately, without a graceful shutdown.
+    ///
+    /// See [`SubsystemHandle::abort_all()`] for more information.
+    pub fn abort_all(&self) {
+        self.root_handle.abort_all();
+    }
+
+    /// Captures the current state of the subsystem tree.
+    ///
+    /// Intended for debugging, for example to expose the tree through a debug endpoint.
+    ///
+    /// # Returns
+    ///
+    /// The snapshot of the toplevel subsystem `/`, containing all subsystems
+    /// that are still running.
+    pub fn dump_tree(&self) -> TreeSnapshot {
+        let tree_node = self.root_handle.tree_node();
+        tree_node.children().pop().unwrap_or_else(|| TreeSnapshot {
+            // The toplevel subsystem is already finished
+            name: self.toplevel_subsys.name(),
+            alive: false,
+            shutdown_requested: self.root_handle.is_shutdown_requested(),
+            health: HealthStatus::Healthy,
+            children: Vec::new(),
+        })
+    }
+
+    /// The worst health reported by any subsystem in the tree.
+    ///
+    /// Intended for health check endpoints, for example a readiness probe.
+    /// Finished subsystems are not taken into account.
+    ///
+    /// For more information, see [`SubsystemHandle::set_health()`].
+    pub fn aggregate_health(&self) -> HealthStatus {
+        self.root_handle.tree_node().aggregate_health()
+    }
+
+    /// Subscribes to the lifecycle events of all subsystems in the tree.
+    ///
+    /// Only events that happen after subscribing will be received.
+    /// Receivers that fall behind do not slow down the subsystems;
+    /// instead, they will miss events and receive a
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+    pub fn events(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.root_handle.get_lifecycle_events().subscribe()
+    }
+
+    /// Subscribes to the samples of all subsystems in the tree.
+    ///
+    /// Samples get recorded by subsystems that were started with
+    /// [`SubsystemBuilder::with_sampler()`](crate::SubsystemBuilder::with_sampler).
+    /// Only samples that get recorded after subscribing will be received.
+    /// Receivers that fall behind do not slow down the subsystems;
+    /// instead, they will miss samples and receive a
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+    pub fn samples(&self) -> broadcast::Receiver<SubsystemSample> {
+        self.root_handle.get_samples().subscribe()
+    }
+
+    /// Observes the progress of the program shutdown.
+    ///
+    /// The returned receiver reflects the phase that
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests) is currently in,
+    /// which gives insight into why a shutdown takes long, for example from a supervising task.
+    ///
+    /// Once [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests) returns,
+    /// the receiver keeps the last state and
+    /// [`changed()`](tokio::sync::watch::Receiver::changed) returns an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn my_subsystem(subsys: SubsystemHandle) -> Result<()> {
+    ///     subsys.request_shutdown();
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let toplevel = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("MySubsystem", my_subsystem));
+    ///     });
+    ///
+    ///     let mut shutdown_state = toplevel.shutdown_state();
+    ///     tokio::spawn(async move {
+    ///         while shutdown_state.changed().await.is_ok() {
+    ///             println!("Shutdown state: {:?}", *shutdown_state.borrow());
+    ///         }
+    ///     });
+    ///
+    ///     toplevel
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await
+    ///         .map_err(Into::into)
+    /// }
+    /// ```
+    pub fn shutdown_state(&self) -> watch::Receiver<ShutdownState> {
+        self.shutdown_state.subscribe()
+    }
+
     /// Performs a clean program shutdown, once a shutdown is requested or all subsystems have
     /// finished.
     ///
@@ -146,6 +1173,9 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
     /// When the shutdown takes longer than the given timeout, an error will be returned and remaining subsystems
     /// will be cancelled.
     ///
+    /// If the returned future gets dropped before it is finished, for example because it lost
+    /// a `select!`, a shutdown gets requested and all remaining subsystems get cancelled immediately.
+    ///
     /// # Arguments
     ///
     /// * `shutdown_timeout` - The maximum time that is allowed to pass after a shutdown was initiated.
@@ -155,29 +1185,260 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
     /// An error of type [`GracefulShutdownError`] if an error occurred.
     ///
     pub async fn handle_shutdown_requests(
-        mut self,
+        self,
         shutdown_timeout: Duration,
     ) -> Result<(), GracefulShutdownError<ErrType>> {
-        let collect_errors = move || {
-            let mut errors = vec![];
-            self.errors.close();
-            while let Ok(e) = self.errors.try_recv() {
-                errors.push(e);
+        self.handle_shutdown_requests_with_output(shutdown_timeout)
+            .await
+            .map(|_| ())
+    }
+
+    /// Performs a clean program shutdown like
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests),
+    /// and additionally returns the value returned by the root subsystem.
+    ///
+    /// See [`new_with_output()`](Toplevel::new_with_output) for how to create
+    /// a root subsystem that returns a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown_timeout` - The maximum time that is allowed to pass after a shutdown was initiated.
+    ///
+    /// # Returns
+    ///
+    /// The return value of the root subsystem, or an error of type
+    /// [`GracefulShutdownError`] if an error occurred.
+    pub async fn handle_shutdown_requests_with_output(
+        self,
+        shutdown_timeout: Duration,
+    ) -> Result<Output, GracefulShutdownError<ErrType>> {
+        self.run(move || Instant::now() + shutdown_timeout).await
+    }
+
+    /// Performs a clean program shutdown like
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests),
+    /// but reads the shutdown timeout from an environment variable.
+    ///
+    /// This allows operators to match the shutdown timeout with the grace period
+    /// of their orchestrator without recompiling the program.
+    ///
+    /// The variable has to contain the timeout in milliseconds. If it is not set,
+    /// the default is used; if it cannot be parsed, a warning gets logged
+    /// and the default is used as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `var` - The name of the environment variable, like `SHUTDOWN_TIMEOUT_MS`.
+    /// * `default` - The shutdown timeout to use if the variable is missing or invalid.
+    ///
+    /// # Returns
+    ///
+    /// An error of type [`GracefulShutdownError`] if an error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemHandle, Toplevel};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     Toplevel::new(|s: SubsystemHandle| async move {
+    ///         s.request_shutdown();
+    ///     })
+    ///     .handle_shutdown_requests_from_env("SHUTDOWN_TIMEOUT_MS", Duration::from_millis(1000))
+    ///     .await
+    ///     .map_err(Into::into)
+    /// }
+    /// ```
+    pub async fn handle_shutdown_requests_from_env(
+        self,
+        var: &str,
+        default: Duration,
+    ) -> Result<(), GracefulShutdownError<ErrType>> {
+        let shutdown_timeout = shutdown_timeout_from_env(var, default);
+        self.handle_shutdown_requests(shutdown_timeout).await
+    }
+
+    /// Performs a clean program shutdown like
+    /// [`handle_shutdown_requests()`](Toplevel::handle_shutdown_requests),
+    /// but with an absolute deadline instead of a timeout.
+    ///
+    /// This is useful if the shutdown has to be finished by an externally given point in time.
+    ///
+    /// Note that the deadline is absolute, independent of when the shutdown gets requested.
+    /// If a shutdown is requested after the deadline already passed, for example through
+    /// a signal, the remaining subsystems will get cancelled immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The point in time at which the shutdown has to be finished.
+    ///
+    /// # Returns
+    ///
+    /// An error of type [`GracefulShutdownError`] if an error occurred.
+    pub async fn handle_shutdown_requests_until(
+        self,
+        deadline: Instant,
+    ) -> Result<(), GracefulShutdownError<ErrType>> {
+        self.run(move || deadline).await.map(|_| ())
+    }
+
+    /// Gracefully replaces the subsystem tree with a fresh one, without
+    /// shutting down the program.
+    ///
+    /// Initiates a shutdown of the current root subsystem and all of its children,
+    /// waits for them to finish, and then starts `subsystem` as the new root subsystem.
+    /// This is useful for reloading the configuration, for example on `SIGHUP`.
+    ///
+    /// Errors of the old subsystem tree that happen during the reload don't shut down
+    /// the program; they get returned instead. If the old subsystem tree does not finish
+    /// within `shutdown_timeout`, its remaining subsystems get cancelled.
+    /// The new root subsystem gets started in either case.
+    ///
+    /// Everything else, like the signal handlers, stays in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsystem` - The subsystem that should be spawned as the new root node.
+    /// * `shutdown_timeout` - The maximum time the old subsystem tree has to shut down.
+    ///
+    /// # Returns
+    ///
+    /// An error of type [`GracefulShutdownError`] if the old subsystem tree
+    /// failed or timed out during the reload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use miette::Result;
+    /// use tokio::time::Duration;
+    /// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+    ///
+    /// async fn server(subsys: SubsystemHandle, port: u16) -> Result<()> {
+    ///     tracing::info!("Serving on port {port} ...");
+    ///     subsys.on_shutdown_requested().await;
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut toplevel = Toplevel::new(|s| async move {
+    ///         s.start(SubsystemBuilder::new("Server", |s| server(s, 8080)));
+    ///     });
+    ///
+    ///     // The configuration changed
+    ///     toplevel
+    ///         .reload(
+    ///             |s| async move {
+    ///                 s.start(SubsystemBuilder::new("Server", |s| server(s, 8081)));
+    ///                 s.request_shutdown();
+    ///             },
+    ///             Duration::from_millis(1000),
+    ///         )
+    ///         .await?;
+    ///
+    ///     toplevel
+    ///         .handle_shutdown_requests(Duration::from_millis(1000))
+    ///         .await
+    ///         .map_err(Into::into)
+    /// }
+    /// ```
+    pub async fn reload<Fut, Subsys>(
+        &mut self,
+        subsystem: Subsys,
+        shutdown_timeout: Duration,
+    ) -> Result<(), GracefulShutdownError<ErrType>>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Output> + Send,
+    {
+        tracing::info!("Reloading ...");
+
+        // Idle watchers must not mistake the shutdown of the old tree for idleness
+        self.root_descendants.send_replace(None);
+
+        // Catch the errors of the old tree, so they don't shut down the program
+        let old_subsys = &self.toplevel_subsys;
+        old_subsys.change_failure_action(ErrorAction::CatchAndLocalShutdown);
+        old_subsys.change_panic_action(ErrorAction::CatchAndLocalShutdown);
+        old_subsys.initiate_shutdown();
+
+        let result = match tokio::time::timeout(shutdown_timeout, old_subsys.finished()).await {
+            Ok(()) => {
+                let errors = old_subsys.take_errors();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(GracefulShutdownError::SubsystemsFailed(errors))
+                }
+            }
+            Err(_) => {
+                tracing::error!("Reload timed out!");
+                old_subsys.abort();
+                old_subsys.finished().await;
+                Err(GracefulShutdownError::ShutdownTimeout(
+                    old_subsys.take_errors(),
+                ))
             }
-            drop(self.errors);
-            errors.into_boxed_slice()
+        };
+
+        let (toplevel_subsys, output) =
+            start_root_subsystem(&self.root_handle, old_subsys.name(), subsystem);
+        self.root_descendants
+            .send_replace(Some(toplevel_subsys.watch_descendants()));
+        self.toplevel_subsys = toplevel_subsys;
+        self.output = output;
+
+        tracing::info!("Reload finished.");
+        result
+    }
+
+    async fn run(
+        self,
+        get_deadline: impl FnOnce() -> Instant,
+    ) -> Result<Output, GracefulShutdownError<ErrType>> {
+        // If this future gets dropped, request a shutdown so that leaked handles
+        // notice it. Dropping `self` then cancels all remaining subsystems.
+        let _shutdown_on_drop = self
+            .root_handle
+            .get_cancellation_token()
+            .clone()
+            .drop_guard();
+
+        let mut output = self.output;
+        let root_name = self.toplevel_subsys.name();
+        let mut take_output = move || {
+            // The root subsystem produced a value if it finished without an error,
+            // unless it got aborted through `SubsystemHandle::abort_all()`.
+            output.try_recv().map_err(|_| {
+                GracefulShutdownError::SubsystemsFailed(Box::new([SubsystemError::Cancelled(
+                    Arc::clone(&root_name),
+                )]))
+            })
+        };
+
+        // Errors that were already consumed through an `ErrorStream` are not
+        // returned any more, but still count as failures.
+        let collect_errors = move || {
+            let errors = self.errors.collect_remaining();
+            let failed = !errors.is_empty() || self.errors.streamed_any();
+            (errors, failed)
         };
 
         tokio::select!(
             _ = self.toplevel_subsys.join() => {
                 tracing::info!("All subsystems finished.");
+                self.shutdown_state.send_replace(ShutdownState::Finished);
 
                 // Not really necessary, but for good measure.
-                self.root_handle.request_shutdown();
+                // Bypasses the prestop hook, as this is not a shutdown.
+                self.root_handle.get_cancellation_token().cancel();
 
-                let errors = collect_errors();
-                let result = if errors.is_empty() {
-                    Ok(())
+                let (errors, failed) = collect_errors();
+                let result = if !failed {
+                    take_output()
                 } else {
                     Err(GracefulShutdownError::SubsystemsFailed(errors))
                 };
@@ -185,28 +1446,94 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
             },
             _ = self.root_handle.on_shutdown_requested() => {
                 tracing::info!("Shutting down ...");
+                self.shutdown_state.send_replace(ShutdownState::ShutdownRequested);
             }
         );
 
-        match tokio::time::timeout(shutdown_timeout, self.toplevel_subsys.join()).await {
+        let shutdown_started = Instant::now();
+        let deadline = get_deadline();
+        self.root_handle.set_shutdown_deadline(deadline);
+
+        let track_remaining = async {
+            let running_changed = self.root_handle.running_changed();
+            loop {
+                // Register before counting, to not miss changes in between
+                let changed = running_changed.notified();
+                self.shutdown_state
+                    .send_replace(ShutdownState::WaitingForSubsystems {
+                        remaining: self.root_handle.running_subsystems().len(),
+                    });
+                changed.await;
+            }
+        };
+
+        let report_progress = async {
+            let Some(interval) = self.shutdown_progress_interval else {
+                return std::future::pending().await;
+            };
+            let mut interval = tokio::time::interval_at(shutdown_started + interval, interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let remaining = self.root_handle.running_subsystems();
+                tracing::info!(
+                    remaining = ?remaining,
+                    "Shutdown in progress, {} subsystems remaining ...",
+                    remaining.len()
+                );
+            }
+        };
+
+        let join = async {
+            tokio::select! {
+                result = self.toplevel_subsys.join() => result,
+                never = track_remaining => never,
+                never = report_progress => never,
+            }
+        };
+
+        let join_result = tokio::time::timeout_at(deadline, join).await;
+
+        if let Some(hook) = self.shutdown_finished_hook {
+            hook(shutdown_started.elapsed()).await;
+        }
+
+        match join_result {
             Ok(result) => {
                 // An `Err` here would indicate a programming error,
                 // because the toplevel subsys doesn't catch any errors;
                 // it only forwards them.
                 assert!(result.is_ok());
+                self.shutdown_state.send_replace(ShutdownState::Finished);
 
-                let errors = collect_errors();
-                if errors.is_empty() {
+                let (errors, failed) = collect_errors();
+                let result = if !failed {
+                    take_output()
+                } else {
+                    Err(GracefulShutdownError::SubsystemsFailed(errors))
+                };
+                if result.is_ok() {
                     tracing::info!("Shutdown finished.");
-                    Ok(())
                 } else {
                     tracing::warn!("Shutdown finished with errors.");
-                    Err(GracefulShutdownError::SubsystemsFailed(errors))
                 }
+                result
             }
             Err(_) => {
                 tracing::error!("Shutdown timed out!");
-                Err(GracefulShutdownError::ShutdownTimeout(collect_errors()))
+                self.shutdown_state.send_replace(ShutdownState::TimedOut);
+                let mut errors = collect_errors().0.into_vec();
+                let mut cancelled = self
+                    .root_handle
+                    .tree_node()
+                    .abort_all(CancelReason::ShutdownTimeout);
+                if self.report_cancelled {
+                    cancelled.sort();
+                    errors.extend(cancelled.into_iter().map(SubsystemError::Cancelled));
+                }
+                Err(GracefulShutdownError::ShutdownTimeout(
+                    errors.into_boxed_slice(),
+                ))
             }
         }
     }
@@ -217,3 +1544,151 @@ impl<ErrType: ErrTypeTraits> Toplevel<ErrType> {
         self.root_handle.get_cancellation_token()
     }
 }
+
+/// Starts the root subsystem of a Toplevel.
+///
+/// Returns the subsystem and the receiver for its output.
+#[track_caller]
+fn start_root_subsystem<ErrType, Output, Fut, Subsys>(
+    root_handle: &SubsystemHandle<ErrType>,
+    name: Arc<str>,
+    subsystem: Subsys,
+) -> (NestedSubsystem<ErrType>, oneshot::Receiver<Output>)
+where
+    ErrType: ErrTypeTraits,
+    Output: 'static + Send,
+    Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+    Fut: 'static + Future<Output = Output> + Send,
+{
+    let (output_sender, output) = oneshot::channel();
+
+    let mut toplevel_subsys = root_handle.start_with_abs_name(
+        name,
+        move |s| async move {
+            // Ignore errors; an error only means that nobody is interested in the output.
+            let _ = output_sender.send(subsystem(s).await);
+            Result::<(), ErrType>::Ok(())
+        },
+        ErrorActions::new(ErrorAction::Forward, ErrorAction::Forward),
+        false,
+        RunnerOptions::default(),
+        RunnerHooks::default(),
+    );
+
+    // Nobody joins the toplevel subsystem in detail; don't let its outcome log grow forever.
+    toplevel_subsys.forget_outcomes();
+
+    (toplevel_subsys, output)
+}
+
+/// Observes the number of running descendants of the root subsystem,
+/// following it across reloads.
+struct DescendantsWatch {
+    trees: watch::Receiver<Option<watch::Receiver<(bool, u32)>>>,
+    descendants: Option<watch::Receiver<(bool, u32)>>,
+}
+
+impl DescendantsWatch {
+    fn new(root_descendants: &watch::Sender<Option<watch::Receiver<(bool, u32)>>>) -> Self {
+        let mut trees = root_descendants.subscribe();
+        let descendants = trees.borrow_and_update().clone();
+        Self { trees, descendants }
+    }
+
+    /// Waits until `predicate` accepts the number of running descendants,
+    /// which is `None` while a reload is in progress, and returns that number.
+    ///
+    /// Fails once the [`Toplevel`] is gone.
+    async fn wait_for(
+        &mut self,
+        mut predicate: impl FnMut(Option<u32>) -> bool,
+    ) -> Result<Option<u32>, watch::error::RecvError> {
+        loop {
+            let count = self
+                .descendants
+                .as_mut()
+                .map(|descendants| descendants.borrow_and_update().1);
+            if predicate(count) {
+                return Ok(count);
+            }
+
+            let descendants_changed = async {
+                match &mut self.descendants {
+                    Some(descendants) => descendants.changed().await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                changed = self.trees.changed() => {
+                    changed?;
+                    self.descendants = self.trees.borrow_and_update().clone();
+                }
+                changed = descendants_changed => {
+                    // The old tree is finished; wait for the reload to replace it
+                    if changed.is_err() {
+                        self.descendants = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn log_error<ErrType: ErrTypeTraits>(
+    error: &SubsystemError<ErrType>,
+    failure_log_limits: &FailureLogLimits,
+) {
+    let Some(suppressed) = failure_log_limits.record(error.name()) else {
+        return;
+    };
+
+    if suppressed > 0 {
+        tracing::error!(
+            subsystem = %error.name(),
+            suppressed,
+            "More errors from subsystem were suppressed."
+        );
+    }
+
+    match error {
+        SubsystemError::Panicked(name) => {
+            tracing::error!(subsystem = %name, "Uncaught panic from subsystem.")
+        }
+        SubsystemError::Failed(name, e) => {
+            tracing::error!(subsystem = %name, error = %e, "Uncaught error from subsystem.")
+        }
+        SubsystemError::Cancelled(name) => {
+            tracing::error!(subsystem = %name, "Subsystem got cancelled.")
+        }
+    };
+}
+
+/// Reads a timeout in milliseconds from the given environment variable.
+fn shutdown_timeout_from_env(var: &str, default: Duration) -> Duration {
+    match std::env::var(var) {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(millis) => Duration::from_millis(millis),
+            Err(e) => {
+                tracing::warn!(
+                    var,
+                    value,
+                    error = %e,
+                    default = ?default,
+                    "Invalid shutdown timeout, using the default."
+                );
+                default
+            }
+        },
+        Err(std::env::VarError::NotPresent) => default,
+        Err(e) => {
+            tracing::warn!(
+                var,
+                error = %e,
+                default = ?default,
+                "Invalid shutdown timeout, using the default."
+            );
+            default
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{future::Future, marker::PhantomData, sync::Arc};
+
+use tokio::runtime::Handle;
+
+use crate::{BoxedError, ErrTypeTraits, SubsystemHandle, Toplevel};
+
+/// Configures a [`Toplevel`] before its root subsystem gets started.
+///
+/// Created through [`Toplevel::builder()`].
+///
+/// The root subsystem starts running as soon as the [`Toplevel`] is created,
+/// so everything that affects it, like the names of the subsystems, has to be
+/// configured here instead of on the [`Toplevel`] itself.
+///
+/// # Examples
+///
+/// ```
+/// use miette::Result;
+/// use tokio::time::Duration;
+/// use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+///
+/// async fn nested(subsys: SubsystemHandle) -> Result<()> {
+///     assert_eq!(subsys.name(), "app.server.nested");
+///     Ok(())
+/// }
+///
+/// async fn server(subsys: SubsystemHandle) -> Result<()> {
+///     subsys.start(SubsystemBuilder::new("nested", nested));
+///     Ok(())
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     Toplevel::builder()
+///         .with_name_separator('.')
+///         .with_root_name("app")
+///         .build(|s| async move {
+///             s.start(SubsystemBuilder::new("server", server));
+///         })
+///         .handle_shutdown_requests(Duration::from_millis(1000))
+///         .await
+///         .map_err(Into::into)
+/// }
+/// ```
+#[must_use = "This builder must be consumed by calling `build` on it."]
+pub struct ToplevelBuilder<ErrType: ErrTypeTraits = BoxedError> {
+    pub(crate) runtime: Option<Handle>,
+    pub(crate) name_separator: char,
+    pub(crate) root_name: Option<Arc<str>>,
+    _err_type: PhantomData<fn() -> ErrType>,
+}
+
+impl<ErrType: ErrTypeTraits> ToplevelBuilder<ErrType> {
+    pub(crate) fn new() -> Self {
+        Self {
+            runtime: None,
+            name_separator: '/',
+            root_name: None,
+            _err_type: PhantomData,
+        }
+    }
+
+    /// Runs the subsystem tree on the given runtime.
+    ///
+    /// See [`Toplevel::new_on()`] for more information.
+    ///
+    /// # Arguments
+    ///
+    /// * `runtime` - The runtime the subsystem tree should run on.
+    pub fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Changes the character that joins the segments of the absolute subsystem names.
+    ///
+    /// By default, names are built like unix paths, for example `/a/b/c`.
+    /// With `.` as separator, they look like `.a.b.c` instead; together with
+    /// [`with_root_name()`](ToplevelBuilder::with_root_name), like `root.a.b.c`.
+    /// Useful if the names end up somewhere where `/` has a special meaning,
+    /// like in metric labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `separator` - The separator of the name segments.
+    pub fn with_name_separator(mut self, separator: char) -> Self {
+        self.name_separator = separator;
+        self
+    }
+
+    /// Changes the name of the toplevel subsystem, which prefixes
+    /// the absolute names of all other subsystems.
+    ///
+    /// By default, the toplevel subsystem is named after the separator, like `/`,
+    /// and its children are called `/a` instead of `//a`. With a custom root name,
+    /// the names look like `root/a` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the toplevel subsystem.
+    pub fn with_root_name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.root_name = Some(name.into());
+        self
+    }
+
+    /// Creates the [`Toplevel`] and starts its root subsystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsystem` - The subsystem that should be spawned as the root node.
+    ///   Usually the job of this subsystem is to spawn further subsystems.
+    #[track_caller]
+    pub fn build<Fut, Subsys>(self, subsystem: Subsys) -> Toplevel<ErrType>
+    where
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = ()> + Send,
+    {
+        Toplevel::from_builder(self, subsystem)
+    }
+
+    /// Creates the [`Toplevel`] and starts its root subsystem, which returns a value.
+    ///
+    /// See [`Toplevel::new_with_output()`] for more information.
+    ///
+    /// # Arguments
+    ///
+    /// * `subsystem` - The subsystem that should be spawned as the root node.
+    ///   Its return value will be the output of the Toplevel.
+    #[track_caller]
+    pub fn build_with_output<Output, Fut, Subsys>(
+        self,
+        subsystem: Subsys,
+    ) -> Toplevel<ErrType, Output>
+    where
+        Output: 'static + Send,
+        Subsys: 'static + FnOnce(SubsystemHandle<ErrType>) -> Fut + Send,
+        Fut: 'static + Future<Output = Output> + Send,
+    {
+        Toplevel::from_builder(self, subsystem)
+    }
+}
@@ -0,0 +1,182 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU8, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    runner::CancelReason,
+    utils::remote_drop_collection::{RemoteDrop, RemotelyDroppableItems},
+    HealthStatus, SubsystemOutcome,
+};
+
+/// The state of a subsystem and all of its children at a given point in time.
+///
+/// Created through [`Toplevel::dump_tree()`](crate::Toplevel::dump_tree).
+#[derive(Clone, Debug)]
+pub struct TreeSnapshot {
+    /// The absolute name of the subsystem.
+    pub name: Arc<str>,
+    /// Whether the subsystem function is still running.
+    ///
+    /// If `false`, the subsystem already returned and only waits for its children to finish.
+    pub alive: bool,
+    /// Whether the subsystem received a shutdown request.
+    pub shutdown_requested: bool,
+    /// The health the subsystem reported for itself.
+    pub health: HealthStatus,
+    /// The children of the subsystem that are still running.
+    pub children: Vec<TreeSnapshot>,
+}
+
+/// A node of the subsystem tree, owned by the subsystem handle.
+///
+/// Nodes get removed from their parent once the subsystem and all of its children are finished.
+pub(crate) struct TreeNode {
+    name: Arc<Mutex<Arc<str>>>,
+    cancellation_token: CancellationToken,
+    alive: AtomicBool,
+    health: AtomicU8,
+    outcome: AtomicU8,
+    groups: Mutex<Vec<Arc<str>>>,
+    runner: Mutex<Option<AbortHandle>>,
+    // Why the runner got aborted, if it got aborted on purpose
+    cancel_reason: OnceLock<CancelReason>,
+    children: RemotelyDroppableItems<Arc<TreeNode>>,
+}
+
+impl TreeNode {
+    pub(crate) fn new(name: Arc<Mutex<Arc<str>>>, cancellation_token: CancellationToken) -> Self {
+        Self {
+            name,
+            cancellation_token,
+            alive: AtomicBool::new(true),
+            health: AtomicU8::new(HealthStatus::Healthy.to_u8()),
+            outcome: AtomicU8::new(0),
+            groups: Mutex::new(Vec::new()),
+            runner: Mutex::new(None),
+            cancel_reason: OnceLock::new(),
+            children: RemotelyDroppableItems::new(),
+        }
+    }
+
+    pub(crate) fn insert_children(
+        &self,
+        children: impl IntoIterator<Item = Arc<TreeNode>>,
+    ) -> Vec<RemoteDrop<Arc<TreeNode>>> {
+        self.children.insert_many(children)
+    }
+
+    pub(crate) fn set_runner(&self, runner: AbortHandle) {
+        *self.runner.lock().unwrap() = Some(runner);
+    }
+
+    /// Aborts the runners of all subsystems in this part of the tree.
+    ///
+    /// Returns the names of the subsystems whose function was still running;
+    /// the others only waited for their children.
+    pub(crate) fn abort_all(&self, reason: CancelReason) -> Vec<Arc<str>> {
+        let mut interrupted = Vec::new();
+        self.abort_all_into(reason, &mut interrupted);
+        interrupted
+    }
+
+    fn abort_all_into(&self, reason: CancelReason, interrupted: &mut Vec<Arc<str>>) {
+        if let Some(runner) = self.runner.lock().unwrap().as_ref() {
+            if self.alive.load(Ordering::Acquire) {
+                interrupted.push(Arc::clone(&self.name.lock().unwrap()));
+            }
+            self.set_cancel_reason(reason);
+            runner.abort();
+        }
+        for child in self.children.items() {
+            child.abort_all_into(reason, interrupted);
+        }
+    }
+
+    /// Records why the runner gets aborted. Only the first reason sticks.
+    pub(crate) fn set_cancel_reason(&self, reason: CancelReason) {
+        let _ = self.cancel_reason.set(reason);
+    }
+
+    pub(crate) fn cancel_reason(&self) -> Option<CancelReason> {
+        self.cancel_reason.get().copied()
+    }
+
+    pub(crate) fn set_groups(&self, groups: Vec<Arc<str>>) {
+        *self.groups.lock().unwrap() = groups;
+    }
+
+    /// Requests a shutdown of all subsystems in this part of the tree
+    /// that are members of the given group.
+    pub(crate) fn shutdown_group(&self, group: &str) {
+        if self
+            .groups
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|g| g.as_ref() == group)
+        {
+            self.cancellation_token.cancel();
+        }
+        for child in self.children.items() {
+            child.shutdown_group(group);
+        }
+    }
+
+    /// Marks that the subsystem function returned.
+    pub(crate) fn mark_finished(&self) {
+        self.alive.store(false, Ordering::Release);
+    }
+
+    /// Records how the subsystem ended. Only the first outcome sticks.
+    pub(crate) fn set_outcome(&self, outcome: SubsystemOutcome) {
+        let _ =
+            self.outcome
+                .compare_exchange(0, outcome.to_u8(), Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    pub(crate) fn outcome(&self) -> Option<SubsystemOutcome> {
+        SubsystemOutcome::from_u8(self.outcome.load(Ordering::Acquire))
+    }
+
+    pub(crate) fn set_health(&self, health: HealthStatus) {
+        self.health.store(health.to_u8(), Ordering::Release);
+    }
+
+    pub(crate) fn health(&self) -> HealthStatus {
+        HealthStatus::from_u8(self.health.load(Ordering::Acquire))
+    }
+
+    /// The worst health of all subsystems in this part of the tree.
+    pub(crate) fn aggregate_health(&self) -> HealthStatus {
+        self.children
+            .items()
+            .iter()
+            .map(|child| child.aggregate_health())
+            .fold(self.health(), Ord::max)
+    }
+
+    pub(crate) fn children(&self) -> Vec<TreeSnapshot> {
+        let mut children: Vec<_> = self
+            .children
+            .items()
+            .iter()
+            .map(|child| child.snapshot())
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        children
+    }
+
+    pub(crate) fn snapshot(&self) -> TreeSnapshot {
+        TreeSnapshot {
+            name: Arc::clone(&self.name.lock().unwrap()),
+            alive: self.alive.load(Ordering::Acquire),
+            shutdown_requested: self.cancellation_token.is_cancelled(),
+            health: self.health(),
+            children: self.children(),
+        }
+    }
+}
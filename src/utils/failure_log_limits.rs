@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+struct FailureLogLimit {
+    max_count: usize,
+    window: Duration,
+    window_start: Option<Instant>,
+    logged: usize,
+    suppressed: usize,
+}
+
+/// Limits how often the errors of individual subsystems get logged,
+/// keyed by the absolute subsystem name.
+#[derive(Default)]
+pub(crate) struct FailureLogLimits {
+    limits: Mutex<HashMap<Arc<str>, FailureLogLimit>>,
+}
+
+impl FailureLogLimits {
+    /// Allows at most `max_count` errors of the subsystem `name` to be logged within `window`.
+    ///
+    /// Subsystems that share the same name also share their limit.
+    pub(crate) fn set(&self, name: Arc<str>, max_count: usize, window: Duration) {
+        let mut limits = self.limits.lock().unwrap();
+        let limit = limits.entry(name).or_insert(FailureLogLimit {
+            max_count,
+            window,
+            window_start: None,
+            logged: 0,
+            suppressed: 0,
+        });
+        limit.max_count = max_count;
+        limit.window = window;
+    }
+
+    /// Applies the limit of the subsystem `old_name` to `new_name` as well,
+    /// for subsystems that got renamed.
+    ///
+    /// The limit of `old_name` stays in place for other subsystems that share the name.
+    pub(crate) fn rename(&self, old_name: &str, new_name: Arc<str>) {
+        let mut limits = self.limits.lock().unwrap();
+        let Some(limit) = limits.get(old_name) else {
+            return;
+        };
+        let (max_count, window) = (limit.max_count, limit.window);
+        limits.entry(new_name).or_insert(FailureLogLimit {
+            max_count,
+            window,
+            window_start: None,
+            logged: 0,
+            suppressed: 0,
+        });
+    }
+
+    /// Records an error of the subsystem `name` and decides whether it should get logged.
+    ///
+    /// Returns `None` if the error should be suppressed. Otherwise, returns the number
+    /// of errors that got suppressed since the last logged one.
+    pub(crate) fn record(&self, name: &str) -> Option<usize> {
+        let mut limits = self.limits.lock().unwrap();
+        let Some(limit) = limits.get_mut(name) else {
+            return Some(0);
+        };
+
+        let now = Instant::now();
+        if limit
+            .window_start
+            .map_or(true, |start| now.duration_since(start) >= limit.window)
+        {
+            limit.window_start = Some(now);
+            limit.logged = 0;
+        }
+
+        if limit.logged < limit.max_count {
+            limit.logged += 1;
+            Some(std::mem::take(&mut limit.suppressed))
+        } else {
+            limit.suppressed += 1;
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn unlimited_subsystems_always_get_logged() {
+    let limits = FailureLogLimits::default();
+
+    for _ in 0..10 {
+        assert_eq!(limits.record("/a"), Some(0));
+    }
+}
+
+#[test]
+fn excess_errors_get_suppressed_and_counted() {
+    let limits = FailureLogLimits::default();
+    limits.set(Arc::from("/a"), 2, Duration::from_millis(100));
+
+    assert_eq!(limits.record("/a"), Some(0));
+    assert_eq!(limits.record("/a"), Some(0));
+    assert_eq!(limits.record("/a"), None);
+    assert_eq!(limits.record("/a"), None);
+
+    // Other subsystems are not affected
+    assert_eq!(limits.record("/b"), Some(0));
+
+    std::thread::sleep(Duration::from_millis(150));
+
+    assert_eq!(limits.record("/a"), Some(2));
+    assert_eq!(limits.record("/a"), Some(0));
+    assert_eq!(limits.record("/a"), None);
+}
+
+#[test]
+fn renamed_subsystems_keep_their_limit() {
+    let limits = FailureLogLimits::default();
+    limits.set(Arc::from("/a"), 1, Duration::from_millis(100));
+    limits.rename("/a", Arc::from("/b"));
+
+    assert_eq!(limits.record("/b"), Some(0));
+    assert_eq!(limits.record("/b"), None);
+
+    // The old name keeps its own limit
+    assert_eq!(limits.record("/a"), Some(0));
+    assert_eq!(limits.record("/a"), None);
+
+    // Renaming an unlimited subsystem does not limit it
+    limits.rename("/c", Arc::from("/d"));
+    assert_eq!(limits.record("/d"), Some(0));
+    assert_eq!(limits.record("/d"), Some(0));
+}
@@ -1,4 +1,10 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+};
 
 use tokio::sync::watch;
 
@@ -7,10 +13,25 @@ use crate::{
     ErrTypeTraits,
 };
 
+/// Decides what happens with the errors of a token; see [`JoinerToken::new`].
+pub(crate) type OnError<ErrType> =
+    Box<dyn Fn(SubsystemError<ErrType>) -> Option<SubsystemError<ErrType>> + Sync + Send>;
+
 struct Inner<ErrType: ErrTypeTraits> {
     counter: watch::Sender<(bool, u32)>,
+    // (running, finished) direct children.
+    // A child counts as finished once it and all of its children are finished.
+    direct_children: watch::Sender<(u32, u64)>,
+    // The number of running direct children that announced that they are about to finish.
+    // Only modified while holding the lock of `direct_children`.
+    leaving_children: AtomicU32,
+    // Whether this token announced to its parent that it is about to finish.
+    leaving: AtomicBool,
+    // If set, the children of this token are accounted to its parent instead.
+    // Only modified while holding the lock of `counter`.
+    detached: AtomicBool,
     parent: Option<Arc<Inner<ErrType>>>,
-    on_error: Box<dyn Fn(SubsystemError<ErrType>) -> Option<SubsystemError<ErrType>> + Sync + Send>,
+    on_error: OnError<ErrType>,
 }
 
 /// A token that keeps reference of its existance and its children.
@@ -18,6 +39,12 @@ pub(crate) struct JoinerToken<ErrType: ErrTypeTraits> {
     inner: Arc<Inner<ErrType>>,
 }
 
+/// A second reference to a [`JoinerToken`] that can mark it as finished
+/// while the token itself is still held elsewhere, for example by a leaked handle.
+pub(crate) struct JoinerTokenFinisher<ErrType: ErrTypeTraits> {
+    inner: Arc<Inner<ErrType>>,
+}
+
 /// A reference version that does not keep the content alive; purely for
 /// joining the subtree.
 #[derive(Clone)]
@@ -60,6 +87,10 @@ impl<ErrType: ErrTypeTraits> JoinerToken<ErrType> {
     ) -> (Self, JoinerTokenRef) {
         let inner = Arc::new(Inner {
             counter: watch::channel((true, 0)).0,
+            direct_children: watch::channel((0, 0)).0,
+            leaving_children: AtomicU32::new(0),
+            leaving: AtomicBool::new(false),
+            detached: AtomicBool::new(false),
             parent: None,
             on_error: Box::new(on_error),
         });
@@ -81,6 +112,7 @@ impl<ErrType: ErrTypeTraits> JoinerToken<ErrType> {
             .await;
     }
 
+    #[cfg(test)]
     pub(crate) fn child_token(
         &self,
         on_error: impl Fn(SubsystemError<ErrType>) -> Option<SubsystemError<ErrType>>
@@ -88,39 +120,164 @@ impl<ErrType: ErrTypeTraits> JoinerToken<ErrType> {
             + Send
             + 'static,
     ) -> (Self, JoinerTokenRef) {
+        self.child_tokens(vec![Box::new(on_error) as OnError<ErrType>])
+            .pop()
+            .expect("One token per callback")
+    }
+
+    /// Creates one child token per `on_error` callback, while only
+    /// updating the counters of the ancestors once.
+    pub(crate) fn child_tokens(
+        &self,
+        on_errors: Vec<OnError<ErrType>>,
+    ) -> Vec<(Self, JoinerTokenRef)> {
+        let count = on_errors.len() as u32;
+        if count == 0 {
+            return Vec::new();
+        }
+
         let mut maybe_parent = Some(&self.inner);
         while let Some(parent) = maybe_parent {
-            parent
-                .counter
-                .send_modify(|(_alive, children)| *children += 1);
+            parent.modify_children(|children| *children += count);
             maybe_parent = parent.parent.as_ref();
         }
 
-        let inner = Arc::new(Inner {
-            counter: watch::channel((true, 0)).0,
-            parent: Some(Arc::clone(&self.inner)),
-            on_error: Box::new(on_error),
-        });
+        self.inner
+            .direct_children
+            .send_modify(|(running, _finished)| *running += count);
 
-        let weak_ref = JoinerTokenRef {
-            counter: inner.counter.subscribe(),
-        };
+        on_errors
+            .into_iter()
+            .map(|on_error| {
+                let inner = Arc::new(Inner {
+                    counter: watch::channel((true, 0)).0,
+                    direct_children: watch::channel((0, 0)).0,
+                    leaving_children: AtomicU32::new(0),
+                    leaving: AtomicBool::new(false),
+                    detached: AtomicBool::new(false),
+                    parent: Some(Arc::clone(&self.inner)),
+                    on_error,
+                });
 
-        (Self { inner }, weak_ref)
+                let weak_ref = JoinerTokenRef {
+                    counter: inner.counter.subscribe(),
+                };
+
+                (Self { inner }, weak_ref)
+            })
+            .collect()
     }
 
-    #[cfg(test)]
+    /// Waits until `count` direct children finished, counted from now on.
+    pub(crate) async fn join_direct_children(&self, count: usize) {
+        let mut subscriber = self.inner.direct_children.subscribe();
+        let target = subscriber.borrow().1 + count as u64;
+
+        // Ignore errors; the channel can't close while we hold `self`.
+        let _ = subscriber
+            .wait_for(|(_running, finished)| *finished >= target)
+            .await;
+    }
+
+    /// The number of children in the entire subtree of this token.
     pub(crate) fn count(&self) -> u32 {
         self.inner.counter.borrow().1
     }
 
+    /// The number of direct children of this token that are not finished yet.
+    pub(crate) fn direct_children_count(&self) -> u32 {
+        self.inner.direct_children.borrow().0
+    }
+
+    /// Announces that this token is about to finish.
+    ///
+    /// Returns `true` if all other direct children of the parent are finished
+    /// or announced their finish as well, or if there is no parent.
+    pub(crate) fn leave_siblings(&self) -> bool {
+        let Some(parent) = &self.inner.parent else {
+            return true;
+        };
+
+        let mut is_last = false;
+        parent
+            .direct_children
+            .send_if_modified(|(running, _finished)| {
+                if !self.inner.leaving.swap(true, Ordering::Relaxed) {
+                    parent.leaving_children.fetch_add(1, Ordering::Relaxed);
+                }
+                is_last = parent.leaving_children.load(Ordering::Relaxed) >= *running;
+                false
+            });
+
+        is_last
+    }
+
     pub(crate) fn raise_failure(&self, stop_reason: SubsystemError<ErrType>) {
+        self.inner.raise_failure(stop_reason);
+    }
+
+    /// Marks the token as finished without waiting for its children.
+    ///
+    /// The children are still accounted to all ancestors of this token, and their
+    /// errors get passed on to the parent without going through `on_error` of this token.
+    pub(crate) fn detach_children(&self) {
+        self.inner.finish();
+        self.inner.counter.send_modify(|(_alive, children)| {
+            self.inner.detached.store(true, Ordering::Relaxed);
+            *children = 0;
+        });
+    }
+
+    /// Waits until all direct children and their children are finished,
+    /// even if they got detached.
+    pub(crate) async fn join_detached_children(&self) {
+        let mut subscriber = self.inner.direct_children.subscribe();
+
+        // Ignore errors; the channel can't close while we hold `self`.
+        let _ = subscriber
+            .wait_for(|(running, _finished)| *running == 0)
+            .await;
+    }
+
+    pub(crate) fn finisher(&self) -> JoinerTokenFinisher<ErrType> {
+        JoinerTokenFinisher {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    pub(crate) fn downgrade(self) -> JoinerTokenRef {
+        JoinerTokenRef {
+            counter: self.inner.counter.subscribe(),
+        }
+    }
+}
+
+impl<ErrType: ErrTypeTraits> JoinerTokenFinisher<ErrType> {
+    pub(crate) fn raise_failure(&self, stop_reason: SubsystemError<ErrType>) {
+        self.inner.raise_failure(stop_reason);
+    }
+
+    /// Marks the token as finished, as if it got dropped.
+    ///
+    /// Its children still count until they are finished.
+    pub(crate) fn finish(&self) {
+        self.inner.finish();
+    }
+}
+
+impl<ErrType: ErrTypeTraits> Inner<ErrType> {
+    fn raise_failure(self: &Arc<Self>, stop_reason: SubsystemError<ErrType>) {
         let mut maybe_stop_reason = Some(stop_reason);
 
-        let mut maybe_parent = Some(&self.inner);
+        let mut maybe_parent = Some(self);
         while let Some(parent) = maybe_parent {
             if let Some(stop_reason) = maybe_stop_reason {
-                maybe_stop_reason = (parent.on_error)(stop_reason);
+                // A detached token is finished and no longer handles the errors of its children
+                maybe_stop_reason = if parent.detached.load(Ordering::Relaxed) {
+                    Some(stop_reason)
+                } else {
+                    (parent.on_error)(stop_reason)
+                };
             } else {
                 break;
             }
@@ -131,11 +288,30 @@ impl<ErrType: ErrTypeTraits> JoinerToken<ErrType> {
         handle_unhandled_stopreason(maybe_stop_reason);
     }
 
-    pub(crate) fn downgrade(self) -> JoinerTokenRef {
-        JoinerTokenRef {
-            counter: self.inner.counter.subscribe(),
+    /// Marks the token as no longer alive. Only has an effect the first time.
+    fn finish(&self) {
+        let was_alive = self
+            .counter
+            .send_if_modified(|(alive, _children)| std::mem::replace(alive, false));
+        if !was_alive {
+            return;
+        }
+
+        let mut maybe_parent = self.parent.as_ref();
+        while let Some(parent) = maybe_parent {
+            parent.modify_children(|children| *children -= 1);
+            maybe_parent = parent.parent.as_ref();
         }
     }
+
+    /// Modifies the children counter, unless the children got detached.
+    fn modify_children(&self, f: impl FnOnce(&mut u32)) {
+        self.counter.send_modify(|(_alive, children)| {
+            if !self.detached.load(Ordering::Relaxed) {
+                f(children);
+            }
+        });
+    }
 }
 
 impl JoinerTokenRef {
@@ -149,11 +325,22 @@ impl JoinerTokenRef {
             .await;
     }
 
+    pub(crate) fn is_finished(&self) -> bool {
+        let (alive, children) = *self.counter.borrow();
+        !alive && children == 0
+    }
+
     #[cfg(test)]
     pub(crate) fn count(&self) -> u32 {
         self.counter.borrow().1
     }
 
+    /// Returns a receiver that observes `(alive, children)`
+    /// of the subsystem, where `children` counts all descendants.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<(bool, u32)> {
+        self.counter.clone()
+    }
+
     #[cfg(test)]
     pub(crate) fn alive(&self) -> bool {
         self.counter.borrow().0
@@ -162,16 +349,22 @@ impl JoinerTokenRef {
 
 impl<ErrType: ErrTypeTraits> Drop for JoinerToken<ErrType> {
     fn drop(&mut self) {
-        self.inner
-            .counter
-            .send_modify(|(alive, _children)| *alive = false);
+        self.inner.finish();
+    }
+}
 
-        let mut maybe_parent = self.inner.parent.as_ref();
-        while let Some(parent) = maybe_parent {
-            parent
-                .counter
-                .send_modify(|(_alive, children)| *children -= 1);
-            maybe_parent = parent.parent.as_ref();
+impl<ErrType: ErrTypeTraits> Drop for Inner<ErrType> {
+    fn drop(&mut self) {
+        // All children of this token hold a reference to `self`,
+        // so this token and all of its children are finished now.
+        if let Some(parent) = &self.parent {
+            parent.direct_children.send_modify(|(running, finished)| {
+                if self.leaving.load(Ordering::Relaxed) {
+                    parent.leaving_children.fetch_sub(1, Ordering::Relaxed);
+                }
+                *running -= 1;
+                *finished += 1;
+            });
         }
     }
 }
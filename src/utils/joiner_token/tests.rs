@@ -229,3 +229,141 @@ fn debug_print_ref() {
         "JoinerTokenRef(alive = false, children = 0)"
     );
 }
+
+#[tokio::test]
+#[traced_test]
+async fn join_direct_children() {
+    let (root, _) = JoinerToken::<BoxedError>::new(|_| None);
+    let (child1, _) = root.child_token(|_| None);
+    let (child2, _) = root.child_token(|_| None);
+    let (grandchild, _) = child1.child_token(|_| None);
+
+    let join_one = root.join_direct_children(1);
+    tokio::pin!(join_one);
+
+    // A child is only finished once its own children are finished
+    drop(child1);
+    assert!(timeout(Duration::from_millis(20), &mut join_one)
+        .await
+        .is_err());
+
+    drop(grandchild);
+    assert!(timeout(Duration::from_millis(20), &mut join_one)
+        .await
+        .is_ok());
+
+    // Only children that finish after the call are counted
+    let join_one = root.join_direct_children(1);
+    tokio::pin!(join_one);
+    assert!(timeout(Duration::from_millis(20), &mut join_one)
+        .await
+        .is_err());
+
+    drop(child2);
+    assert!(timeout(Duration::from_millis(20), &mut join_one)
+        .await
+        .is_ok());
+}
+
+#[test]
+#[traced_test]
+fn direct_children_count() {
+    let (root, _) = JoinerToken::<BoxedError>::new(|_| None);
+    let (child1, _) = root.child_token(|_| None);
+    let (child2, _) = root.child_token(|_| None);
+    let (grandchild, _) = child1.child_token(|_| None);
+
+    assert_eq!(2, root.direct_children_count());
+    assert_eq!(1, child1.direct_children_count());
+    assert_eq!(3, root.count());
+
+    drop(child1);
+    assert_eq!(2, root.direct_children_count());
+
+    drop(grandchild);
+    assert_eq!(1, root.direct_children_count());
+
+    drop(child2);
+    assert_eq!(0, root.direct_children_count());
+}
+
+#[test]
+#[traced_test]
+fn finisher() {
+    let (root, weak_root) = JoinerToken::<BoxedError>::new(|_| None);
+    let (child, weak_child) = root.child_token(|_| None);
+    let (grandchild, _) = child.child_token(|_| None);
+    assert_eq!(2, root.count());
+
+    child.finisher().finish();
+    assert!(!weak_child.alive());
+    assert!(!weak_child.is_finished());
+    assert_eq!(1, root.count());
+
+    // Finishing is idempotent
+    child.finisher().finish();
+    drop(child);
+    assert_eq!(1, root.count());
+
+    drop(grandchild);
+    assert!(weak_child.is_finished());
+    assert_eq!(0, root.count());
+    assert!(weak_root.alive());
+}
+
+#[tokio::test]
+async fn detach_children() {
+    let (root, _) = JoinerToken::<BoxedError>::new(|_| None);
+    let (child, weak_child) = root.child_token(|_| None);
+    let (grandchild, _) = child.child_token(|_| None);
+    let (great_grandchild, _) = grandchild.child_token(|_| None);
+    assert_eq!(3, root.count());
+
+    child.detach_children();
+    assert!(weak_child.is_finished());
+    assert_eq!(2, root.count());
+
+    // The children are no longer counted by the detached token
+    let (great_great_grandchild, _) = great_grandchild.child_token(|_| None);
+    assert!(weak_child.is_finished());
+    assert_eq!(3, root.count());
+    drop(great_great_grandchild);
+    drop(great_grandchild);
+    assert!(weak_child.is_finished());
+    assert_eq!(1, root.count());
+
+    // The detached children still get awaited
+    assert!(
+        timeout(Duration::from_millis(10), child.join_detached_children())
+            .await
+            .is_err()
+    );
+    drop(grandchild);
+    timeout(Duration::from_millis(10), child.join_detached_children())
+        .await
+        .unwrap();
+    assert_eq!(0, root.count());
+}
+
+#[test]
+#[traced_test]
+fn leave_siblings() {
+    let (root, _) = JoinerToken::<BoxedError>::new(|_| None);
+    assert!(root.leave_siblings());
+
+    let (child1, _) = root.child_token(|_| None);
+    let (child2, _) = root.child_token(|_| None);
+    let (child3, _) = root.child_token(|_| None);
+
+    assert!(!child1.leave_siblings());
+    // Announcing twice counts only once
+    assert!(!child1.leave_siblings());
+
+    // A finished child is no longer counted as leaving
+    drop(child1);
+    assert!(!child2.leave_siblings());
+
+    // Leaving children count as gone, even if they are still running
+    assert!(child3.leave_siblings());
+    assert!(child2.leave_siblings());
+}
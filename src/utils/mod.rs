@@ -1,5 +1,13 @@
+mod failure_log_limits;
 mod joiner_token;
+mod shutdown_initiator;
+mod subsystem_registry;
+pub(crate) use failure_log_limits::FailureLogLimits;
 pub(crate) use joiner_token::JoinerToken;
+pub(crate) use joiner_token::JoinerTokenFinisher;
 pub(crate) use joiner_token::JoinerTokenRef;
+pub(crate) use joiner_token::OnError;
+pub(crate) use shutdown_initiator::ShutdownInitiator;
+pub(crate) use subsystem_registry::SubsystemRegistry;
 
 pub(crate) mod remote_drop_collection;
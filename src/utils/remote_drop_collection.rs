@@ -4,7 +4,7 @@ use std::sync::{
 };
 
 struct RemotelyDroppableItem<T> {
-    _item: T,
+    item: T,
     offset: Arc<AtomicUsize>,
 }
 
@@ -30,20 +30,38 @@ impl<T> RemotelyDroppableItems<T> {
 
     pub(crate) fn insert(&self, item: T) -> RemoteDrop<T> {
         let mut items = self.items.lock().unwrap();
+        self.push(&mut items, item)
+    }
+
+    /// Inserts multiple items at once, while only locking the collection once.
+    pub(crate) fn insert_many(&self, new_items: impl IntoIterator<Item = T>) -> Vec<RemoteDrop<T>> {
+        let mut items = self.items.lock().unwrap();
+        new_items
+            .into_iter()
+            .map(|item| self.push(&mut items, item))
+            .collect()
+    }
 
+    fn push(&self, items: &mut Vec<RemotelyDroppableItem<T>>, item: T) -> RemoteDrop<T> {
         let offset = Arc::new(AtomicUsize::new(items.len()));
         let weak_offset = Arc::downgrade(&offset);
 
-        items.push(RemotelyDroppableItem {
-            _item: item,
-            offset,
-        });
+        items.push(RemotelyDroppableItem { item, offset });
 
         RemoteDrop {
             data: Arc::downgrade(&self.items),
             offset: weak_offset,
         }
     }
+
+    /// Returns copies of all items that are currently contained, in no particular order.
+    pub(crate) fn items(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let items = self.items.lock().unwrap();
+        items.iter().map(|item| item.item.clone()).collect()
+    }
 }
 
 /// Drops its referenced item when dropped
@@ -84,3 +84,25 @@ fn drop_token() {
     assert_eq!(0, count3.count());
     assert_eq!(0, count4.count());
 }
+
+#[test]
+fn insert_many() {
+    let items = RemotelyDroppableItems::new();
+
+    let (count1, _) = JoinerToken::<BoxedError>::new(|_| None);
+    assert_eq!(0, count1.count());
+
+    let mut tokens = items.insert_many([
+        count1.child_token(|_| None),
+        count1.child_token(|_| None),
+        count1.child_token(|_| None),
+    ]);
+    assert_eq!(3, tokens.len());
+    assert_eq!(3, count1.count());
+
+    drop(tokens.remove(1));
+    assert_eq!(2, count1.count());
+
+    drop(tokens);
+    assert_eq!(0, count1.count());
+}
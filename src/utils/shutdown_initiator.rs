@@ -0,0 +1,89 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use tokio::runtime::Handle;
+use tokio_util::sync::CancellationToken;
+
+type PrestopHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Initiates the shutdown of the entire subsystem tree.
+///
+/// All shutdown requests go through this, no matter whether they come from
+/// the Toplevel, from a subsystem or from an error. This guarantees that the
+/// prestop hook finishes before any subsystem gets notified about the shutdown.
+pub(crate) struct ShutdownInitiator {
+    token: CancellationToken,
+    prestop_hook: Mutex<Option<PrestopHook>>,
+    initiated: AtomicBool,
+    runtime: Option<Handle>,
+}
+
+impl ShutdownInitiator {
+    pub(crate) fn new(token: CancellationToken, runtime: Option<Handle>) -> Self {
+        Self {
+            token,
+            prestop_hook: Mutex::new(None),
+            initiated: AtomicBool::new(false),
+            runtime,
+        }
+    }
+
+    /// The token that gets cancelled once the shutdown is initiated
+    /// and the prestop hook is finished.
+    pub(crate) fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Sets the hook that runs before the shutdown gets propagated.
+    ///
+    /// Has no effect if the shutdown was already initiated.
+    pub(crate) fn set_prestop_hook(&self, hook: PrestopHook) {
+        let mut prestop_hook = self.prestop_hook.lock().unwrap();
+        if !self.initiated.load(Ordering::Acquire) {
+            *prestop_hook = Some(hook);
+        }
+    }
+
+    /// Initiates the shutdown, if it wasn't initiated already.
+    ///
+    /// Does not block; if a prestop hook is set, the token gets cancelled
+    /// by a task once the hook is finished.
+    pub(crate) fn initiate(&self) {
+        let hook = {
+            let mut prestop_hook = self.prestop_hook.lock().unwrap();
+            if self.initiated.swap(true, Ordering::AcqRel) {
+                return;
+            }
+            prestop_hook.take()
+        };
+
+        match hook {
+            Some(hook) => {
+                // Cancels the token even if the task gets dropped, for example
+                // because the runtime shuts down
+                let shutdown = self.token.clone().drop_guard();
+                crate::tokio_task::spawn(
+                    async move {
+                        hook().await;
+                        drop(shutdown);
+                    },
+                    "prestop",
+                    self.runtime.as_ref(),
+                );
+            }
+            None => self.token.cancel(),
+        }
+    }
+
+    /// Returns whether the shutdown got initiated, even if the
+    /// prestop hook is still running.
+    pub(crate) fn is_initiated(&self) -> bool {
+        self.initiated.load(Ordering::Acquire) || self.token.is_cancelled()
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+type NameCell = Arc<Mutex<Arc<str>>>;
+
+struct Entry {
+    // Identifies the entry, as multiple subsystems can share a name
+    name: NameCell,
+    finished: CancellationToken,
+}
+
+/// The running subsystems of the entire tree, indexed by their absolute name.
+///
+/// Every subsystem stays registered until its [`Registration`] gets dropped.
+pub(crate) struct SubsystemRegistry {
+    entries: Mutex<HashMap<Arc<str>, Vec<Entry>>>,
+    // Notified whenever a subsystem got removed
+    changed: Notify,
+}
+
+impl SubsystemRegistry {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Registers multiple subsystems at once, while only locking the registry once.
+    pub(crate) fn register_many(
+        self: &Arc<Self>,
+        names: impl IntoIterator<Item = NameCell>,
+    ) -> Vec<Registration> {
+        // Read the names first; the registry must never be locked before a name
+        let names: Vec<(Arc<str>, NameCell)> = names
+            .into_iter()
+            .map(|name| {
+                let key = Arc::clone(&name.lock().unwrap());
+                (key, name)
+            })
+            .collect();
+
+        let mut entries = self.entries.lock().unwrap();
+        names
+            .into_iter()
+            .map(|(key, name)| {
+                let finished = CancellationToken::new();
+                entries.entry(key).or_default().push(Entry {
+                    name: Arc::clone(&name),
+                    finished: finished.clone(),
+                });
+                Registration {
+                    registry: Arc::downgrade(self),
+                    name,
+                    finished,
+                }
+            })
+            .collect()
+    }
+
+    /// Moves a renamed subsystem to its new name.
+    ///
+    /// Must be called while holding the lock of the name, to keep concurrent renames in order.
+    pub(crate) fn rename(&self, old: &str, new: Arc<str>, name: &NameCell) {
+        let mut entries = self.entries.lock().unwrap();
+        // Already gone if the subsystem finished in the meantime
+        if let Some(entry) = remove_entry(&mut entries, old, name) {
+            entries.entry(new).or_default().push(entry);
+        }
+    }
+
+    /// The tokens that get cancelled once the subsystems with the given name are finished.
+    pub(crate) fn finished_tokens(&self, name: &str) -> Vec<CancellationToken> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|entries| entries.iter().map(|e| e.finished.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// The names of all registered subsystems, sorted.
+    pub(crate) fn names(&self) -> Vec<Arc<str>> {
+        let mut names: Vec<Arc<str>> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|(name, entries)| std::iter::repeat(name).take(entries.len()).cloned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Gets notified whenever a subsystem got removed.
+    pub(crate) fn changed(&self) -> &Notify {
+        &self.changed
+    }
+}
+
+fn remove_entry(
+    entries: &mut HashMap<Arc<str>, Vec<Entry>>,
+    key: &str,
+    name: &NameCell,
+) -> Option<Entry> {
+    let list = entries.get_mut(key)?;
+    let position = list.iter().position(|e| Arc::ptr_eq(&e.name, name))?;
+    let entry = list.swap_remove(position);
+    if list.is_empty() {
+        entries.remove(key);
+    }
+    Some(entry)
+}
+
+/// Keeps a subsystem registered; removes it and marks it as finished when dropped.
+pub(crate) struct Registration {
+    registry: Weak<SubsystemRegistry>,
+    name: NameCell,
+    finished: CancellationToken,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            let key = Arc::clone(&self.name.lock().unwrap());
+            let mut entries = registry.entries.lock().unwrap();
+            if remove_entry(&mut entries, &key, &self.name).is_none() {
+                // Got renamed concurrently and not re-keyed yet
+                let old_key = entries
+                    .iter()
+                    .find(|(_, list)| list.iter().any(|e| Arc::ptr_eq(&e.name, &self.name)))
+                    .map(|(key, _)| Arc::clone(key));
+                if let Some(old_key) = old_key {
+                    remove_entry(&mut entries, &old_key, &self.name);
+                }
+            }
+            drop(entries);
+            registry.changed.notify_waiters();
+        }
+        self.finished.cancel();
+    }
+}
@@ -0,0 +1,12 @@
+/// The reason why [`SubsystemHandle::wait_shutdown_or_children_done()`](crate::SubsystemHandle::wait_shutdown_or_children_done)
+/// returned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WakeReason {
+    /// A shutdown of the subsystem was requested.
+    ///
+    /// The children might still be running.
+    ShutdownRequested,
+    /// All children of the subsystem finished on their own,
+    /// before a shutdown was requested.
+    ChildrenFinished,
+}
@@ -1,6 +1,7 @@
 use tokio::time::{sleep, Duration};
 use tokio_graceful_shutdown::{
-    errors::CancelledByShutdown, FutureExt, SubsystemBuilder, SubsystemHandle, Toplevel,
+    errors::{CancelledByShutdown, ShutdownCancelReason},
+    FutureExt, SubsystemBuilder, SubsystemHandle, Toplevel,
 };
 use tracing_test::traced_test;
 
@@ -75,3 +76,237 @@ async fn cancel_on_shutdown_cancels_on_shutdown() {
 
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_detailed_reports_global_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        async fn compute_value(subsys: &SubsystemHandle) -> i32 {
+            sleep(Duration::from_millis(100)).await;
+            subsys.request_shutdown();
+            sleep(Duration::from_millis(100)).await;
+            42
+        }
+
+        let value = compute_value(&subsys)
+            .cancel_on_shutdown_detailed(&subsys)
+            .await;
+
+        assert_eq!(value, Err(ShutdownCancelReason::Global));
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_detailed_reports_local_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        async fn compute_value(subsys: &SubsystemHandle) -> i32 {
+            sleep(Duration::from_millis(100)).await;
+            subsys.request_local_shutdown();
+            sleep(Duration::from_millis(100)).await;
+            42
+        }
+
+        let value = compute_value(&subsys)
+            .cancel_on_shutdown_detailed(&subsys)
+            .await;
+
+        assert_eq!(value, Err(ShutdownCancelReason::Local));
+
+        subsys.request_shutdown();
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_detailed_propagates_result() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let compute_value = async {
+            sleep(Duration::from_millis(10)).await;
+            42
+        };
+
+        let value = compute_value.cancel_on_shutdown_detailed(&subsys).await;
+
+        assert_eq!(value, Ok(42));
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn on_shutdown_switch_propagates_result() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let compute_value = async {
+            sleep(Duration::from_millis(10)).await;
+            42
+        };
+
+        let value = compute_value.on_shutdown_switch(&subsys, async { 0 }).await;
+
+        assert_eq!(value, 42);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn on_shutdown_switch_runs_cleanup_on_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let (dropped_sender, mut dropped_receiver) = tokio::sync::oneshot::channel::<()>();
+
+        let compute_value = async {
+            let _dropped_sender = dropped_sender;
+            sleep(Duration::from_millis(100)).await;
+            subsys.request_shutdown();
+            sleep(Duration::from_millis(100)).await;
+            42
+        };
+
+        let cleanup = async {
+            // The original future got dropped before the cleanup started
+            assert_eq!(
+                dropped_receiver.try_recv(),
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed)
+            );
+            sleep(Duration::from_millis(10)).await;
+            0
+        };
+
+        let value = compute_value.on_shutdown_switch(&subsys, cleanup).await;
+
+        assert_eq!(value, 0);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_or_propagates_result() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let compute_value = async {
+            sleep(Duration::from_millis(10)).await;
+            42
+        };
+
+        let value = compute_value.cancel_on_shutdown_or(&subsys, 0).await;
+
+        assert_eq!(value, 42);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_or_returns_default_on_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        async fn compute_value(subsys: &SubsystemHandle) -> i32 {
+            sleep(Duration::from_millis(100)).await;
+            subsys.request_shutdown();
+            sleep(Duration::from_millis(100)).await;
+            42
+        }
+
+        let value = compute_value(&subsys)
+            .cancel_on_shutdown_or(&subsys, 0)
+            .await;
+
+        assert_eq!(value, 0);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancel_on_shutdown_or_else_only_computes_default_on_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let compute_value = async {
+            sleep(Duration::from_millis(10)).await;
+            42
+        };
+
+        let value = compute_value
+            .cancel_on_shutdown_or_else(&subsys, || panic!("The default is not needed"))
+            .await;
+        assert_eq!(value, 42);
+
+        subsys.request_shutdown();
+        let value = async { 42 }.cancel_on_shutdown_or_else(&subsys, || 0).await;
+        assert_eq!(value, 0);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
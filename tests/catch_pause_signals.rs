@@ -0,0 +1,66 @@
+#![cfg(unix)]
+
+use tokio::time::{sleep, timeout, Duration};
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+use std::error::Error;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn pause_signals_pause_and_resume_subsystems() {
+    use nix::sys::signal::{self, Signal};
+    use nix::unistd::Pid;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        assert!(!subsys.is_paused());
+        subsys.on_resume().await;
+
+        sleep(Duration::from_millis(100)).await;
+        assert!(subsys.is_paused());
+        assert!(!subsys.is_shutdown_requested());
+        assert!(timeout(Duration::from_millis(50), subsys.on_resume())
+            .await
+            .is_err());
+
+        signal::kill(Pid::this(), Signal::SIGCONT).unwrap();
+        timeout(Duration::from_millis(100), subsys.on_resume())
+            .await
+            .unwrap();
+        assert!(!subsys.is_paused());
+
+        // A shutdown resumes the tree
+        signal::kill(Pid::this(), Signal::SIGTSTP).unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert!(subsys.is_paused());
+        subsys.request_shutdown();
+        timeout(Duration::from_millis(100), subsys.on_resume())
+            .await
+            .unwrap();
+
+        BoxedResult::Ok(())
+    };
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(50)).await;
+            signal::kill(Pid::this(), Signal::SIGTSTP).unwrap();
+        },
+        async {
+            let result = Toplevel::new(move |s| async move {
+                s.start(SubsystemBuilder::new("subsys", subsystem));
+            })
+            .catch_pause_signals()
+            .handle_shutdown_requests(Duration::from_millis(400))
+            .await;
+            assert!(result.is_ok());
+        },
+    );
+
+    assert!(logs_contain("Pausing subsystems."));
+    assert!(logs_contain("Resuming subsystems."));
+}
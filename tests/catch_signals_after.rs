@@ -0,0 +1,81 @@
+#![cfg(unix)]
+
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{EarlySignalPolicy, SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+pub mod common;
+use common::Event;
+
+use std::error::Error;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+// Both policies get tested in the same test, because signals are
+// process-wide and would interfere with concurrently running tests.
+#[tokio::test]
+#[traced_test]
+async fn catch_signals_after_gate() {
+    use nix::sys::signal::{self, Signal};
+    use nix::unistd::Pid;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    // Queue: the early signal gets delivered once the gate opens
+    let (gate_opened, set_gate_opened) = Event::create();
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .catch_signals_after(
+        async move {
+            sleep(Duration::from_millis(200)).await;
+            set_gate_opened();
+        },
+        EarlySignalPolicy::Queue,
+    );
+    let shutdown_token = toplevel._get_shutdown_token().clone();
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(100)).await;
+            signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+            sleep(Duration::from_millis(50)).await;
+            assert!(!shutdown_token.is_cancelled());
+        },
+        async {
+            let result = toplevel
+                .handle_shutdown_requests(Duration::from_millis(400))
+                .await;
+            assert!(gate_opened.get());
+            assert!(result.is_ok());
+        },
+    );
+
+    // Drop: the early signal gets ignored, only the later one counts
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .catch_signals_after(sleep(Duration::from_millis(200)), EarlySignalPolicy::Drop);
+    let shutdown_token = toplevel._get_shutdown_token().clone();
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(100)).await;
+            signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+            sleep(Duration::from_millis(200)).await;
+            assert!(!shutdown_token.is_cancelled());
+            signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+        },
+        async {
+            let result = toplevel
+                .handle_shutdown_requests(Duration::from_millis(400))
+                .await;
+            assert!(result.is_ok());
+        },
+    );
+}
@@ -0,0 +1,40 @@
+#![cfg(unix)]
+
+use tokio::time::Duration;
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+use std::error::Error;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn catch_signals_does_not_miss_immediate_signals() {
+    use nix::sys::signal::{self, Signal};
+    use nix::unistd::Pid;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .catch_signals();
+
+    // Send the signal before the signal handling task had a chance to run.
+    // If the handler was not registered yet, this would kill the process.
+    signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(500),
+        toplevel.handle_shutdown_requests(Duration::from_millis(100)),
+    )
+    .await;
+
+    assert!(matches!(result, Ok(Ok(()))));
+}
@@ -0,0 +1,833 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{
+    errors::GracefulShutdownError, ErrorAction, SubsystemBuilder, SubsystemHandle,
+    SubsystemOutcome, Toplevel,
+};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn catch_until_threshold_escalates_repeated_failures() {
+    use tokio_graceful_shutdown::ErrorAction;
+
+    let failing = |_subsys: SubsystemHandle| async move { BoxedResult::Err("failed".into()) };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let parent = subsys.start(
+            SubsystemBuilder::new("parent", move |s: SubsystemHandle| async move {
+                for _ in 0..3 {
+                    s.start(SubsystemBuilder::new("failing", failing))
+                        .finished()
+                        .await;
+                }
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            })
+            .on_failure(ErrorAction::CatchUntilThreshold {
+                count: 3,
+                window: Duration::from_secs(10),
+            }),
+        );
+        let result = parent.join().await;
+        assert!(result.is_err());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    // The first two errors got caught, the third one got forwarded
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/subsys/parent/failing");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn map_panic_converts_panic_into_error() {
+    use tokio_graceful_shutdown::errors::SubsystemError;
+
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        panic!("Oh no!");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("subsys", subsystem).map_panic(|name, payload| {
+                let message = payload.downcast_ref::<&str>().unwrap();
+                format!("'{name}' panicked: {message}").into()
+            }),
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        SubsystemError::Failed(name, e) => {
+            assert_eq!(name.as_ref(), "/subsys");
+            assert_eq!(e.to_string(), "'/subsys' panicked: Oh no!");
+        }
+        SubsystemError::Panicked(_) => panic!("Panic did not get converted"),
+        SubsystemError::Cancelled(_) => panic!("Subsystem should not be cancelled"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn panic_backtrace_gets_captured_if_enabled() {
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        panic!("Oh no!");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/subsys");
+
+    let backtrace = errors[0].backtrace();
+    if cfg!(feature = "capture-backtrace") {
+        assert_eq!(
+            backtrace.unwrap().status(),
+            std::backtrace::BacktraceStatus::Captured
+        );
+    } else {
+        assert!(backtrace.is_none());
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn map_error_converts_error() {
+    use tokio_graceful_shutdown::errors::SubsystemError;
+
+    let subsystem = |_subsys: SubsystemHandle| async move { Err(std::fmt::Error) };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("subsys", subsystem)
+                .map_error(|e| format!("Database failed: {e}").into()),
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        SubsystemError::Failed(name, e) => {
+            assert_eq!(name.as_ref(), "/subsys");
+            assert_eq!(
+                e.to_string(),
+                "Database failed: an error occurred when formatting an argument"
+            );
+        }
+        SubsystemError::Panicked(_) => panic!("Subsystem should not panic"),
+        SubsystemError::Cancelled(_) => panic!("Subsystem should not be cancelled"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn map_error_applies_to_restartable_subsystems() {
+    let subsystem = |_subsys: SubsystemHandle| async move { Err(std::fmt::Error) };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("subsys", subsystem)
+                .map_error(|_| "Mapped".into())
+                .restartable(),
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "Error in subsystem '/subsys'");
+    assert!(matches!(
+        &errors[0],
+        tokio_graceful_shutdown::errors::SubsystemError::Failed(_, e) if e.to_string() == "Mapped"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn log_failures_at_most_suppresses_excess_error_logs() {
+    let failing = |_subsys: SubsystemHandle| async move { BoxedResult::Err("Oh no!".into()) };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        for _ in 0..5 {
+            s.start(
+                SubsystemBuilder::new("failing", failing)
+                    .log_failures_at_most(2, Duration::from_millis(100)),
+            );
+        }
+
+        sleep(Duration::from_millis(150)).await;
+
+        s.start(
+            SubsystemBuilder::new("failing", failing)
+                .log_failures_at_most(2, Duration::from_millis(100)),
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    // All errors get reported, even if they were not logged
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => assert_eq!(errors.len(), 6),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    logs_assert(|lines: &[&str]| {
+        let count = |text: &str| lines.iter().filter(|line| line.contains(text)).count();
+        match (
+            count("Uncaught error from subsystem. subsystem=/failing"),
+            count("More errors from subsystem were suppressed. subsystem=/failing suppressed=3"),
+        ) {
+            (3, 1) => Ok(()),
+            other => Err(format!("Unexpected error logs: {other:?}")),
+        }
+    });
+}
+
+#[tokio::test]
+#[traced_test]
+async fn error_stream_leaves_unconsumed_errors_in_result() {
+    let failing = |_subsys: SubsystemHandle| async move { BoxedResult::Err("Oh no!".into()) };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("caught", failing)
+                .on_failure(tokio_graceful_shutdown::ErrorAction::CatchAndLocalShutdown),
+        );
+        s.start(SubsystemBuilder::new("failing", failing));
+        s.start(SubsystemBuilder::new("failing2", failing));
+    });
+
+    let errors = toplevel.error_stream();
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    // All errors got returned by the result, none are left in the stream
+    assert!(errors.recv().await.is_none());
+
+    let remaining = match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => errors,
+        other => panic!("Unexpected result: {other:?}"),
+    };
+    assert_eq!(remaining.len(), 2);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn concurrent_error_streams_receive_every_error_once() {
+    use tokio_stream::StreamExt as _;
+
+    let failing = |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        subsys.request_shutdown();
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let failing_on_shutdown = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("failing", failing));
+        s.start(SubsystemBuilder::new(
+            "failing_on_shutdown",
+            failing_on_shutdown,
+        ));
+    });
+
+    let errors1 = toplevel.error_stream();
+    let mut errors2 = toplevel.error_stream();
+
+    let (result, streamed1, streamed2) = tokio::time::timeout(Duration::from_millis(1000), async {
+        tokio::join!(
+            toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+            async {
+                let mut received = vec![];
+                while let Some(error) = errors1.recv().await {
+                    received.push(error.name().to_string());
+                }
+                received
+            },
+            async {
+                let mut received = vec![];
+                while let Some(error) = errors2.next().await {
+                    received.push(error.name().to_string());
+                }
+                received
+            }
+        )
+    })
+    .await
+    .expect("All streams should end once the shutdown is finished");
+
+    // Errors that arrive right before the end of the shutdown might not
+    // get streamed any more; those are contained in the result instead
+    let remaining = match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => errors,
+        other => panic!("Unexpected result: {other:?}"),
+    };
+    let mut received = [streamed1, streamed2].concat();
+    received.extend(remaining.iter().map(|error| error.name().to_string()));
+    received.sort();
+    assert_eq!(received, ["/failing", "/failing_on_shutdown"]);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn error_stream_consumed_errors_still_count_as_failure() {
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let slow_shutdown = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("failing", failing));
+        s.start(SubsystemBuilder::new("slow_shutdown", slow_shutdown));
+    });
+
+    let errors = toplevel.error_stream();
+
+    let (result, streamed) = tokio::join!(
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+        async {
+            let error = errors.recv().await.unwrap();
+            assert_eq!(error.name(), "/failing");
+            errors.recv().await
+        }
+    );
+
+    // The stream ends once the shutdown is finished
+    assert!(streamed.is_none());
+
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => assert!(errors.is_empty()),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn error_buffer_drops_oldest_errors() {
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        for i in 0..4 {
+            s.start(SubsystemBuilder::new(format!("failing{i}"), failing));
+        }
+    })
+    .with_error_buffer(2)
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => assert_eq!(errors.len(), 2),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    assert!(logs_contain(
+        "Error buffer is full, dropping the oldest error"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn join_detailed_reports_successes_and_failures() {
+    let succeeding = |_subsys: SubsystemHandle| async move { BoxedResult::Ok(()) };
+    let failing = |_subsys: SubsystemHandle| async move { BoxedResult::Err("Oh no!".into()) };
+
+    let batch = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("job1", succeeding));
+        subsys.start(SubsystemBuilder::new("job2", failing));
+        subsys.start(SubsystemBuilder::new("job3", succeeding));
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let nested = subsys.start(
+            SubsystemBuilder::new("batch", batch)
+                .on_failure(tokio_graceful_shutdown::ErrorAction::CatchAndLocalShutdown),
+        );
+
+        let mut report = nested.join_detailed().await;
+        report.succeeded.sort();
+
+        assert_eq!(
+            report
+                .succeeded
+                .iter()
+                .map(|n| n.as_ref())
+                .collect::<Vec<_>>(),
+            ["/subsys/batch", "/subsys/batch/job1", "/subsys/batch/job3"]
+        );
+        assert_eq!(
+            report.failed.iter().map(|n| n.as_ref()).collect::<Vec<_>>(),
+            ["/subsys/batch/job2"]
+        );
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].name(), "/subsys/batch/job2");
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn join_detailed_reports_outcomes_by_name() {
+    let succeeding = |_subsys: SubsystemHandle| async move { BoxedResult::Ok(()) };
+    let failing = |_subsys: SubsystemHandle| async move { BoxedResult::Err("Oh no!".into()) };
+    let panicking = |_subsys: SubsystemHandle| async move {
+        panic!("Panic!");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let batch = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("job1", succeeding));
+        subsys.start(SubsystemBuilder::new("job2", failing));
+        subsys.start(SubsystemBuilder::new("job3", panicking));
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let nested = subsys.start(
+            SubsystemBuilder::new("batch", batch)
+                .on_failure(ErrorAction::CatchAndLocalShutdown)
+                .on_panic(ErrorAction::CatchAndLocalShutdown),
+        );
+
+        let report = nested.join_detailed().await;
+
+        assert_eq!(
+            report.outcome_of("/subsys/batch"),
+            Some(SubsystemOutcome::Succeeded)
+        );
+        assert_eq!(
+            report.outcome_of("/subsys/batch/job1"),
+            Some(SubsystemOutcome::Succeeded)
+        );
+        assert_eq!(
+            report.outcome_of("/subsys/batch/job2"),
+            Some(SubsystemOutcome::Failed)
+        );
+        assert_eq!(
+            report.outcome_of("/subsys/batch/job3"),
+            Some(SubsystemOutcome::Panicked)
+        );
+        assert_eq!(report.outcome_of("/subsys/batch/job4"), None);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test(flavor = "current_thread")]
+#[traced_test]
+async fn no_panic_isolation_runs_subsystem() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested", nested).no_panic_isolation());
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem).no_panic_isolation());
+
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(nested_finished.get());
+    assert!(!logs_contain("Subsystem cancelled"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+#[traced_test]
+async fn no_panic_isolation_reports_panics() {
+    use tokio_graceful_shutdown::errors::SubsystemError;
+
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested", nested));
+        sleep(Duration::from_millis(50)).await;
+        panic!("Oh no!");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem).no_panic_isolation());
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        SubsystemError::Panicked(name) => assert_eq!(name.as_ref(), "/subsys"),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    logs_assert(|lines: &[&str]| {
+        match lines
+            .iter()
+            .find(|line| line.ends_with("Subsystem cancelled. subsystem=/subsys"))
+        {
+            Some(line) => Err(format!("Panicking subsystem reported as cancelled: {line}")),
+            None => Ok(()),
+        }
+    });
+}
+
+#[tokio::test]
+#[traced_test]
+async fn no_panic_isolation_subsystem_gets_cancelled() {
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_secs(10)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem).no_panic_isolation());
+
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(100))
+        .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+
+    // The runners of cancelled subsystems get aborted asynchronously
+    sleep(Duration::from_millis(50)).await;
+    assert!(logs_contain("Subsystem cancelled. subsystem=/subsys"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn failure_filter_ignores_benign_errors() {
+    let benign =
+        |_subsys: SubsystemHandle| async move { BoxedResult::Err("Connection closed".into()) };
+
+    let failing = |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(100)).await;
+        subsys.request_shutdown();
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        let is_failure = |e: &BoxedError| e.to_string() != "Connection closed";
+        s.start(SubsystemBuilder::new("benign", benign).failure_filter(is_failure));
+        s.start(SubsystemBuilder::new("failing", failing).failure_filter(is_failure));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/failing");
+    assert!(logs_contain(
+        "Subsystem returned an error that is not a failure. subsystem=/benign error=Connection closed"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn error_severity_gets_attached_to_failures() {
+    use tokio_graceful_shutdown::Severity;
+
+    let failing = |_subsys: SubsystemHandle| async move {
+        BoxedResult::Err(String::from("Sink unavailable").into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("sink", failing).error_severity(Severity::Warning));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    let error = result.unwrap_err();
+
+    match error.subsystem_error("/sink") {
+        Some(tokio_graceful_shutdown::errors::SubsystemError::Failed(_, failure)) => {
+            assert_eq!(failure.severity(), Severity::Warning)
+        }
+        _ => panic!("Subsystem should have failed"),
+    }
+    assert_eq!(error.max_severity(), Severity::Warning);
+    assert_eq!(error.exit_code(), 1);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn error_severity_gets_attached_to_panics() {
+    use tokio_graceful_shutdown::Severity;
+
+    let panicking = |_subsys: SubsystemHandle| async move {
+        panic!("Engine broke");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("engine", panicking));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    let error = result.unwrap_err();
+    assert_eq!(error.max_severity(), Severity::Error);
+    assert_eq!(error.exit_code(), 1);
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("engine", panicking).error_severity(Severity::Critical));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    let error = result.unwrap_err();
+    assert_eq!(error.max_severity(), Severity::Critical);
+    assert_eq!(error.exit_code(), 3);
+
+    match &error.get_subsystem_errors()[0] {
+        tokio_graceful_shutdown::errors::SubsystemError::Panicked(panic) => {
+            assert_eq!(panic.severity(), Severity::Critical)
+        }
+        _ => panic!("Subsystem should have panicked"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn panic_in_root_subsystem_tears_down_started_children() {
+    let child_shut_down = Arc::new(AtomicBool::new(false));
+
+    let child = {
+        let child_shut_down = Arc::clone(&child_shut_down);
+        move |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            child_shut_down.store(true, Ordering::Release);
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("child", child));
+        panic!("Root subsystem failed during startup");
+    });
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(1000),
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+    )
+    .await
+    .expect("The half-built tree did not shut down");
+
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(
+                &errors[0],
+                tokio_graceful_shutdown::errors::SubsystemError::Panicked(name) if name.as_ref() == "/"
+            ));
+        }
+        _ => panic!("Expected the root panic to be reported"),
+    }
+    assert!(child_shut_down.load(Ordering::Acquire));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn outcome_reports_how_subsystems_ended() {
+    use tokio_graceful_shutdown::{ErrorAction, NestedSubsystem, SubsystemOutcome};
+
+    let (stuck_tx, stuck_rx) = tokio::sync::oneshot::channel::<NestedSubsystem>();
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let start = |name: &'static str, result: BoxedResult| {
+            subsys.start(
+                SubsystemBuilder::new(name, move |_subsys: SubsystemHandle| async move {
+                    sleep(Duration::from_millis(50)).await;
+                    result
+                })
+                .on_failure(ErrorAction::CatchAndLocalShutdown),
+            )
+        };
+        let succeeding = start("succeeding", Ok(()));
+        let failing = start("failing", Err(String::from("Failed").into()));
+        let panicking = subsys.start(
+            SubsystemBuilder::new("panicking", |_subsys: SubsystemHandle| async {
+                sleep(Duration::from_millis(50)).await;
+                panic!("Panicked");
+                #[allow(unreachable_code)]
+                BoxedResult::Ok(())
+            })
+            .on_panic(ErrorAction::CatchAndLocalShutdown),
+        );
+        let stuck = subsys.start(SubsystemBuilder::new(
+            "stuck",
+            |_subsys: SubsystemHandle| async {
+                sleep(Duration::from_secs(10)).await;
+                BoxedResult::Ok(())
+            },
+        ));
+
+        assert_eq!(succeeding.outcome(), None);
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(succeeding.outcome(), Some(SubsystemOutcome::Succeeded));
+        assert_eq!(failing.outcome(), Some(SubsystemOutcome::Failed));
+        assert_eq!(panicking.outcome(), Some(SubsystemOutcome::Panicked));
+        assert_eq!(stuck.outcome(), None);
+
+        stuck_tx.send(stuck).ok().unwrap();
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+    assert!(result.is_err());
+
+    let stuck = stuck_rx.await.unwrap();
+    stuck.finished().await;
+    assert_eq!(stuck.outcome(), Some(SubsystemOutcome::Cancelled));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn total_errors_seen_counts_consumed_errors() {
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let slow_shutdown = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("caught", failing)
+                .on_failure(tokio_graceful_shutdown::ErrorAction::CatchAndLocalShutdown),
+        );
+        s.start(SubsystemBuilder::new("failing", failing));
+        s.start(SubsystemBuilder::new("slow_shutdown", slow_shutdown));
+    });
+
+    let total_errors = toplevel.total_errors_seen();
+    let errors = toplevel.error_stream();
+
+    let (result, streamed) = tokio::join!(
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+        async { errors.recv().await.unwrap() }
+    );
+    assert_eq!(streamed.name(), "/failing");
+
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => assert_eq!(errors.len(), 1),
+        other => panic!("Unexpected result: {other:?}"),
+    }
+
+    // The consumed error still counts, the caught one doesn't
+    assert_eq!(total_errors.get(), 2);
+}
@@ -563,6 +563,38 @@ async fn shutdown_triggers_if_no_task_exists() {
     .unwrap();
 }
 
+#[tokio::test]
+#[traced_test]
+async fn idle_toplevel_waits_for_shutdown_request() {
+    let toplevel = Toplevel::<BoxedError>::new_idle();
+    let shutdown_token = toplevel._get_shutdown_token().clone();
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(100),
+        toplevel.handle_shutdown_requests(Duration::from_millis(100)),
+    )
+    .await;
+    assert!(result.is_err());
+    // Dropping the unfinished future requests a shutdown
+    assert!(shutdown_token.is_cancelled());
+
+    let toplevel = Toplevel::<BoxedError>::new_idle();
+    let shutdown_token = toplevel._get_shutdown_token().clone();
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(100)).await;
+            shutdown_token.cancel();
+        },
+        async {
+            let result = toplevel
+                .handle_shutdown_requests(Duration::from_millis(100))
+                .await;
+            assert!(result.is_ok());
+        },
+    );
+}
+
 #[tokio::test]
 #[traced_test]
 async fn destroying_toplevel_cancels_nested_toplevel_subsystems() {
@@ -865,6 +897,57 @@ async fn subsystem_errors_get_propagated_to_user_when_timeout() {
     }
 }
 
+#[tokio::test]
+#[traced_test]
+async fn cancelled_subsystems_get_reported_when_timeout() {
+    let nested_subsystem1 = |_: SubsystemHandle| async {
+        sleep(Duration::from_millis(10000)).await;
+        BoxedResult::Ok(())
+    };
+
+    let nested_subsystem2 = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested1", nested_subsystem1));
+        subsys.start(SubsystemBuilder::new("nested2", nested_subsystem2));
+
+        sleep(Duration::from_millis(100)).await;
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .report_cancelled_subsystems();
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(200))
+        .await;
+
+    if let Err(GracefulShutdownError::ShutdownTimeout(errors)) = result {
+        let names = errors
+            .iter()
+            .map(|el| {
+                assert!(matches!(el, SubsystemError::Cancelled(_)));
+                el.name()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["/subsys/nested1"]);
+    } else {
+        panic!("Incorrect return value!");
+    }
+
+    // The runners of cancelled subsystems get aborted asynchronously
+    sleep(Duration::from_millis(50)).await;
+    assert!(logs_contain(
+        "Subsystem cancelled. subsystem=/subsys/nested1 reason=\"shutdown timeout\""
+    ));
+}
+
 #[tokio::test]
 #[traced_test]
 async fn is_shutdown_requested_works_as_intended() {
@@ -942,3 +1025,59 @@ async fn access_name_from_within_subsystem() {
     .await
     .unwrap();
 }
+
+#[tokio::test]
+#[traced_test]
+async fn toplevel_returns_output_of_root_subsystem() {
+    let result = Toplevel::<BoxedError, _>::new_with_output(|s| async move {
+        let nested = s.start(SubsystemBuilder::new("nested", |_| async {
+            sleep(Duration::from_millis(20)).await;
+            BoxedResult::Ok(())
+        }));
+        nested.join().await.unwrap();
+        42
+    })
+    .handle_shutdown_requests_with_output(Duration::from_millis(100))
+    .await;
+    assert_eq!(result.unwrap(), 42);
+
+    let result = Toplevel::<BoxedError, _>::new_with_output(|s| async move {
+        s.start(SubsystemBuilder::new("failing", |_| async {
+            BoxedResult::Err(anyhow!("failed").into())
+        }));
+        s.on_shutdown_requested().await;
+        42
+    })
+    .handle_shutdown_requests_with_output(Duration::from_millis(100))
+    .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::SubsystemsFailed(_))
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_deadline_causes_timeout() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(200)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    // The deadline is absolute, so the time until the shutdown request counts as well
+    let result = toplevel
+        .handle_shutdown_requests_until(tokio::time::Instant::now() + Duration::from_millis(250))
+        .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+}
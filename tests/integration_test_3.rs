@@ -0,0 +1,817 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{
+    errors::GracefulShutdownError, SubsystemBuilder, SubsystemHandle, Toplevel,
+};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{atomic::Ordering, Arc, Mutex},
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn sampler_publishes_samples_while_running() {
+    use std::sync::atomic::AtomicU32;
+    use tokio_graceful_shutdown::Sample;
+
+    let counter = Arc::new(AtomicU32::new(0));
+
+    let subsystem = {
+        let counter = Arc::clone(&counter);
+        move |subsys: SubsystemHandle| async move {
+            counter.fetch_add(1, Ordering::Relaxed);
+            sleep(Duration::from_millis(125)).await;
+            subsys.request_shutdown();
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("sampled", subsystem)
+                .with_sampler(Duration::from_millis(50), move || {
+                    Sample::new().metric("counter", counter.load(Ordering::Relaxed) as f64)
+                }),
+        );
+    });
+    let mut samples = toplevel.samples();
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let mut received = vec![];
+    while let Ok(sample) = samples.try_recv() {
+        assert_eq!(sample.name.as_ref(), "/sampled");
+        received.push(sample.sample.get("counter"));
+    }
+
+    // Sampled right after the start, and then every 50ms until the subsystem returned
+    assert_eq!(received.len(), 3);
+    assert!(received.iter().all(|value| value.is_some()));
+}
+
+#[test]
+#[should_panic(expected = "The sampling interval must be non-zero")]
+fn sampler_rejects_zero_interval() {
+    use tokio_graceful_shutdown::Sample;
+
+    let subsystem = |_subsys: SubsystemHandle| async move { BoxedResult::Ok(()) };
+
+    let _ = SubsystemBuilder::new("sampled", subsystem).with_sampler(Duration::ZERO, Sample::new);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn scoped_subsystem_borrows_handle() {
+    async fn scoped(subsys: &SubsystemHandle) -> BoxedResult {
+        sleep(Duration::from_millis(20)).await;
+        subsys.request_shutdown();
+        subsys.on_shutdown_requested().await;
+        Err("scoped failed".into())
+    }
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new_scoped("scoped", scoped));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].name(), "/scoped");
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_progress_gets_logged() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(250)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("slow", subsystem));
+        sleep(Duration::from_millis(20)).await;
+        s.request_shutdown();
+    })
+    .with_shutdown_progress_interval(Duration::from_millis(100));
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    assert!(logs_contain("Shutdown in progress"));
+    assert!(logs_contain("/slow"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_progress_does_not_get_logged_by_default() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(150)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("slow", subsystem));
+        sleep(Duration::from_millis(20)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    assert!(!logs_contain("Shutdown in progress"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn spawn_supervised_restarts_on_failure() {
+    use std::sync::atomic::AtomicU32;
+    use tokio_graceful_shutdown::RestartPolicy;
+
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let worker = {
+        let attempts = Arc::clone(&attempts);
+        move |subsys: SubsystemHandle| async move {
+            if attempts.fetch_add(1, Ordering::Relaxed) < 2 {
+                return BoxedResult::Err("worker failed".into());
+            }
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let worker = s.spawn_supervised("worker", worker, RestartPolicy::new());
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(worker.restart_count(), 2);
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert_eq!(attempts.load(Ordering::Relaxed), 3);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn spawn_supervised_forwards_failure_once_out_of_restarts() {
+    use tokio_graceful_shutdown::RestartPolicy;
+
+    let worker = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(10)).await;
+        BoxedResult::Err("worker failed".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let worker = s.spawn_supervised(
+            "worker",
+            worker,
+            RestartPolicy::new()
+                .max_restarts(2)
+                .delay(Duration::from_millis(10)),
+        );
+        s.on_shutdown_requested().await;
+        assert_eq!(worker.restart_count(), 2);
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].name(), "/worker");
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+}
+
+// Multi-threaded, so the root subsystem starts running right away
+#[tokio::test(flavor = "multi_thread")]
+#[traced_test]
+async fn custom_name_separator_and_root_name() {
+    let names = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let nested = {
+        let names = Arc::clone(&names);
+        move |subsys: SubsystemHandle| async move {
+            names.lock().unwrap().push(subsys.name());
+            subsys.set_name("renamed");
+            names.lock().unwrap().push(subsys.name());
+            BoxedResult::Ok(())
+        }
+    };
+    let server = {
+        let names = Arc::clone(&names);
+        move |subsys: SubsystemHandle| async move {
+            names.lock().unwrap().push(subsys.name());
+            subsys.start(SubsystemBuilder::new("nested", nested));
+            BoxedResult::Ok(())
+        }
+    };
+
+    let root = {
+        let names = Arc::clone(&names);
+        move |s: SubsystemHandle| async move {
+            names.lock().unwrap().push(s.name());
+            s.start(SubsystemBuilder::new("server", server));
+        }
+    };
+
+    let result = Toplevel::builder()
+        .with_name_separator('.')
+        .with_root_name("app")
+        .build(root)
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let names = names.lock().unwrap();
+    let names: Vec<_> = names.iter().map(|name| name.as_ref()).collect();
+    assert_eq!(
+        names,
+        [
+            "app",
+            "app.server",
+            "app.server.nested",
+            "app.server.renamed"
+        ]
+    );
+}
+
+#[tokio::test]
+#[traced_test]
+async fn custom_name_separator_keeps_root_unprefixed() {
+    let name = Arc::new(std::sync::Mutex::new(None));
+
+    let subsystem = {
+        let name = Arc::clone(&name);
+        move |subsys: SubsystemHandle| async move {
+            *name.lock().unwrap() = Some(subsys.name());
+            BoxedResult::Ok(())
+        }
+    };
+
+    let result = Toplevel::builder()
+        .with_name_separator('.')
+        .build(move |s| async move {
+            s.start(SubsystemBuilder::new("subsys", subsystem));
+        })
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    assert_eq!(name.lock().unwrap().as_deref(), Some(".subsys"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn connection_set_drains_connections() {
+    use std::sync::atomic::AtomicU32;
+
+    let served = Arc::new(AtomicU32::new(0));
+
+    let subsystem = {
+        let served = Arc::clone(&served);
+        move |subsys: SubsystemHandle| async move {
+            let connections = subsys.connection_set(Duration::from_millis(200));
+            for _ in 0..2 {
+                let served = Arc::clone(&served);
+                assert!(connections.spawn(async move {
+                    sleep(Duration::from_millis(50)).await;
+                    served.fetch_add(1, Ordering::Relaxed);
+                }));
+            }
+            assert_eq!(connections.len(), 2);
+
+            subsys.request_shutdown();
+            assert!(!connections.spawn(async {}));
+
+            connections.drain().await;
+            assert!(connections.is_empty());
+            BoxedResult::Ok(())
+        }
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("server", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+
+    assert_eq!(served.load(Ordering::Relaxed), 2);
+    assert!(!logs_contain("grace period"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn connection_set_cancels_stragglers() {
+    use std::sync::atomic::AtomicBool;
+
+    let finished = Arc::new(AtomicBool::new(false));
+
+    let subsystem = {
+        let finished = Arc::clone(&finished);
+        move |subsys: SubsystemHandle| async move {
+            let connections = subsys.connection_set(Duration::from_millis(50));
+            connections.spawn(async move {
+                sleep(Duration::from_millis(1000)).await;
+                finished.store(true, Ordering::Relaxed);
+            });
+
+            subsys.on_shutdown_requested().await;
+            connections.drain().await;
+            assert!(connections.is_empty());
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("server", subsystem));
+        sleep(Duration::from_millis(20)).await;
+        s.request_shutdown();
+    });
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(300),
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+    )
+    .await
+    .unwrap();
+    assert!(result.is_ok());
+
+    assert!(!finished.load(Ordering::Relaxed));
+    assert!(logs_contain("grace period"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn exit_when_idle_tolerates_transient_idleness() {
+    let job = |subsys: SubsystemHandle| async move {
+        tokio::select! {
+            _ = sleep(Duration::from_millis(100)) => (),
+            _ = subsys.on_shutdown_requested() => panic!("Job got interrupted"),
+        }
+        BoxedResult::Ok(())
+    };
+
+    let start = tokio::time::Instant::now();
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("job1", job));
+        sleep(Duration::from_millis(150)).await;
+        // The tree was idle for 50ms, which is shorter than the idle timeout
+        s.start(SubsystemBuilder::new("job2", job));
+        s.on_shutdown_requested().await;
+    })
+    .exit_when_idle(Duration::from_millis(200))
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(700), "{elapsed:?}");
+    assert!(logs_contain(
+        "No subsystems running, shutting down ... idle_timeout=200ms"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn subsystem_without_shutdown_authority_cannot_shut_down_tree() {
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        // Children inherit the restriction
+        subsys.start(SubsystemBuilder::new("nested", nested));
+        subsys.request_shutdown();
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("restricted", subsystem).without_shutdown_authority());
+        sleep(Duration::from_millis(100)).await;
+        assert!(!s.is_shutdown_requested());
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    assert!(logs_contain(
+        "Ignoring shutdown request of subsystem without shutdown authority."
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn wait_for_subsystem_waits_for_cousin() {
+    let database_finished = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    let database = {
+        let database_finished = Arc::clone(&database_finished);
+        move |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            sleep(Duration::from_millis(200)).await;
+            database_finished.store(true, Ordering::Release);
+            BoxedResult::Ok(())
+        }
+    };
+    let storage = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("database", database));
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+    let backup = {
+        let database_finished = Arc::clone(&database_finished);
+        move |subsys: SubsystemHandle| async move {
+            sleep(Duration::from_millis(50)).await;
+            subsys.request_shutdown();
+            subsys.wait_for_subsystem("/storage/database").await?;
+            assert!(database_finished.load(Ordering::Acquire));
+            let missing = subsys
+                .wait_for_subsystem("/storage/database")
+                .await
+                .unwrap_err();
+            assert_eq!(missing.name.as_ref(), "/storage/database");
+            BoxedResult::Ok(())
+        }
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("storage", storage));
+        s.start(SubsystemBuilder::new("backup", backup));
+    })
+    .handle_shutdown_requests(Duration::from_millis(500))
+    .await;
+    assert!(result.is_ok(), "{result:?}");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn wait_for_subsystem_finds_renamed_subsystem() {
+    let worker = |subsys: SubsystemHandle| async move {
+        subsys.set_name("job-42");
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+    let observer = move |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        assert!(subsys.wait_for_subsystem("/worker").await.is_err());
+        subsys.request_shutdown();
+        subsys.wait_for_subsystem("/job-42").await?;
+        assert!(subsys.wait_for_subsystem("/job-42").await.is_err());
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("worker", worker));
+        s.start(SubsystemBuilder::new("observer", observer));
+    })
+    .handle_shutdown_requests(Duration::from_millis(500))
+    .await;
+    assert!(result.is_ok(), "{result:?}");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn wait_for_subsystem_fails_for_unknown_name() {
+    let (subsys, _errors) = SubsystemHandle::<BoxedError>::new_root();
+    let result = subsys.wait_for_subsystem("/does/not/exist").await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn local_subsystem_runs_non_send_futures() {
+    use std::{cell::Cell, rc::Rc};
+
+    let nested_started = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+    let nested = {
+        let nested_started = Arc::clone(&nested_started);
+        move |subsys: SubsystemHandle| async move {
+            nested_started.store(true, Ordering::Release);
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+
+    let local = move |subsys: SubsystemHandle| async move {
+        let counter = Rc::new(Cell::new(0));
+        tokio::task::spawn_local({
+            let counter = Rc::clone(&counter);
+            async move { counter.set(counter.get() + 1) }
+        })
+        .await
+        .unwrap();
+
+        subsys.start(SubsystemBuilder::new("nested", nested));
+        subsys.on_shutdown_requested().await;
+
+        assert_eq!(counter.get(), 1);
+        BoxedResult::Err("local failure".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start_local(SubsystemBuilder::new("local", local));
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(nested_started.load(Ordering::Acquire));
+
+    let Err(GracefulShutdownError::SubsystemsFailed(errors)) = result else {
+        panic!("Unexpected result: {result:?}");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/local");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn local_subsystems_of_a_handle_share_their_thread() {
+    let threads = Arc::new(Mutex::new(Vec::new()));
+
+    let local = |threads: Arc<Mutex<Vec<std::thread::ThreadId>>>| {
+        move |subsys: SubsystemHandle| async move {
+            let _not_send = std::rc::Rc::new(());
+            threads.lock().unwrap().push(std::thread::current().id());
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new({
+        let threads = Arc::clone(&threads);
+        move |s| async move {
+            s.start_local(SubsystemBuilder::new("local1", local(Arc::clone(&threads))));
+            s.start_local(SubsystemBuilder::new("local2", local(threads)));
+            sleep(Duration::from_millis(100)).await;
+            s.request_shutdown();
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 2);
+    assert_eq!(threads[0], threads[1]);
+    assert_ne!(threads[0], std::thread::current().id());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn local_subsystem_panics_get_reported() {
+    let local = |_subsys: SubsystemHandle| async move {
+        let _not_send = std::rc::Rc::new(());
+        sleep(Duration::from_millis(10)).await;
+        panic!("local panic");
+        #[allow(unreachable_code)]
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start_local(SubsystemBuilder::new("local", local));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+
+    let Err(GracefulShutdownError::SubsystemsFailed(errors)) = result else {
+        panic!("Unexpected result: {result:?}");
+    };
+    assert!(matches!(
+        errors[0],
+        tokio_graceful_shutdown::errors::SubsystemError::Panicked(_)
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn with_shutdown_token_overrides_shutdown_for_scope() {
+    use tokio_util::sync::CancellationToken;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let token = CancellationToken::new();
+        let nested = |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Err("nested failure".into())
+        };
+
+        subsys
+            .with_shutdown_token(token.clone(), |scoped| async move {
+                assert!(!scoped.is_shutdown_requested());
+                let nested = scoped.start(SubsystemBuilder::new("nested", nested));
+                sleep(Duration::from_millis(100)).await;
+                assert!(nested.outcome().is_none());
+
+                token.cancel();
+                scoped.on_shutdown_requested().await;
+                scoped.wait_for_children().await;
+                assert!(nested.outcome().is_some());
+            })
+            .await;
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+
+    // The errors of the scope belong to the subsystem
+    let Err(GracefulShutdownError::SubsystemsFailed(errors)) = result else {
+        panic!("Unexpected result: {result:?}");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/subsys/nested");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn with_shutdown_token_scope_receives_global_shutdown() {
+    use tokio_util::sync::CancellationToken;
+
+    let (nested_stopped, set_nested_stopped) = Event::create();
+
+    let nested = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_stopped();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        // Never cancelled
+        let token = CancellationToken::new();
+
+        subsys
+            .with_shutdown_token(token, |scoped| async move {
+                scoped.start(SubsystemBuilder::new("nested", nested));
+                scoped.on_shutdown_requested().await;
+                scoped.wait_for_children().await;
+            })
+            .await;
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(nested_stopped.get());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn on_idle_gets_invoked_whenever_tree_becomes_idle() {
+    use std::sync::atomic::AtomicU32;
+
+    let idle_count = Arc::new(AtomicU32::new(0));
+
+    let job = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new({
+        let idle_count = Arc::clone(&idle_count);
+        move |s| async move {
+            s.start(SubsystemBuilder::new("job1", job));
+            s.start(SubsystemBuilder::new("job2", job));
+            sleep(Duration::from_millis(100)).await;
+            assert_eq!(idle_count.load(Ordering::Acquire), 1);
+
+            // New work makes the tree busy again
+            s.start(SubsystemBuilder::new("job3", job));
+            sleep(Duration::from_millis(100)).await;
+            assert_eq!(idle_count.load(Ordering::Acquire), 2);
+
+            s.start(SubsystemBuilder::new("job4", job));
+            s.request_shutdown();
+        }
+    })
+    .on_idle({
+        let idle_count = Arc::clone(&idle_count);
+        move || {
+            let idle_count = Arc::clone(&idle_count);
+            async move {
+                idle_count.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    // Finishing during the shutdown does not count as idle
+    assert_eq!(idle_count.load(Ordering::Acquire), 2);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn idle_watchers_follow_reloads() {
+    use std::sync::atomic::AtomicU32;
+
+    let idle_count = Arc::new(AtomicU32::new(0));
+
+    let mut toplevel = Toplevel::new(|s| async move {
+        s.start(SubsystemBuilder::new(
+            "old",
+            |subsys: SubsystemHandle| async move {
+                subsys.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+        s.on_shutdown_requested().await;
+    })
+    .on_idle({
+        let idle_count = Arc::clone(&idle_count);
+        move || {
+            let idle_count = Arc::clone(&idle_count);
+            async move {
+                idle_count.fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    })
+    .exit_when_idle(Duration::from_millis(100));
+
+    sleep(Duration::from_millis(50)).await;
+    let result = toplevel
+        .reload(
+            |s| async move {
+                s.start(SubsystemBuilder::new(
+                    "new",
+                    |_subsys: SubsystemHandle| async move {
+                        sleep(Duration::from_millis(50)).await;
+                        BoxedResult::Ok(())
+                    },
+                ));
+                s.on_shutdown_requested().await;
+            },
+            Duration::from_millis(400),
+        )
+        .await;
+    assert!(result.is_ok());
+    // The shutdown of the old tree does not count as idle
+    assert_eq!(idle_count.load(Ordering::Acquire), 0);
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(1000),
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+    )
+    .await
+    .expect("The new tree never became idle");
+    assert!(result.is_ok());
+    assert_eq!(idle_count.load(Ordering::Acquire), 1);
+    assert!(logs_contain("shutting down"));
+}
@@ -0,0 +1,63 @@
+#![cfg(unix)]
+
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+use std::error::Error;
+
+pub mod common;
+use common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn paused_signal_handling_delays_shutdown() {
+    use nix::sys::signal::{self, Signal};
+    use nix::unistd::Pid;
+
+    let (critical_finished, set_critical_finished) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let signal_handler_control = subsys.signal_handler_control();
+
+        signal_handler_control.pause();
+        assert!(signal_handler_control.is_paused());
+
+        // The signal arrives during the critical operation
+        sleep(Duration::from_millis(300)).await;
+        assert!(!subsys.is_shutdown_requested());
+        set_critical_finished();
+
+        signal_handler_control.resume();
+
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(100)).await;
+
+            // Send SIGTERM to ourselves.
+            signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+        },
+        async {
+            let result = Toplevel::new(move |s| async move {
+                s.start(SubsystemBuilder::new("subsys", subsystem));
+            })
+            .catch_signals()
+            .handle_shutdown_requests(Duration::from_millis(400))
+            .await;
+            assert!(result.is_ok());
+            assert!(critical_finished.get());
+        },
+    );
+
+    assert!(logs_contain(
+        "Signal handling is paused, delaying the signal until it gets resumed. signal=Terminate"
+    ));
+}
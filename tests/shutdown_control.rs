@@ -0,0 +1,1073 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{
+    errors::GracefulShutdownError, LeakPolicy, ShutdownState, SubsystemBuilder, SubsystemHandle,
+    Toplevel,
+};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn leak_subsystem_handle_log_and_cancel() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let subsys_ext: Arc<Mutex<Option<SubsystemHandle>>> = Default::default();
+    let subsys_ext2 = Arc::clone(&subsys_ext);
+
+    let nested = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested", nested));
+
+        *subsys_ext2.lock().unwrap() = Some(subsys);
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let leaking = s.start(SubsystemBuilder::new("subsys", subsystem));
+        leaking.join().await.unwrap();
+    })
+    .on_handle_leak(LeakPolicy::LogAndCancel);
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(nested_finished.get());
+    assert!(logs_contain(
+        "The SubsystemHandle object must not be leaked out of the subsystem! Treating the subsystem as finished. subsystem=/subsys"
+    ));
+
+    // The handle is still usable, but its subsystem is gone
+    assert!(subsys_ext
+        .lock()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .is_shutdown_requested());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn request_shutdown_if_last() {
+    let (worker1_failed, fail_worker1) = Event::create();
+    let (worker2_failed, fail_worker2) = Event::create();
+
+    let worker = |failed: Event| {
+        move |subsys: SubsystemHandle| async move {
+            tokio::select! {
+                _ = subsys.on_shutdown_requested() => (),
+                _ = failed.wait() => {
+                    subsys.request_shutdown_if_last();
+                }
+            }
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("worker1", worker(worker1_failed)));
+        s.start(SubsystemBuilder::new("worker2", worker(worker2_failed)));
+
+        // The first failing worker only stops itself
+        fail_worker1();
+        sleep(Duration::from_millis(100)).await;
+        assert!(!s.is_shutdown_requested());
+        assert_eq!(s.child_count(), 1);
+
+        // The last one stops the program
+        fail_worker2();
+        s.on_shutdown_requested().await;
+    });
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(1000),
+        toplevel.handle_shutdown_requests(Duration::from_millis(400)),
+    )
+    .await
+    .unwrap();
+    assert!(result.is_ok());
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+#[traced_test]
+async fn axum_shutdown_signal() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let (shutdown_signal_finished, set_shutdown_signal_finished) = Event::create();
+
+        let shutdown_signal = subsys.axum_shutdown_signal();
+        drop(subsys);
+
+        tokio::spawn(async move {
+            shutdown_signal.await;
+            set_shutdown_signal_finished();
+        });
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!shutdown_signal_finished.get());
+        shutdown_signal_finished.wait().await;
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_deadline_is_set_during_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        assert!(subsys.shutdown_deadline().is_none());
+        assert!(subsys.time_until_shutdown_timeout().is_none());
+
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(20)).await;
+
+        assert!(subsys.shutdown_deadline().is_some());
+        let remaining = subsys.time_until_shutdown_timeout().unwrap();
+        assert!(remaining <= Duration::from_millis(400));
+        assert!(remaining > Duration::from_millis(200));
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_detached_does_not_propagate_shutdown() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let detached_subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let nested = subsys.start_detached("detached", detached_subsystem);
+        assert_eq!(&*subsys.name(), "/subsys");
+
+        subsys.on_shutdown_requested().await;
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(!nested_finished.get());
+
+        nested.initiate_shutdown();
+        nested.join().await?;
+        assert!(nested_finished.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_detached_children_in_reverse_order() {
+    use tokio_graceful_shutdown::ShutdownOrder;
+
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+
+    let detached_subsystem = |stopped: Arc<Mutex<Vec<Arc<str>>>>| {
+        move |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            sleep(Duration::from_millis(20)).await;
+            stopped.lock().unwrap().push(subsys.name().into());
+            BoxedResult::Ok(())
+        }
+    };
+
+    let subsystem = {
+        let stopped = Arc::clone(&stopped);
+        move |subsys: SubsystemHandle| async move {
+            for name in ["a", "b", "c"] {
+                subsys.start_detached(name, detached_subsystem(Arc::clone(&stopped)));
+            }
+
+            subsys.on_shutdown_requested().await;
+
+            sleep(Duration::from_millis(20)).await;
+            assert!(stopped.lock().unwrap().is_empty());
+
+            subsys
+                .shutdown_detached_children(ShutdownOrder::Reverse)
+                .await;
+            assert_eq!(subsys.child_count(), 0);
+
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let stopped = stopped.lock().unwrap();
+    let stopped: Vec<_> = stopped.iter().map(|name| name.as_ref()).collect();
+    assert_eq!(stopped, ["/subsys/c", "/subsys/b", "/subsys/a"]);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_acknowledgement() {
+    let (release_stuck, set_release_stuck) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let listening = subsys.start(SubsystemBuilder::new(
+            "listening",
+            |s: SubsystemHandle| async move {
+                s.on_shutdown_requested().await;
+                sleep(Duration::from_millis(100)).await;
+                BoxedResult::Ok(())
+            },
+        ));
+        let stuck = subsys.start(SubsystemBuilder::new(
+            "stuck",
+            move |s: SubsystemHandle| async move {
+                release_stuck.wait().await;
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+
+        assert!(!listening.shutdown_acknowledged());
+        assert!(!stuck.shutdown_acknowledged());
+
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(20)).await;
+
+        assert!(listening.shutdown_acknowledged());
+        assert!(!stuck.shutdown_acknowledged());
+
+        set_release_stuck();
+        sleep(Duration::from_millis(20)).await;
+        assert!(stuck.shutdown_acknowledged());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_detached_children_ignores_linked_children() {
+    use tokio_graceful_shutdown::ShutdownOrder;
+
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+
+    let child_subsystem = |stopped: Arc<Mutex<Vec<Arc<str>>>>| {
+        move |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            stopped.lock().unwrap().push(subsys.name().into());
+            BoxedResult::Ok(())
+        }
+    };
+
+    let subsystem = {
+        let stopped = Arc::clone(&stopped);
+        move |subsys: SubsystemHandle| async move {
+            let first = subsys.start(SubsystemBuilder::new(
+                "first",
+                child_subsystem(Arc::clone(&stopped)),
+            ));
+            subsys.start(
+                SubsystemBuilder::new("dependent", child_subsystem(Arc::clone(&stopped)))
+                    .shutdown_after(&first),
+            );
+            subsys.start(
+                SubsystemBuilder::new("linked", child_subsystem(Arc::clone(&stopped)))
+                    .root_linked(),
+            );
+            subsys.start_detached("detached", child_subsystem(Arc::clone(&stopped)));
+
+            sleep(Duration::from_millis(20)).await;
+            subsys
+                .shutdown_detached_children(ShutdownOrder::Sequential)
+                .await;
+
+            sleep(Duration::from_millis(20)).await;
+            let stopped_early: Vec<Arc<str>> = stopped.lock().unwrap().clone();
+            assert_eq!(
+                stopped_early
+                    .iter()
+                    .map(|name| name.as_ref())
+                    .collect::<Vec<_>>(),
+                ["/subsys/detached"]
+            );
+            assert_eq!(subsys.child_count(), 3);
+
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    assert_eq!(stopped.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_after_delays_shutdown_until_dependency_finished() {
+    let (first_finished, set_first_finished) = Event::create();
+
+    let first = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(100)).await;
+        set_first_finished();
+        BoxedResult::Ok(())
+    };
+
+    let second = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        assert!(first_finished.get());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let first = s.start(SubsystemBuilder::new("first", first));
+        s.start(SubsystemBuilder::new("second", second).shutdown_after(&first));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn dropping_handle_shutdown_requests_aborts_subsystems() {
+    let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let _sender = sender;
+        subsys.on_shutdown_requested().await;
+        // Ignore the shutdown request
+        sleep(Duration::from_secs(10)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    // Drop the future in the middle of the shutdown
+    let result = tokio::time::timeout(
+        Duration::from_millis(200),
+        toplevel.handle_shutdown_requests(Duration::from_secs(10)),
+    )
+    .await;
+    assert!(result.is_err());
+
+    // The subsystem got aborted, which dropped the sender
+    let aborted = tokio::time::timeout(Duration::from_millis(100), receiver).await;
+    assert!(matches!(aborted, Ok(Err(_))));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn dropping_handle_shutdown_requests_requests_shutdown() {
+    let token = Arc::new(Mutex::new(None));
+
+    let toplevel = Toplevel::new({
+        let token = Arc::clone(&token);
+        move |s: SubsystemHandle| async move {
+            *token.lock().unwrap() = Some(s.create_cancellation_token());
+            s.on_shutdown_requested().await;
+        }
+    });
+
+    let result = tokio::time::timeout(
+        Duration::from_millis(100),
+        toplevel.handle_shutdown_requests(Duration::from_secs(10)),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let token = token.lock().unwrap().take().unwrap();
+    assert!(token.is_cancelled());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn on_shutdown_runs_callback_once_shutdown_is_requested() {
+    let (callback_finished, set_callback_finished) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown(move || async move {
+            sleep(Duration::from_millis(50)).await;
+            set_callback_finished();
+        });
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!callback_finished.get());
+
+        subsys.on_shutdown_requested().await;
+        assert!(!callback_finished.get());
+
+        subsys.wait_for_children().await;
+        assert!(callback_finished.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn mirror_shutdown_to_cancels_external_token() {
+    let external = tokio_util::sync::CancellationToken::new();
+
+    let subsystem = {
+        let external = external.clone();
+        move |subsys: SubsystemHandle| async move {
+            subsys.mirror_shutdown_to(external.clone());
+
+            sleep(Duration::from_millis(50)).await;
+            assert!(!external.is_cancelled());
+
+            subsys.on_shutdown_requested().await;
+            subsys.wait_for_children().await;
+            assert!(external.is_cancelled());
+
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(external.is_cancelled());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn root_linked_subsystem_ignores_local_shutdown_of_parent() {
+    let (linked_finished, set_linked_finished) = Event::create();
+    let (normal_finished, set_normal_finished) = Event::create();
+
+    let linked_subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_linked_finished();
+        BoxedResult::Ok(())
+    };
+
+    let normal_subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_normal_finished();
+        BoxedResult::Ok(())
+    };
+
+    let parent = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("linked", linked_subsystem).root_linked());
+        subsys.start(SubsystemBuilder::new("normal", normal_subsystem));
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let nested = subsys.start(SubsystemBuilder::new("parent", parent));
+        sleep(Duration::from_millis(20)).await;
+
+        nested.initiate_shutdown();
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(normal_finished.get());
+        assert!(!linked_finished.get());
+        assert!(!nested.downgrade().is_finished());
+
+        subsys.on_shutdown_requested().await;
+        nested.join().await?;
+        assert!(linked_finished.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn cancellation_token_is_the_subsystems_own_token() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let nested = |subsys: SubsystemHandle| async move {
+        let token = subsys.cancellation_token();
+        assert!(!token.is_cancelled());
+
+        // Cancelling the token shuts down the subsystem itself
+        token.cancel();
+        assert!(subsys.is_shutdown_requested());
+
+        subsys.on_shutdown_requested().await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let token = subsys.cancellation_token();
+
+        subsys.start(SubsystemBuilder::new("nested", nested));
+        sleep(Duration::from_millis(20)).await;
+
+        // Only the nested subsystem got shut down
+        assert!(nested_finished.get());
+        assert!(!token.is_cancelled());
+
+        subsys.on_shutdown_requested().await;
+        assert!(token.is_cancelled());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn abort_all_stops_subsystems_without_graceful_shutdown() {
+    let (cleanup_ran, set_cleanup_ran) = Event::create();
+
+    let stubborn = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(1000)).await;
+        set_cleanup_ran();
+        BoxedResult::Ok(())
+    };
+
+    let emergency = |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(20)).await;
+        subsys.abort_all();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new(
+            "nested",
+            |s: SubsystemHandle| async move {
+                s.start(SubsystemBuilder::new("stubborn", stubborn));
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+        s.start(SubsystemBuilder::new("emergency", emergency));
+    });
+
+    let start = tokio::time::Instant::now();
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(2000))
+        .await;
+
+    assert!(result.is_ok());
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(!cleanup_ran.get());
+    assert!(logs_contain(
+        "Subsystem cancelled. subsystem=/nested/stubborn"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn abort_all_reports_aborted_root_subsystem() {
+    let emergency = |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(20)).await;
+        subsys.abort_all();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new_with_output(move |s| async move {
+        s.start(SubsystemBuilder::new("emergency", emergency));
+        s.on_shutdown_requested().await;
+        // Still cleaning up when the abort hits
+        sleep(Duration::from_millis(1000)).await;
+        42
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests_with_output(Duration::from_millis(2000))
+        .await;
+
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(
+                &errors[0],
+                tokio_graceful_shutdown::errors::SubsystemError::Cancelled(name) if name.as_ref() == "/"
+            ));
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_state_reports_progress() {
+    let fast = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let slow = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(200)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("fast", fast));
+        s.start(SubsystemBuilder::new("slow", slow));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let shutdown_state = toplevel.shutdown_state();
+    assert_eq!(*shutdown_state.borrow(), ShutdownState::Running);
+
+    let observer = tokio::spawn(async move {
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(*shutdown_state.borrow(), ShutdownState::Running);
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            *shutdown_state.borrow(),
+            ShutdownState::WaitingForSubsystems { remaining: 2 }
+        );
+
+        let mut shutdown_state = shutdown_state;
+        while shutdown_state.changed().await.is_ok() {}
+        assert_eq!(*shutdown_state.borrow(), ShutdownState::Finished);
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    observer.await.unwrap();
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_state_reports_timeout() {
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(1000)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        s.request_shutdown();
+    });
+
+    let shutdown_state = toplevel.shutdown_state();
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(100))
+        .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+
+    assert_eq!(*shutdown_state.borrow(), ShutdownState::TimedOut);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_trigger_works_from_other_thread() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let shutdown_trigger = toplevel.shutdown_trigger();
+    assert!(!shutdown_trigger.is_triggered());
+
+    let thread = std::thread::spawn({
+        let shutdown_trigger = shutdown_trigger.clone();
+        move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            shutdown_trigger.trigger();
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(shutdown_trigger.is_triggered());
+    thread.join().unwrap();
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_group_shuts_down_members() {
+    let (http_finished, set_http_finished) = Event::create();
+    let (nested_http_finished, set_nested_http_finished) = Event::create();
+    let (other_finished, set_other_finished) = Event::create();
+
+    let http = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_http_finished();
+        BoxedResult::Ok(())
+    };
+
+    let nested_http = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_http_finished();
+        BoxedResult::Ok(())
+    };
+
+    let other = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested_http", nested_http).group("http"));
+        subsys.on_shutdown_requested().await;
+        set_other_finished();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("http", http).group("http"));
+        s.start(SubsystemBuilder::new("other", other).group("other"));
+
+        sleep(Duration::from_millis(50)).await;
+        s.shutdown_group("http");
+        sleep(Duration::from_millis(50)).await;
+        assert!(!s.is_shutdown_requested());
+        s.request_shutdown();
+    });
+
+    sleep(Duration::from_millis(75)).await;
+    assert!(http_finished.get());
+    assert!(nested_http_finished.get());
+    assert!(!other_finished.get());
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(other_finished.get());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn wait_shutdown_or_children_done_reports_reason() {
+    use tokio_graceful_shutdown::WakeReason;
+
+    let job = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Ok(())
+    };
+
+    let worker = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("job", job));
+        assert_eq!(
+            s.wait_shutdown_or_children_done().await,
+            WakeReason::ChildrenFinished
+        );
+
+        s.start(SubsystemBuilder::new("worker", worker));
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+        assert_eq!(
+            s.wait_shutdown_or_children_done().await,
+            WakeReason::ShutdownRequested
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_flag_stops_blocking_subsystem() {
+    let blocking_finished = Arc::new(AtomicBool::new(false));
+    let blocking_finished2 = Arc::clone(&blocking_finished);
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let shutdown_flag = subsys.shutdown_flag();
+        assert!(!shutdown_flag.load(Ordering::Acquire));
+
+        tokio::task::spawn_blocking(move || {
+            while !shutdown_flag.load(Ordering::Acquire) {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            blocking_finished2.store(true, Ordering::Release);
+        })
+        .await?;
+
+        assert!(subsys.shutdown_flag().load(Ordering::Acquire));
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(blocking_finished.load(Ordering::Acquire));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_deadline_warning_fires_before_timeout() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        let shutdown_started = tokio::time::Instant::now();
+
+        subsys
+            .on_shutdown_deadline_warning(Duration::from_millis(300))
+            .await;
+
+        let elapsed = shutdown_started.elapsed();
+        assert!(elapsed > Duration::from_millis(50));
+        assert!(elapsed < Duration::from_millis(250));
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn finalizer_runs_even_if_subsystem_gets_cancelled() {
+    let graceful_finalized = Arc::new(AtomicBool::new(false));
+    let stubborn_finalized = Arc::new(AtomicBool::new(false));
+
+    let graceful = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let stubborn = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_secs(10)).await;
+        BoxedResult::Ok(())
+    };
+
+    let finalizer = |finalized: Arc<AtomicBool>| {
+        move || async move {
+            sleep(Duration::from_millis(20)).await;
+            finalized.store(true, Ordering::Release);
+        }
+    };
+
+    let toplevel = Toplevel::new({
+        let graceful_finalized = Arc::clone(&graceful_finalized);
+        let stubborn_finalized = Arc::clone(&stubborn_finalized);
+        move |s| async move {
+            s.start(
+                SubsystemBuilder::new("graceful", graceful)
+                    .finalizer(finalizer(graceful_finalized)),
+            );
+            s.start(
+                SubsystemBuilder::new("stubborn", stubborn)
+                    .finalizer(finalizer(stubborn_finalized)),
+            );
+            sleep(Duration::from_millis(50)).await;
+            s.request_shutdown();
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(200))
+        .await;
+    assert!(result.is_err());
+
+    // The finished subsystem waited for its finalizer
+    assert!(graceful_finalized.load(Ordering::Acquire));
+
+    // The cancelled subsystem's finalizer runs in the background
+    sleep(Duration::from_millis(100)).await;
+    assert!(stubborn_finalized.load(Ordering::Acquire));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_timeout_gets_read_from_env() {
+    let stuck_subsystem = |subsys: SubsystemHandle| async move {
+        subsys.request_shutdown();
+        sleep(Duration::from_secs(10)).await;
+        BoxedResult::Ok(())
+    };
+
+    std::env::set_var("TGS_TEST_SHUTDOWN_TIMEOUT_MS", "100");
+    let result = tokio::time::timeout(
+        Duration::from_secs(1),
+        Toplevel::new(move |s| async move {
+            s.start(SubsystemBuilder::new("subsys", stuck_subsystem));
+        })
+        .handle_shutdown_requests_from_env("TGS_TEST_SHUTDOWN_TIMEOUT_MS", Duration::from_secs(5)),
+    )
+    .await
+    .expect("The timeout from the environment should have been used");
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+
+    std::env::set_var("TGS_TEST_SHUTDOWN_TIMEOUT_MS", "abc");
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", stuck_subsystem));
+    })
+    .handle_shutdown_requests_from_env("TGS_TEST_SHUTDOWN_TIMEOUT_MS", Duration::from_millis(100))
+    .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+    assert!(logs_contain(
+        "Invalid shutdown timeout, using the default. var=\"TGS_TEST_SHUTDOWN_TIMEOUT_MS\" value=\"abc\""
+    ));
+    std::env::remove_var("TGS_TEST_SHUTDOWN_TIMEOUT_MS");
+}
@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{SignalKind, SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn on_signal_receives_signal_kind() {
+    use nix::sys::signal::{self, Signal};
+    use nix::unistd::Pid;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let received = Arc::new(Mutex::new(None));
+
+    tokio::join!(
+        async {
+            sleep(Duration::from_millis(100)).await;
+
+            // Send SIGTERM to ourselves.
+            signal::kill(Pid::this(), Signal::SIGTERM).unwrap();
+        },
+        async {
+            let received = Arc::clone(&received);
+            let result = Toplevel::new(move |s| async move {
+                s.start(SubsystemBuilder::new("subsys", subsystem));
+            })
+            .catch_signals()
+            .on_signal(move |signal| {
+                let received = Arc::clone(&received);
+                async move {
+                    *received.lock().unwrap() = Some(signal);
+                }
+            })
+            .handle_shutdown_requests(Duration::from_millis(400))
+            .await;
+            assert!(result.is_ok());
+        },
+    );
+
+    assert_eq!(*received.lock().unwrap(), Some(SignalKind::Terminate));
+}
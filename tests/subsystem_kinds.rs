@@ -0,0 +1,794 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{
+    errors::GracefulShutdownError, SubsystemBuilder, SubsystemHandle, Toplevel,
+};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn wait_for_children_with_timeout() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let nested = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(100)).await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsys1 = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested", nested));
+
+        subsys.request_shutdown();
+
+        // Gives up while the child is still shutting down
+        assert!(subsys
+            .wait_for_children_with_timeout(Duration::from_millis(50))
+            .await
+            .is_err());
+        assert!(!nested_finished.get());
+
+        subsys
+            .wait_for_children_with_timeout(Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(nested_finished.get());
+
+        BoxedResult::Ok(())
+    };
+
+    Toplevel::new(|s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsys1));
+    })
+    .handle_shutdown_requests(Duration::from_millis(500))
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+#[traced_test]
+async fn join_with_timeout_leaves_subsystem_running() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let nested = subsys.start(SubsystemBuilder::new(
+            "nested",
+            |s: SubsystemHandle| async move {
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+
+        let result = nested.join_with_timeout(Duration::from_millis(50)).await;
+        assert!(result.is_err());
+
+        nested.initiate_shutdown();
+        let result = nested.join_with_timeout(Duration::from_millis(50)).await;
+        assert!(matches!(result, Ok(Ok(()))));
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn quiet_cancel_lowers_log_level() {
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(1000)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("loud", subsystem));
+        s.start(SubsystemBuilder::new("quiet", subsystem).quiet_cancel());
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(100))
+        .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+
+    // Give the runners time to get cancelled
+    sleep(Duration::from_millis(50)).await;
+
+    logs_assert(|lines: &[&str]| {
+        let cancelled = |name: &str, level: &str| {
+            lines
+                .iter()
+                .any(|line| line.contains(level) && line.contains(name))
+        };
+        match (
+            cancelled("Subsystem cancelled. subsystem=/loud", "WARN"),
+            cancelled("Subsystem cancelled. subsystem=/quiet", "DEBUG"),
+            cancelled("Subsystem cancelled. subsystem=/quiet", "WARN"),
+        ) {
+            (true, true, false) => Ok(()),
+            other => Err(format!("Unexpected cancellation logs: {other:?}")),
+        }
+    });
+}
+
+#[tokio::test]
+#[traced_test]
+async fn spawn_periodic_runs_until_shutdown() {
+    use std::sync::atomic::AtomicU32;
+    use tokio::time::MissedTickBehavior;
+
+    let counter = Arc::new(AtomicU32::new(0));
+
+    let subsystem = {
+        let counter = Arc::clone(&counter);
+        move |subsys: SubsystemHandle| async move {
+            let periodic = subsys.spawn_periodic(
+                "periodic",
+                Duration::from_millis(20),
+                MissedTickBehavior::Skip,
+                move |subsys: &SubsystemHandle| {
+                    // Every run is a child of the periodic subsystem
+                    let counter = Arc::clone(&counter);
+                    let tick = subsys.start(SubsystemBuilder::new(
+                        "tick",
+                        move |subsys: SubsystemHandle| async move {
+                            assert_eq!(subsys.name(), "/subsys/periodic/tick");
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            BoxedResult::Ok(())
+                        },
+                    ));
+                    async move { tick.join().await }
+                },
+            );
+
+            subsys.on_shutdown_requested().await;
+            periodic.join().await?;
+
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(110)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let count = counter.load(Ordering::SeqCst);
+    assert!((5..=7).contains(&count), "count = {count}");
+
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(counter.load(Ordering::SeqCst), count);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn restart_restartable_subsystem() {
+    use std::sync::atomic::AtomicU32;
+
+    let starts = Arc::new(AtomicU32::new(0));
+
+    let restartable = {
+        let starts = Arc::clone(&starts);
+        move |subsys: SubsystemHandle| async move {
+            assert_eq!(&*subsys.name(), "/subsys/restartable");
+            starts.fetch_add(1, Ordering::SeqCst);
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let not_restartable = subsys.start(SubsystemBuilder::new(
+            "not_restartable",
+            |s: SubsystemHandle| async move {
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+        assert!(not_restartable.restart().is_err());
+
+        let nested =
+            subsys.start(SubsystemBuilder::new("restartable", restartable.clone()).restartable());
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+
+        nested.restart().unwrap();
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+
+        subsys.on_shutdown_requested().await;
+        nested.join().await?;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn wait_for_children_count() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        for delay in [20, 40, 1000] {
+            subsys.start(SubsystemBuilder::new(
+                "nested",
+                move |s: SubsystemHandle| async move {
+                    tokio::select! {
+                        _ = s.on_shutdown_requested() => (),
+                        _ = sleep(Duration::from_millis(delay)) => (),
+                    }
+                    BoxedResult::Ok(())
+                },
+            ));
+        }
+
+        let start = tokio::time::Instant::now();
+        subsys.wait_for_children_count(2).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(40));
+        assert!(elapsed < Duration::from_millis(200));
+
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn spawn_pool_starts_and_resizes_workers() {
+    let names = Arc::new(Mutex::new(Vec::new()));
+
+    let worker = {
+        let names = Arc::clone(&names);
+        move |subsys: SubsystemHandle| {
+            names.lock().unwrap().push(subsys.name().to_string());
+            async move {
+                subsys.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            }
+        }
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let pool = subsys.spawn_pool("workers", 2, worker);
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(subsys.descendant_count(), 3);
+
+        pool.resize(4);
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(pool.size(), 4);
+        assert_eq!(subsys.descendant_count(), 5);
+
+        pool.resize(1);
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(subsys.descendant_count(), 2);
+
+        subsys.on_shutdown_requested().await;
+        pool.join().await?;
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let mut names = names.lock().unwrap().clone();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "/subsys/workers/0",
+            "/subsys/workers/1",
+            "/subsys/workers/2",
+            "/subsys/workers/3",
+        ]
+    );
+}
+
+#[tokio::test]
+#[traced_test]
+async fn spawn_pool_respawns_finished_workers() {
+    let starts = Arc::new(Mutex::new(0));
+
+    let worker = {
+        let starts = Arc::clone(&starts);
+        move |_subsys: SubsystemHandle| {
+            *starts.lock().unwrap() += 1;
+            async move {
+                sleep(Duration::from_millis(40)).await;
+                BoxedResult::Ok(())
+            }
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.spawn_pool("workers", 2, worker);
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    // Two workers, each running three times within 100ms
+    let starts = *starts.lock().unwrap();
+    assert!(
+        (4..=6).contains(&starts),
+        "unexpected number of starts: {starts}"
+    );
+}
+
+#[tokio::test]
+#[traced_test]
+async fn adopt_task_waits_for_task() {
+    let (task_finished, set_task_finished) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.adopt_task(tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            set_task_finished();
+        }));
+        assert_eq!(subsys.child_count(), 1);
+
+        subsys.wait_for_children().await;
+        assert!(task_finished.get());
+
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn adopt_task_aborts_task_on_cancel() {
+    let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.adopt_task(tokio::spawn(async move {
+            let _sender = sender;
+            sleep(Duration::from_secs(10)).await;
+        }));
+
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(100))
+        .await;
+    assert!(matches!(
+        result,
+        Err(GracefulShutdownError::ShutdownTimeout(_))
+    ));
+
+    // The task got aborted, which dropped the sender
+    let aborted = tokio::time::timeout(Duration::from_millis(100), receiver).await;
+    assert!(matches!(aborted, Ok(Err(_))));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn adopt_task_reports_panics() {
+    use tokio_graceful_shutdown::errors::SubsystemError;
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.adopt_task(tokio::spawn(async move {
+            panic!("Oh no!");
+        }));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => errors,
+        other => panic!("Unexpected result: {other:?}"),
+    };
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], SubsystemError::Panicked(_)));
+    assert!(errors[0].name().starts_with("/task-"));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_and_finished_resolves_once_subsystem_finished() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let nested = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(20)).await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let (_nested, finished) =
+            subsys.start_and_finished(SubsystemBuilder::new("nested", nested));
+
+        assert!(!nested_finished.get());
+        finished.await;
+        assert!(nested_finished.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_lazy_invokes_subsystem_once_triggered() {
+    let (lazy_started, set_lazy_started) = Event::create();
+
+    let lazy = |subsys: SubsystemHandle| async move {
+        set_lazy_started();
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let (trigger, triggered) = tokio::sync::oneshot::channel::<()>();
+        subsys.start_lazy(SubsystemBuilder::new("lazy", lazy), async {
+            triggered.await.ok();
+        });
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(!lazy_started.get());
+
+        trigger.send(()).unwrap();
+        sleep(Duration::from_millis(20)).await;
+        assert!(lazy_started.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_lazy_never_invokes_subsystem_if_shutdown_first() {
+    let (lazy_started, set_lazy_started) = Event::create();
+
+    let lazy = |_subsys: SubsystemHandle| async move {
+        set_lazy_started();
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let nested = subsys.start_lazy(
+            SubsystemBuilder::new("lazy", lazy),
+            std::future::pending::<()>(),
+        );
+
+        subsys.on_shutdown_requested().await;
+        nested.join().await?;
+        assert!(!lazy_started.get());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn min_runtime_reports_early_exit() {
+    use tokio_graceful_shutdown::errors::{ExitedTooQuickly, SubsystemError};
+
+    let subsystem = |_subsys: SubsystemHandle| async move { BoxedResult::Ok(()) };
+
+    let toplevel = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem).min_runtime(Duration::from_millis(100)));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        SubsystemError::Failed(name, e) => {
+            assert_eq!(name.as_ref(), "/subsys");
+            let e = e.get_error().downcast_ref::<ExitedTooQuickly>().unwrap();
+            assert_eq!(e.min_runtime, Duration::from_millis(100));
+            assert!(e.elapsed < Duration::from_millis(100));
+        }
+        SubsystemError::Panicked(_) => panic!("Subsystem should not panic"),
+        SubsystemError::Cancelled(_) => panic!("Subsystem should not be cancelled"),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn min_runtime_ignores_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem).min_runtime(Duration::from_secs(10)));
+
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn health_gets_aggregated() {
+    use tokio_graceful_shutdown::HealthStatus;
+
+    let (nested_health_checked, set_nested_health_checked) = Event::create();
+
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.set_health(HealthStatus::Degraded);
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        let nested = subsys.start(SubsystemBuilder::new("nested", nested));
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(nested.health(), HealthStatus::Degraded);
+        set_nested_health_checked();
+
+        sleep(Duration::from_millis(50)).await;
+        subsys.set_health(HealthStatus::Unhealthy);
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(150)).await;
+        s.request_shutdown();
+    });
+
+    assert_eq!(toplevel.aggregate_health(), HealthStatus::Healthy);
+
+    sleep(Duration::from_millis(50)).await;
+    assert!(nested_health_checked.get());
+    assert_eq!(toplevel.aggregate_health(), HealthStatus::Degraded);
+
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(toplevel.aggregate_health(), HealthStatus::Unhealthy);
+    let tree = toplevel.dump_tree();
+    assert_eq!(tree.health, HealthStatus::Healthy);
+    assert_eq!(tree.children[0].health, HealthStatus::Unhealthy);
+    assert_eq!(tree.children[0].children[0].health, HealthStatus::Degraded);
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn supervision_strategies_restart_the_right_children() {
+    use std::sync::atomic::AtomicUsize;
+    use tokio_graceful_shutdown::SupervisionStrategy;
+
+    async fn run(strategy: SupervisionStrategy) -> [usize; 3] {
+        let starts: Arc<[AtomicUsize; 3]> = Default::default();
+
+        let child = |index: usize, starts: Arc<[AtomicUsize; 3]>| {
+            move |subsys: SubsystemHandle| async move {
+                let started = starts[index].fetch_add(1, Ordering::SeqCst) + 1;
+                // The second child fails the first time it runs
+                if index == 1 && started == 1 {
+                    sleep(Duration::from_millis(20)).await;
+                    return BoxedResult::Err(String::from("First run failed").into());
+                }
+                subsys.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            }
+        };
+
+        let supervisor = {
+            let starts = Arc::clone(&starts);
+            move |subsys: SubsystemHandle| async move {
+                for (index, name) in ["a", "b", "c"].into_iter().enumerate() {
+                    subsys.start(
+                        SubsystemBuilder::new(name, child(index, Arc::clone(&starts)))
+                            .restartable(),
+                    );
+                }
+                subsys.wait_for_children().await;
+                BoxedResult::Ok(())
+            }
+        };
+
+        let toplevel = Toplevel::new(move |s| async move {
+            s.start(SubsystemBuilder::new("supervisor", supervisor).supervision(strategy));
+            sleep(Duration::from_millis(200)).await;
+            s.request_shutdown();
+        });
+
+        let result = toplevel
+            .handle_shutdown_requests(Duration::from_millis(400))
+            .await;
+        assert!(result.is_ok());
+
+        [0, 1, 2].map(|index| starts[index].load(Ordering::SeqCst))
+    }
+
+    assert_eq!(run(SupervisionStrategy::OneForOne).await, [1, 2, 1]);
+    assert_eq!(run(SupervisionStrategy::OneForAll).await, [2, 2, 2]);
+    assert_eq!(run(SupervisionStrategy::RestForOne).await, [1, 2, 2]);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_after_waits_for_readiness() {
+    let database_ready = Arc::new(AtomicBool::new(false));
+    let server_started = Arc::new(AtomicBool::new(false));
+    let never_started = Arc::new(AtomicBool::new(false));
+
+    let database = {
+        let database_ready = Arc::clone(&database_ready);
+        move |subsys: SubsystemHandle| async move {
+            sleep(Duration::from_millis(100)).await;
+            database_ready.store(true, Ordering::Release);
+            subsys.mark_ready();
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+    let never_ready = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = {
+        let database_ready = Arc::clone(&database_ready);
+        let server_started = Arc::clone(&server_started);
+        let never_started = Arc::clone(&never_started);
+        move |subsys: SubsystemHandle| async move {
+            let database = subsys.start(SubsystemBuilder::new("database", database));
+            let never_ready = subsys.start(SubsystemBuilder::new("never_ready", never_ready));
+
+            subsys.start(
+                SubsystemBuilder::new("server", move |subsys: SubsystemHandle| async move {
+                    assert!(database_ready.load(Ordering::Acquire));
+                    server_started.store(true, Ordering::Release);
+                    subsys.on_shutdown_requested().await;
+                    BoxedResult::Ok(())
+                })
+                .start_after(&database),
+            );
+            subsys.start(
+                SubsystemBuilder::new(
+                    "never_started",
+                    move |_subsys: SubsystemHandle| async move {
+                        never_started.store(true, Ordering::Release);
+                        BoxedResult::Ok(())
+                    },
+                )
+                .start_after(&never_ready),
+            );
+
+            database.ready().await;
+            sleep(Duration::from_millis(50)).await;
+            subsys.request_shutdown();
+            BoxedResult::Ok(())
+        }
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+    assert!(server_started.load(Ordering::Acquire));
+    assert!(!never_started.load(Ordering::Acquire));
+}
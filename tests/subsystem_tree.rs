@@ -0,0 +1,684 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{
+    errors::GracefulShutdownError, SubsystemBuilder, SubsystemHandle, Toplevel,
+};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn max_depth_stops_runaway_recursion() {
+    use tokio_graceful_shutdown::errors::SubsystemError;
+
+    fn recursive(
+        subsys: SubsystemHandle,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = BoxedResult> + Send>> {
+        Box::pin(async move {
+            subsys.start(SubsystemBuilder::new("rec", recursive));
+            subsys.on_shutdown_requested().await;
+            Ok(())
+        })
+    }
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("rec", recursive));
+    })
+    .with_max_depth(3);
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], SubsystemError::Panicked(_)));
+    assert_eq!(errors[0].name(), "/rec/rec/rec");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn children_outlive_parent() {
+    let worker_finished = Arc::new(AtomicBool::new(false));
+
+    let worker = {
+        let worker_finished = Arc::clone(&worker_finished);
+        move |subsys: SubsystemHandle| async move {
+            subsys.on_shutdown_requested().await;
+            worker_finished.store(true, Ordering::Release);
+            BoxedResult::Ok(())
+        }
+    };
+
+    let supervisor = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("worker", worker));
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new({
+        let worker_finished = Arc::clone(&worker_finished);
+        move |s| async move {
+            let supervisor =
+                s.start(SubsystemBuilder::new("supervisor", supervisor).children_outlive_parent());
+
+            // The supervisor is finished, even though the worker is still running
+            supervisor
+                .join_with_timeout(Duration::from_millis(100))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(!worker_finished.load(Ordering::Acquire));
+            assert!(!s.is_shutdown_requested());
+
+            s.request_shutdown();
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(worker_finished.load(Ordering::Acquire));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn children_outlive_parent_forward_errors_to_grandparent() {
+    use tokio_graceful_shutdown::ErrorAction;
+
+    let worker = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(100)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let supervisor = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("worker", worker));
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("supervisor", supervisor)
+                .children_outlive_parent()
+                .on_failure(ErrorAction::CatchAndLocalShutdown),
+        );
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    // The finished supervisor no longer catches the error of the worker
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/supervisor/worker");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn renamed_subsystem_reports_new_name() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.set_name("renamed");
+        assert_eq!(&*subsys.name(), "/subsys/renamed");
+
+        let nested = subsys.start(SubsystemBuilder::new(
+            "nested",
+            |s: SubsystemHandle| async move {
+                assert_eq!(&*s.name(), "/subsys/renamed/nested");
+                BoxedResult::Ok(())
+            },
+        ));
+        nested.join().await.unwrap();
+
+        BoxedResult::Err("failed".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new(
+            "subsys",
+            move |s: SubsystemHandle| async move {
+                s.start(SubsystemBuilder::new("original", subsystem));
+                BoxedResult::Ok(())
+            },
+        ));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    let errors = result.unwrap_err().into_subsystem_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].name(), "/subsys/renamed");
+}
+
+#[tokio::test]
+#[traced_test]
+async fn renamed_subsystem_updates_span_samples_and_filtered_failures() {
+    use tokio_graceful_shutdown::Sample;
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(75)).await;
+        subsys.set_name("renamed");
+        tracing::info!("After rename");
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("not a failure".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("original", subsystem)
+                .with_span_field("component", "worker")
+                .with_sampler(Duration::from_millis(50), Sample::new)
+                .failure_filter(|_| false),
+        );
+    });
+    let mut samples = toplevel.samples();
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let mut names = vec![];
+    while let Ok(sample) = samples.try_recv() {
+        names.push(sample.name);
+    }
+    let names: Vec<_> = names.iter().map(|name| name.as_ref()).collect();
+    assert_eq!(names, ["/original", "/original", "/renamed"]);
+
+    logs_assert(
+        |lines: &[&str]| match lines.iter().find(|line| line.contains("After rename")) {
+            Some(line) if line.contains("name=/renamed") => Ok(()),
+            Some(line) => Err(format!("Span not renamed: {line}")),
+            None => Err("Message not logged".to_string()),
+        },
+    );
+    assert!(logs_contain(
+        "Subsystem returned an error that is not a failure. subsystem=/renamed"
+    ));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn nested_subsystem_exposes_task_id() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let nested1 = subsys.start(SubsystemBuilder::new(
+            "nested1",
+            |_: SubsystemHandle| async { BoxedResult::Ok(()) },
+        ));
+        let (task_id_sender, task_id) = tokio::sync::oneshot::channel();
+        let nested2 = subsys.start(
+            SubsystemBuilder::new("nested2", |_: SubsystemHandle| async move {
+                task_id_sender.send(tokio::task::id()).unwrap();
+                BoxedResult::Ok(())
+            })
+            .no_panic_isolation(),
+        );
+
+        assert_ne!(nested1.task_id(), nested2.task_id());
+        // Without panic isolation, the subsystem function runs in the runner task
+        assert_eq!(task_id.await.unwrap(), nested2.task_id());
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn child_and_descendant_count() {
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new(
+            "nested",
+            |s: SubsystemHandle| async move {
+                s.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            },
+        ));
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        assert_eq!(subsys.child_count(), 0);
+        assert_eq!(subsys.descendant_count(), 0);
+
+        subsys.start(SubsystemBuilder::new("nested1", nested));
+        subsys.start(SubsystemBuilder::new("nested2", nested));
+        sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(subsys.child_count(), 2);
+        assert_eq!(subsys.descendant_count(), 4);
+
+        subsys.on_shutdown_requested().await;
+        subsys.wait_for_children().await;
+
+        assert_eq!(subsys.child_count(), 0);
+        assert_eq!(subsys.descendant_count(), 0);
+
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn weak_nested_subsystem_observes_subsystem() {
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let nested = s.start(SubsystemBuilder::new("nested", nested));
+        let weak = nested.downgrade();
+        drop(nested);
+
+        assert_eq!(&*weak.name(), "/nested");
+        assert!(!weak.is_finished());
+
+        s.request_shutdown();
+        weak.finished().await;
+        assert!(weak.is_finished());
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn subsystem_shutdown_token_is_linked_to_subsystem() {
+    let (subsys, _errors) = SubsystemHandle::<BoxedError>::new_root();
+
+    let token = Toplevel::subsystem_shutdown_token(&subsys);
+    token.cancel();
+    assert!(!subsys.is_shutdown_requested());
+
+    let token = Toplevel::subsystem_shutdown_token(&subsys);
+    subsys.request_local_shutdown();
+    assert!(token.is_cancelled());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn nested_toplevel_shuts_down_with_outer_toplevel() {
+    let inner_finished = Arc::new(AtomicBool::new(false));
+
+    let inner_subsys = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let outer_subsys = {
+        let inner_finished = Arc::clone(&inner_finished);
+        move |subsys: SubsystemHandle| async move {
+            let result = Toplevel::new(move |s| async move {
+                s.start(SubsystemBuilder::new("inner", inner_subsys));
+            })
+            .use_external_shutdown(Toplevel::subsystem_shutdown_token(&subsys))
+            .handle_shutdown_requests(Duration::from_millis(200))
+            .await;
+
+            inner_finished.store(true, Ordering::Release);
+            result
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("outer", outer_subsys));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+
+    assert!(result.is_ok());
+    assert!(inner_finished.load(Ordering::Acquire));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn dump_tree_reflects_running_subsystems() {
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested1", nested));
+        subsys.start(SubsystemBuilder::new("nested2", nested));
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    });
+
+    sleep(Duration::from_millis(50)).await;
+
+    let tree = toplevel.dump_tree();
+    assert_eq!(tree.name.as_ref(), "/");
+    assert!(tree.alive);
+    assert!(!tree.shutdown_requested);
+    assert_eq!(tree.children.len(), 1);
+
+    let subsys = &tree.children[0];
+    assert_eq!(subsys.name.as_ref(), "/subsys");
+    assert!(!subsys.alive);
+
+    let names: Vec<_> = subsys.children.iter().map(|c| c.name.as_ref()).collect();
+    assert_eq!(names, ["/subsys/nested1", "/subsys/nested2"]);
+    assert!(subsys
+        .children
+        .iter()
+        .all(|c| c.alive && c.children.is_empty()));
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn subsystem_tree_builder_starts_tree() {
+    use tokio_graceful_shutdown::{
+        errors::SubsystemJoinError, ErrorAction, SubsystemTreeBuilder, SubsystemTreeNode,
+    };
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+
+    let worker = {
+        let names = Arc::clone(&names);
+        move |subsys: SubsystemHandle| async move {
+            names.lock().unwrap().push(subsys.name().to_string());
+            subsys.on_shutdown_requested().await;
+            BoxedResult::Ok(())
+        }
+    };
+
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(100)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        let nested = SubsystemTreeBuilder::new()
+            .subsystem(
+                SubsystemTreeNode::new("group", worker.clone())
+                    .on_failure(ErrorAction::CatchAndLocalShutdown)
+                    .child(SubsystemTreeNode::new("worker", worker.clone()))
+                    .child(SubsystemTreeNode::new("failing", failing)),
+            )
+            .subsystem(SubsystemTreeNode::new("other", worker).shutdown_after("group"))
+            .start(&s);
+        assert_eq!(nested.len(), 2);
+
+        let mut nested = nested.into_iter();
+        let group = nested.next().unwrap();
+        let other = nested.next().unwrap();
+
+        // The failure shuts down the group locally, but not its sibling
+        let SubsystemJoinError::SubsystemsFailed(errors) = group.join().await.unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].name(), "/group/failing");
+        assert!(!other.downgrade().is_finished());
+
+        s.request_shutdown();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    let mut names = names.lock().unwrap().clone();
+    names.sort();
+    assert_eq!(names, ["/group", "/group/worker", "/other"]);
+}
+
+#[test]
+#[should_panic(expected = "Subsystem 'second' depends on 'first', which has to be added before it")]
+fn subsystem_tree_builder_rejects_unknown_dependency() {
+    use tokio_graceful_shutdown::{SubsystemTreeBuilder, SubsystemTreeNode};
+
+    let subsystem = |_subsys: SubsystemHandle| async move { BoxedResult::Ok(()) };
+
+    let _ = SubsystemTreeBuilder::new()
+        .subsystem(SubsystemTreeNode::new("second", subsystem).shutdown_after("first"))
+        .subsystem(SubsystemTreeNode::new("first", subsystem));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn new_root_runs_subsystems_without_toplevel() {
+    let (nested_finished, set_nested_finished) = Event::create();
+
+    let nested = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        set_nested_finished();
+        BoxedResult::Ok(())
+    };
+
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(100)).await;
+        BoxedResult::Err("Oh no!".into())
+    };
+
+    let (root, mut errors) = SubsystemHandle::<BoxedError>::new_root();
+    root.start(SubsystemBuilder::new("nested", nested));
+    root.start(SubsystemBuilder::new("failing", failing));
+
+    // The uncaught error shuts down the tree
+    let error = errors.recv().await.unwrap();
+    assert_eq!(error.name(), "/failing");
+    assert!(root.is_shutdown_requested());
+
+    tokio::time::timeout(Duration::from_millis(400), root.wait_for_children())
+        .await
+        .unwrap();
+    assert!(nested_finished.get());
+    assert!(errors.try_recv().is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn toplevel_runs_on_given_runtime() {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("dedicated-runtime")
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let on_dedicated_runtime = || std::thread::current().name() == Some("dedicated-runtime");
+
+    let nested = move |_subsys: SubsystemHandle| async move {
+        assert!(on_dedicated_runtime());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new_on(runtime.handle().clone(), move |s| async move {
+        assert!(on_dedicated_runtime());
+        s.start(SubsystemBuilder::new("nested", nested))
+            .join()
+            .await
+            .unwrap();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    runtime.shutdown_background();
+}
+
+#[tokio::test]
+#[traced_test]
+async fn is_detached_reports_detachment() {
+    let check = |expected: bool| {
+        move |subsys: SubsystemHandle| async move {
+            assert_eq!(subsys.is_detached(), expected);
+            BoxedResult::Ok(())
+        }
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        assert!(!s.is_detached());
+
+        s.start(SubsystemBuilder::new("attached", check(false)))
+            .join()
+            .await
+            .unwrap();
+        s.start(SubsystemBuilder::new("root_linked", check(false)).root_linked())
+            .join()
+            .await
+            .unwrap();
+        s.start(SubsystemBuilder::new("detached", check(true)).detached())
+            .join()
+            .await
+            .unwrap();
+        s.start(
+            SubsystemBuilder::new("restartable", check(true))
+                .detached()
+                .restartable(),
+        )
+        .join()
+        .await
+        .unwrap();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn start_batch_starts_all_subsystems() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let nested = subsys.start_batch((0..10).map(|i| {
+            SubsystemBuilder::new(format!("nested{i}"), |subsys: SubsystemHandle| async move {
+                subsys.on_shutdown_requested().await;
+                BoxedResult::Ok(())
+            })
+        }));
+        assert_eq!(nested.len(), 10);
+        assert_eq!(subsys.child_count(), 10);
+        assert_eq!(subsys.descendant_count(), 10);
+        assert_eq!(nested[3].downgrade().name().as_ref(), "/subsys/nested3");
+
+        nested[3].initiate_shutdown();
+        nested[3].join().await.unwrap();
+        assert_eq!(subsys.child_count(), 9);
+
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn reload_replaces_the_subsystem_tree() {
+    let (old_finished, set_old_finished) = Event::create();
+    let (new_started, set_new_started) = Event::create();
+
+    let mut toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new(
+            "old",
+            move |subsys: SubsystemHandle| async move {
+                subsys.on_shutdown_requested().await;
+                set_old_finished();
+                BoxedResult::Err("Failed during reload".into())
+            },
+        ));
+    });
+    let shutdown_token = toplevel._get_shutdown_token().clone();
+
+    sleep(Duration::from_millis(50)).await;
+    let result = toplevel
+        .reload(
+            move |s| async move {
+                assert!(!s.is_shutdown_requested());
+                s.start(SubsystemBuilder::new(
+                    "new",
+                    move |subsys: SubsystemHandle| async move {
+                        set_new_started();
+                        sleep(Duration::from_millis(50)).await;
+                        subsys.request_shutdown();
+                        BoxedResult::Ok(())
+                    },
+                ));
+            },
+            Duration::from_millis(400),
+        )
+        .await;
+
+    // The error of the old tree gets returned, but does not shut down the program
+    assert!(old_finished.get());
+    match result {
+        Err(GracefulShutdownError::SubsystemsFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].name(), "/old");
+        }
+        other => panic!("Unexpected result: {other:?}"),
+    }
+    assert!(!shutdown_token.is_cancelled());
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+    assert!(new_started.get());
+}
@@ -0,0 +1,64 @@
+use tokio::time::{interval, sleep, Duration};
+use tokio_graceful_shutdown::{StreamExt as _, SubsystemBuilder, SubsystemHandle, Toplevel};
+use tokio_stream::{iter, wrappers::IntervalStream, StreamExt as _};
+use tracing_test::traced_test;
+
+use std::{error::Error, pin::pin};
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn take_until_shutdown_forwards_all_items() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let items: Vec<i32> = iter([1, 2, 3]).take_until_shutdown(&subsys).collect().await;
+
+        assert_eq!(items, [1, 2, 3]);
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn take_until_shutdown_ends_stream_on_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        let mut ticks =
+            pin!(IntervalStream::new(interval(Duration::from_millis(20)))
+                .take_until_shutdown(&subsys));
+
+        let mut count = 0;
+        while ticks.next().await.is_some() {
+            count += 1;
+        }
+
+        assert!(subsys.is_shutdown_requested());
+        assert!((3..=7).contains(&count), "Unexpected count: {count}");
+
+        // The stream stays terminated
+        assert!(ticks.next().await.is_none());
+
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::<BoxedError>::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    })
+    .handle_shutdown_requests(Duration::from_millis(200))
+    .await;
+
+    assert!(result.is_ok());
+}
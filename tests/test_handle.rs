@@ -0,0 +1,68 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{errors::SubsystemError, SubsystemBuilder, SubsystemHandle};
+use tracing_test::traced_test;
+
+use std::error::Error;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn subsystem_reacts_to_shutdown_request() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        BoxedResult::Ok(())
+    };
+
+    let (handle, controller) = SubsystemHandle::test_handle();
+    let subsystem = tokio::spawn(subsystem(handle));
+
+    sleep(Duration::from_millis(50)).await;
+    assert!(!subsystem.is_finished());
+
+    controller.request_shutdown();
+    subsystem.await.unwrap().unwrap();
+    assert!(controller.take_errors().is_empty());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn subsystem_requests_shutdown() {
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.request_shutdown();
+        BoxedResult::Ok(())
+    };
+
+    let (handle, controller) = SubsystemHandle::test_handle();
+    assert!(!controller.is_shutdown_requested());
+
+    subsystem(handle).await.unwrap();
+    assert!(controller.is_shutdown_requested());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn errors_of_nested_subsystems_get_collected() {
+    let nested = |_subsys: SubsystemHandle| async move {
+        BoxedResult::Err(String::from("Nested failed").into())
+    };
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.start(SubsystemBuilder::new("nested", nested));
+        subsys.wait_for_children().await;
+        BoxedResult::Ok(())
+    };
+
+    let (handle, controller) = SubsystemHandle::test_handle();
+    subsystem(handle).await.unwrap();
+
+    let errors = controller.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], SubsystemError::Failed(name, _) if name.as_ref() == "/nested"));
+
+    // Uncaught errors shut down the tree
+    assert!(controller.is_shutdown_requested());
+    assert!(controller.take_errors().is_empty());
+}
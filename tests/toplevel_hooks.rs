@@ -0,0 +1,214 @@
+use tokio::time::{sleep, Duration};
+use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle, Toplevel};
+use tracing_test::traced_test;
+
+pub mod common;
+
+use std::{
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+use crate::common::Event;
+
+/// Wrapper function to simplify lambdas
+type BoxedError = Box<dyn Error + Sync + Send>;
+type BoxedResult = Result<(), BoxedError>;
+
+#[tokio::test]
+#[traced_test]
+async fn lifecycle_events_get_emitted() {
+    use tokio_graceful_shutdown::LifecycleEventKind;
+
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(20)).await;
+        BoxedResult::Err("failed".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("failing", failing));
+    });
+    let mut events = toplevel.events();
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_err());
+
+    // The runners emit `Finished` slightly after the subsystems are joined
+    sleep(Duration::from_millis(20)).await;
+
+    let mut received = vec![];
+    while let Ok(event) = events.try_recv() {
+        received.push((event.name.to_string(), event.kind));
+    }
+
+    assert_eq!(
+        received,
+        vec![
+            ("/failing".to_string(), LifecycleEventKind::Started),
+            ("/failing".to_string(), LifecycleEventKind::Failed),
+            ("/failing".to_string(), LifecycleEventKind::Finished),
+            ("/".to_string(), LifecycleEventKind::Finished),
+        ]
+    );
+}
+
+#[tokio::test]
+#[traced_test]
+async fn prestop_hook_finishes_before_subsystems_see_shutdown() {
+    let (prestop_finished, set_prestop_finished) = Event::create();
+    let external_token = tokio_util::sync::CancellationToken::new();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        assert!(prestop_finished.get());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+    })
+    .use_external_shutdown(external_token.clone())
+    .on_prestop(|| async move {
+        sleep(Duration::from_millis(50)).await;
+        set_prestop_finished();
+    });
+
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(50)).await;
+        external_token.cancel();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn prestop_hook_runs_on_subsystem_shutdown_requests() {
+    let (prestop_finished, set_prestop_finished) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        assert!(prestop_finished.get());
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(50)).await;
+        s.request_shutdown();
+        // Merged into the running shutdown
+        s.request_shutdown();
+        assert!(!s.is_shutdown_requested());
+    })
+    .on_prestop(|| async move {
+        sleep(Duration::from_millis(50)).await;
+        set_prestop_finished();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn prestop_hook_runs_on_subsystem_errors() {
+    let (prestop_finished, set_prestop_finished) = Event::create();
+
+    let subsystem = move |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        assert!(prestop_finished.get());
+        BoxedResult::Ok(())
+    };
+    let failing = |_subsys: SubsystemHandle| async move {
+        sleep(Duration::from_millis(50)).await;
+        BoxedResult::Err("failed".into())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        s.start(SubsystemBuilder::new("failing", failing));
+    })
+    .on_prestop(|| async move {
+        sleep(Duration::from_millis(50)).await;
+        set_prestop_finished();
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn shutdown_finished_hook_receives_elapsed_time() {
+    let elapsed = Arc::new(Mutex::new(None));
+
+    let subsystem = |subsys: SubsystemHandle| async move {
+        subsys.on_shutdown_requested().await;
+        sleep(Duration::from_millis(100)).await;
+        BoxedResult::Ok(())
+    };
+
+    let toplevel = Toplevel::new(move |s| async move {
+        s.start(SubsystemBuilder::new("subsys", subsystem));
+        sleep(Duration::from_millis(100)).await;
+        s.request_shutdown();
+    })
+    .on_shutdown_finished({
+        let elapsed = Arc::clone(&elapsed);
+        move |duration| async move {
+            *elapsed.lock().unwrap() = Some(duration);
+        }
+    });
+
+    let result = toplevel
+        .handle_shutdown_requests(Duration::from_millis(400))
+        .await;
+    assert!(result.is_ok());
+
+    // Only the time after the shutdown request counts
+    let elapsed = elapsed.lock().unwrap().unwrap();
+    assert!(elapsed >= Duration::from_millis(100));
+    assert!(elapsed < Duration::from_millis(200));
+}
+
+#[tokio::test]
+#[traced_test]
+async fn span_fields_get_attached_to_subsystem_events() {
+    let subsystem = |_subsys: SubsystemHandle| async move {
+        tracing::info!("Hello from subsystem!");
+        BoxedResult::Ok(())
+    };
+
+    let result = Toplevel::new(move |s| async move {
+        s.start(
+            SubsystemBuilder::new("subsys", subsystem)
+                .with_span_field("component", "db")
+                .with_span_field("tenant", "acme"),
+        );
+    })
+    .handle_shutdown_requests(Duration::from_millis(400))
+    .await;
+    assert!(result.is_ok());
+
+    logs_assert(|lines: &[&str]| {
+        match lines
+            .iter()
+            .find(|line| line.contains("Hello from subsystem!"))
+        {
+            Some(line) if line.contains("subsystem{name=/subsys component=db tenant=acme}") => {
+                Ok(())
+            }
+            Some(line) => Err(format!("Span fields missing: {line}")),
+            None => Err("Message not logged".to_string()),
+        }
+    });
+}